@@ -1,12 +1,15 @@
 #![deny(unsafe_op_in_unsafe_fn)]
 
+use ::provider_core::ScheduleKind;
 use anyhow::{Context, Result};
-use chrono::Utc;
+use chrono::{SubsecRound, Utc};
+use chrono_tz::Tz;
 use greentic_interfaces_guest::component::node::{InvokeResult, NodeError};
 use greentic_interfaces_guest::component_entrypoint;
 use greentic_interfaces_guest::provider_core;
 #[cfg(target_arch = "wasm32")]
 use greentic_interfaces_guest::state_store;
+use provider_timer::Trigger;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 #[cfg(not(target_arch = "wasm32"))]
@@ -64,12 +67,20 @@ struct ProviderConfig {
     default_delay_seconds: Option<u64>,
     #[serde(default)]
     persistence_key_prefix: Option<String>,
+    /// State-store prefix for schedules managed through the `schedule_*` ops.
+    #[serde(default)]
+    schedules_key_prefix: Option<String>,
 }
 
 fn default_timezone() -> String {
     "UTC".into()
 }
 
+fn parse_timezone(name: &str) -> Result<Tz> {
+    name.parse()
+        .map_err(|_| anyhow::anyhow!("unknown timezone {name}"))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 struct TickInput {
     config: ProviderConfig,
@@ -77,6 +88,17 @@ struct TickInput {
     event: Value,
     #[serde(default)]
     handler_id: Option<String>,
+    /// Per-schedule IANA timezone overriding `config.timezone`.
+    #[serde(default)]
+    timezone: Option<String>,
+    /// Delay before the event is due, overriding `config.default_delay_seconds`.
+    #[serde(default)]
+    delay_seconds: Option<u64>,
+    /// RFC 3339 instant the tick was due; retries passing the same value share a receipt id.
+    /// Defaults to the latest instant the `handler_id` schedule was due, or the time of the
+    /// call for handlers without a stored schedule. Truncated to whole seconds.
+    #[serde(default)]
+    scheduled_for: Option<String>,
     #[serde(default)]
     tenant: Option<String>,
     #[serde(default)]
@@ -85,6 +107,76 @@ struct TickInput {
     correlation_id: Option<String>,
 }
 
+/// Input for the `schedule_*` lifecycle ops.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct ScheduleOpInput {
+    #[serde(default)]
+    config: Option<ProviderConfig>,
+    #[serde(default)]
+    tenant: Option<String>,
+    /// Full definition for `schedule_create` and `schedule_update`.
+    #[serde(default)]
+    schedule: Option<ScheduleSpec>,
+    /// Target of `schedule_pause`, `schedule_resume` and `schedule_delete`.
+    #[serde(default)]
+    name: Option<String>,
+}
+
+/// A runtime-managed schedule. The host scheduler evaluates the trigger and invokes
+/// `timer_tick` with `handler_id` set to the schedule name.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct ScheduleSpec {
+    name: String,
+    #[serde(flatten)]
+    trigger: ScheduleTrigger,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timezone: Option<String>,
+    #[serde(default)]
+    event: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ScheduleTrigger {
+    Cron {
+        cron: String,
+    },
+    Interval {
+        every_secs: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        anchor: Option<String>,
+    },
+    Once {
+        at: String,
+    },
+    Delay {
+        delay_secs: u64,
+    },
+}
+
+/// Stored form of a [`ScheduleSpec`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct ScheduleRecord {
+    #[serde(flatten)]
+    spec: ScheduleSpec,
+    paused: bool,
+    revision: u64,
+    created_at: String,
+    updated_at: String,
+}
+
+#[allow(dead_code)]
+const OPS: [&str; 8] = [
+    "timer_tick",
+    "publish",
+    "schedule_create",
+    "schedule_update",
+    "schedule_pause",
+    "schedule_resume",
+    "schedule_delete",
+    "schedule_list",
+];
+
 #[allow(dead_code)]
 struct Component;
 
@@ -93,17 +185,20 @@ impl provider_core::Guest for Component {
         serde_json::to_vec(&json!({
             "provider_type": "events.timer",
             "capabilities": {
-                "operations": ["timer_tick", "publish"],
+                "operations": OPS,
                 "persistence": "state-store",
                 "deterministic": true,
             },
-            "ops": ["timer_tick", "publish"],
+            "ops": OPS,
         }))
         .unwrap_or_default()
     }
 
     fn validate_config(config_json: Vec<u8>) -> Vec<u8> {
-        match serde_json::from_slice::<ProviderConfig>(&config_json) {
+        let parsed = serde_json::from_slice::<ProviderConfig>(&config_json)
+            .map_err(anyhow::Error::from)
+            .and_then(|cfg| parse_timezone(&cfg.timezone).map(|_| cfg));
+        match parsed {
             Ok(cfg) => json!({"valid": true, "config": cfg})
                 .to_string()
                 .into_bytes(),
@@ -179,6 +274,11 @@ mod exports {
 
 #[allow(dead_code)]
 fn handle_invoke(op: &str, input_json: &[u8]) -> Result<Vec<u8>> {
+    if let Some(action) = op.strip_prefix("schedule_") {
+        let parsed: ScheduleOpInput = serde_json::from_slice(input_json)
+            .with_context(|| format!("{op} input is not a valid schedule request"))?;
+        return handle_schedule_op(action, &parsed);
+    }
     let parsed: TickInput = serde_json::from_slice(input_json)
         .with_context(|| "timer_tick input must include config and event")?;
     match op {
@@ -189,22 +289,65 @@ fn handle_invoke(op: &str, input_json: &[u8]) -> Result<Vec<u8>> {
 
 #[allow(dead_code)]
 fn handle_timer_tick(input: &TickInput) -> Result<Vec<u8>> {
-    let receipt_id = stable_receipt_id(&input.event);
+    let timezone = parse_timezone(input.timezone.as_deref().unwrap_or(&input.config.timezone))?;
+    let delay_seconds = input
+        .delay_seconds
+        .or(input.config.default_delay_seconds)
+        .unwrap_or(0);
+    let now = Utc::now();
+    let handler_id = input.handler_id.as_deref().unwrap_or("default");
+    let tenant = input.tenant.as_deref().unwrap_or("default");
+    let record = load_schedule(&input.config, tenant, handler_id)?;
+    if record.as_ref().is_some_and(|record| record.paused) {
+        return Ok(json!({
+            "status": "paused",
+            "emitted_events": [],
+        })
+        .to_string()
+        .into_bytes());
+    }
+    let scheduled_for = match (input.scheduled_for.as_deref(), &record) {
+        (Some(raw), _) => chrono::DateTime::parse_from_rfc3339(raw)
+            .with_context(|| format!("scheduled_for must be RFC 3339, got {raw}"))?
+            .with_timezone(&Utc),
+        (None, Some(record)) => latest_fire(record, timezone, now)?.unwrap_or(now),
+        (None, None) => now,
+    }
+    .trunc_subsecs(0);
+    let fire_at = i64::try_from(delay_seconds)
+        .ok()
+        .and_then(chrono::Duration::try_seconds)
+        .and_then(|delay| scheduled_for.checked_add_signed(delay))
+        .with_context(|| format!("delay of {delay_seconds}s is out of range"))?;
+    let receipt_id = stable_receipt_id(tenant, handler_id, scheduled_for, &input.event);
     let key = state_key(&input.config, &receipt_id);
-    persist_schedule(&key, &input.event)?;
-    let now = Utc::now().to_rfc3339();
+    persist_schedule(
+        &key,
+        ScheduledEntry {
+            event: input.event.clone(),
+            queued_at: queued_at(),
+            timezone: timezone.name().to_string(),
+            default_delay_seconds: input.config.default_delay_seconds,
+            fire_at: fire_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        },
+    )?;
 
     let emitted_event = json!({
         "event_id": receipt_id,
         "event_type": "timer.tick",
-        "occurred_at": now,
+        "occurred_at": now.to_rfc3339(),
+        "occurred_at_local": now.with_timezone(&timezone).to_rfc3339(),
+        "timezone": timezone.name(),
+        "scheduled_for": scheduled_for.to_rfc3339(),
+        "fire_at": fire_at.to_rfc3339(),
+        "delay_seconds": delay_seconds,
         "source": {
             "domain": "events",
             "provider": "events.timer",
-            "handler_id": input.handler_id.clone().unwrap_or_else(|| "default".to_string()),
+            "handler_id": handler_id,
         },
         "scope": {
-            "tenant": input.tenant.clone().unwrap_or_else(|| "default".to_string()),
+            "tenant": tenant,
             "team": input.team,
             "correlation_id": input.correlation_id,
         },
@@ -221,12 +364,252 @@ fn handle_timer_tick(input: &TickInput) -> Result<Vec<u8>> {
     .into_bytes())
 }
 
+fn handle_schedule_op(action: &str, input: &ScheduleOpInput) -> Result<Vec<u8>> {
+    let config = input.config.clone().unwrap_or_else(|| ProviderConfig {
+        timezone: default_timezone(),
+        default_delay_seconds: None,
+        persistence_key_prefix: None,
+        schedules_key_prefix: None,
+    });
+    let tenant = required_tenant(input.tenant.as_deref(), &format!("schedule_{action}"))?;
+    let target = || -> Result<&str> {
+        input
+            .name
+            .as_deref()
+            .or(input.schedule.as_ref().map(|spec| spec.name.as_str()))
+            .context("schedule name is required")
+    };
+    let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+
+    let (status, record) = match action {
+        "list" => {
+            let mut schedules = Vec::new();
+            for name in load_index(&config, tenant)? {
+                if let Some(record) = load_schedule(&config, tenant, &name)? {
+                    schedules.push(record);
+                }
+            }
+            return Ok(json!({"status": "ok", "schedules": schedules})
+                .to_string()
+                .into_bytes());
+        }
+        "create" | "update" => {
+            let spec = input
+                .schedule
+                .clone()
+                .with_context(|| format!("schedule_{action} requires a schedule"))?;
+            validate_schedule(&spec)?;
+            let existing = load_schedule(&config, tenant, &spec.name)?;
+            let record = match (action, existing) {
+                ("create", Some(_)) => anyhow::bail!("schedule {} already exists", spec.name),
+                ("create", None) => ScheduleRecord {
+                    spec,
+                    paused: false,
+                    revision: 1,
+                    created_at: now.clone(),
+                    updated_at: now,
+                },
+                (_, None) => anyhow::bail!("schedule {} not found", spec.name),
+                (_, Some(existing)) => ScheduleRecord {
+                    spec,
+                    revision: existing.revision + 1,
+                    updated_at: now,
+                    ..existing
+                },
+            };
+            (format!("{action}d"), record)
+        }
+        "pause" | "resume" => {
+            let name = target()?;
+            let mut record = load_schedule(&config, tenant, name)?
+                .with_context(|| format!("schedule {name} not found"))?;
+            record.paused = action == "pause";
+            record.revision += 1;
+            record.updated_at = now;
+            (format!("{action}d"), record)
+        }
+        "delete" => {
+            let name = target()?;
+            let record = load_schedule(&config, tenant, name)?
+                .with_context(|| format!("schedule {name} not found"))?;
+            let key = schedule_key(&config, tenant, name);
+            state_delete(&key)?;
+            let mut index = load_index(&config, tenant)?;
+            index.retain(|entry| entry != name);
+            state_write(&index_key(&config, tenant), &serde_json::to_vec(&index)?)?;
+            return Ok(
+                json!({"status": "deleted", "state_key": key, "schedule": record})
+                    .to_string()
+                    .into_bytes(),
+            );
+        }
+        other => anyhow::bail!("unsupported op schedule_{other}"),
+    };
+
+    let key = schedule_key(&config, tenant, &record.spec.name);
+    state_write(&key, &serde_json::to_vec(&record)?)?;
+    let mut index = load_index(&config, tenant)?;
+    if !index.contains(&record.spec.name) {
+        index.push(record.spec.name.clone());
+        index.sort();
+        state_write(&index_key(&config, tenant), &serde_json::to_vec(&index)?)?;
+    }
+    Ok(
+        json!({"status": status, "state_key": key, "schedule": record})
+            .to_string()
+            .into_bytes(),
+    )
+}
+
+fn validate_schedule(spec: &ScheduleSpec) -> Result<()> {
+    let valid_name = !spec.name.is_empty()
+        && spec
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !spec.name.starts_with('.');
+    anyhow::ensure!(
+        valid_name,
+        "schedule name {:?} must be non-empty ASCII letters, digits, '-', '_' or '.'",
+        spec.name
+    );
+    if let Some(timezone) = &spec.timezone {
+        parse_timezone(timezone)?;
+    }
+    match &spec.trigger {
+        ScheduleTrigger::Cron { cron } => {
+            provider_timer::CronExpr::parse(cron)?;
+        }
+        ScheduleTrigger::Interval { every_secs, anchor } => {
+            anyhow::ensure!(*every_secs > 0, "interval must be positive");
+            if let Some(anchor) = anchor {
+                chrono::DateTime::parse_from_rfc3339(anchor)
+                    .with_context(|| format!("anchor must be RFC 3339, got {anchor}"))?;
+            }
+        }
+        ScheduleTrigger::Once { at } => {
+            chrono::DateTime::parse_from_rfc3339(at)
+                .with_context(|| format!("at must be RFC 3339, got {at}"))?;
+        }
+        ScheduleTrigger::Delay { .. } => {}
+    }
+    Ok(())
+}
+
+/// Latest instant at or before `now` that `record` was due, so ticks retried without a
+/// `scheduled_for` still share a receipt id. `timezone` applies when the schedule has none.
+fn latest_fire(
+    record: &ScheduleRecord,
+    timezone: Tz,
+    now: chrono::DateTime<Utc>,
+) -> Result<Option<chrono::DateTime<Utc>>> {
+    let rfc3339 = |raw: &str| -> Result<chrono::DateTime<Utc>> {
+        Ok(chrono::DateTime::parse_from_rfc3339(raw)
+            .with_context(|| format!("stored schedule has an invalid instant {raw}"))?
+            .with_timezone(&Utc))
+    };
+    let created_at = rfc3339(&record.created_at)?;
+    let kind = match &record.spec.trigger {
+        ScheduleTrigger::Cron { cron } => ScheduleKind::Cron { cron: cron.clone() },
+        ScheduleTrigger::Interval { every_secs, anchor } => ScheduleKind::Interval {
+            every_secs: *every_secs,
+            anchor: anchor.as_deref().map(rfc3339).transpose()?,
+        },
+        ScheduleTrigger::Once { at } => ScheduleKind::Once { at: rfc3339(at)? },
+        // Delays count from when the schedule was stored.
+        ScheduleTrigger::Delay { delay_secs } => ScheduleKind::Delay {
+            delay_secs: *delay_secs,
+            anchor: Some(created_at),
+        },
+    };
+    let schedule = ::provider_core::Schedule {
+        name: record.spec.name.clone(),
+        kind,
+        topic: String::new(),
+        payload: Value::Null,
+        payload_template: false,
+        timezone: Some(
+            record
+                .spec
+                .timezone
+                .clone()
+                .unwrap_or_else(|| timezone.name().to_string()),
+        ),
+        dst_policy: Default::default(),
+        catch_up: Default::default(),
+        jitter: None,
+        calendar: None,
+    };
+    Ok(Trigger::compile(&schedule, created_at)?.latest_at_or_before(now))
+}
+
+fn schedules_prefix(config: &ProviderConfig) -> &str {
+    config
+        .schedules_key_prefix
+        .as_deref()
+        .unwrap_or("events/timer/schedules")
+}
+
+fn schedule_key(config: &ProviderConfig, tenant: &str, name: &str) -> String {
+    format!(
+        "{}/{}/{name}.json",
+        schedules_prefix(config),
+        tenant_segment(tenant)
+    )
+}
+
+/// Names of a tenant's schedules; the state store cannot list keys.
+fn index_key(config: &ProviderConfig, tenant: &str) -> String {
+    format!(
+        "{}/{}/index",
+        schedules_prefix(config),
+        tenant_segment(tenant)
+    )
+}
+
+/// `tenant` as one state-key segment: bytes other than ASCII letters, digits, `-` and `_`
+/// are percent-encoded, so a tenant containing `/` or `..` cannot reach another's keys.
+fn tenant_segment(tenant: &str) -> String {
+    tenant
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' => char::from(byte).to_string(),
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+fn required_tenant<'a>(tenant: Option<&'a str>, op: &str) -> Result<&'a str> {
+    tenant
+        .filter(|tenant| !tenant.is_empty())
+        .with_context(|| format!("{op} requires a tenant"))
+}
+
+fn load_index(config: &ProviderConfig, tenant: &str) -> Result<Vec<String>> {
+    match state_read(&index_key(config, tenant))? {
+        Some(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn load_schedule(
+    config: &ProviderConfig,
+    tenant: &str,
+    name: &str,
+) -> Result<Option<ScheduleRecord>> {
+    state_read(&schedule_key(config, tenant, name))?
+        .map(|bytes| serde_json::from_slice(&bytes).context("stored schedule is corrupt"))
+        .transpose()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ScheduledEntry {
     event: Value,
     queued_at: String,
     timezone: String,
     default_delay_seconds: Option<u64>,
+    /// When the event is due: the tick time plus the applicable delay.
+    fire_at: String,
 }
 
 fn state_key(config: &ProviderConfig, receipt_id: &str) -> String {
@@ -237,44 +620,90 @@ fn state_key(config: &ProviderConfig, receipt_id: &str) -> String {
     format!("{prefix}/{receipt_id}.json")
 }
 
-fn stable_receipt_id(event: &Value) -> String {
-    let bytes = serde_json::to_vec(event).unwrap_or_default();
+/// UUIDv5 over tenant, handler, scheduled instant and payload, so a retried tick maps to the
+/// same receipt while identical payloads at different times do not collide.
+fn stable_receipt_id(
+    tenant: &str,
+    handler_id: &str,
+    scheduled_for: chrono::DateTime<Utc>,
+    event: &Value,
+) -> String {
+    let mut bytes = format!(
+        "{tenant}\n{handler_id}\n{}\n",
+        scheduled_for.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    )
+    .into_bytes();
+    bytes.extend(serde_json::to_vec(event).unwrap_or_default());
     Uuid::new_v5(&Uuid::NAMESPACE_OID, &bytes).to_string()
 }
 
-fn persist_schedule(key: &str, event: &Value) -> Result<()> {
+fn queued_at() -> String {
     #[cfg(target_arch = "wasm32")]
     {
-        let entry = ScheduledEntry {
-            event: event.clone(),
-            queued_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
-            timezone: default_timezone(),
-            default_delay_seconds: None,
-        };
-        let bytes = serde_json::to_vec(&entry)?;
-        state_store::write(key, &bytes, None)
+        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        "1970-01-01T00:00:00Z".into()
+    }
+}
+
+fn persist_schedule(key: &str, entry: ScheduledEntry) -> Result<()> {
+    state_write(key, &serde_json::to_vec(&entry)?)
+}
+
+fn state_write(key: &str, bytes: &[u8]) -> Result<()> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        state_store::write(key, bytes, None)
             .map_err(|e| anyhow::anyhow!("state-store write failed: {e:?}"))?;
         Ok(())
     }
 
     #[cfg(not(target_arch = "wasm32"))]
     {
-        let entry = ScheduledEntry {
-            event: event.clone(),
-            queued_at: "1970-01-01T00:00:00Z".into(),
-            timezone: default_timezone(),
-            default_delay_seconds: None,
-        };
-        let bytes = serde_json::to_vec(&entry)?;
         let cache = HOST_STATE.get_or_init(|| Mutex::new(BTreeMap::new()));
         let mut guard = cache.lock().expect("host state mutex poisoned");
-        guard.insert(key.to_string(), bytes);
+        guard.insert(key.to_string(), bytes.to_vec());
+        Ok(())
+    }
+}
+
+fn state_read(key: &str) -> Result<Option<Vec<u8>>> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        match state_store::read(key, None) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.code.replace('_', "-") == "not-found" => Ok(None),
+            Err(e) => Err(anyhow::anyhow!("state-store read failed: {e:?}")),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        Ok(host_read(key))
+    }
+}
+
+fn state_delete(key: &str) -> Result<()> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        state_store::delete(key, None)
+            .map_err(|e| anyhow::anyhow!("state-store delete failed: {e:?}"))?;
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        if let Some(cache) = HOST_STATE.get() {
+            cache.lock().expect("host state mutex poisoned").remove(key);
+        }
         Ok(())
     }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-#[allow(dead_code)]
 fn host_read(key: &str) -> Option<Vec<u8>> {
     HOST_STATE
         .get()
@@ -299,9 +728,13 @@ mod tests {
                 timezone: "UTC".into(),
                 default_delay_seconds: Some(30),
                 persistence_key_prefix: Some("events/timer/scheduled".into()),
+                schedules_key_prefix: None,
             },
             event: json!({"kind": "reminder", "id": 1}),
             handler_id: Some("nightly-reminder".into()),
+            timezone: None,
+            delay_seconds: None,
+            scheduled_for: None,
             tenant: Some("tenant-a".into()),
             team: Some("team-1".into()),
             correlation_id: Some("corr-123".into()),
@@ -311,9 +744,121 @@ mod tests {
     #[test]
     fn receipt_is_deterministic() {
         let input = sample_input();
-        let id1 = stable_receipt_id(&input.event);
-        let id2 = stable_receipt_id(&input.event);
+        let at = |raw| {
+            chrono::DateTime::parse_from_rfc3339(raw)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let ten = at("2024-01-01T10:00:00Z");
+        let id1 = stable_receipt_id("tenant-a", "nightly", ten, &input.event);
+        let id2 = stable_receipt_id("tenant-a", "nightly", ten, &input.event);
         assert_eq!(id1, id2);
+
+        let eleven = at("2024-01-01T11:00:00Z");
+        assert_ne!(
+            id1,
+            stable_receipt_id("tenant-a", "nightly", eleven, &input.event)
+        );
+        assert_ne!(
+            id1,
+            stable_receipt_id("tenant-b", "nightly", ten, &input.event)
+        );
+        assert_ne!(
+            id1,
+            stable_receipt_id("tenant-a", "hourly", ten, &input.event)
+        );
+    }
+
+    #[test]
+    fn retried_ticks_share_a_receipt() {
+        let mut input = sample_input();
+        input.event = json!({"kind": "reminder", "id": 4});
+        input.scheduled_for = Some("2024-01-01T10:00:00+01:00".into());
+        let tick = |input: &TickInput| -> Value {
+            serde_json::from_slice(&handle_timer_tick(input).unwrap()).unwrap()
+        };
+        let first = tick(&input);
+        let retry = tick(&input);
+        assert_eq!(first["receipt_id"], retry["receipt_id"]);
+        assert_eq!(first["state_key"], retry["state_key"]);
+        let emitted = &first["emitted_events"][0];
+        assert_eq!(emitted["scheduled_for"], "2024-01-01T09:00:00+00:00");
+        assert_eq!(emitted["fire_at"], "2024-01-01T09:00:30+00:00");
+
+        input.scheduled_for = Some("2024-01-01T11:00:00+01:00".into());
+        assert_ne!(tick(&input)["receipt_id"], first["receipt_id"]);
+
+        input.scheduled_for = Some("yesterday".into());
+        assert!(handle_timer_tick(&input).is_err());
+    }
+
+    #[test]
+    fn ticks_default_to_the_schedules_latest_due_instant() {
+        let yearly = json!({
+            "name": "new-year", "kind": "cron", "cron": "0 0 1 1 *", "event": {"kind": "greeting"}
+        });
+        schedule_op(
+            "schedule_create",
+            json!({"tenant": "tenant-retry", "schedule": yearly}),
+        )
+        .unwrap();
+        let mut input = sample_input();
+        input.tenant = Some("tenant-retry".into());
+        input.handler_id = Some("new-year".into());
+        let tick =
+            || -> Value { serde_json::from_slice(&handle_timer_tick(&input).unwrap()).unwrap() };
+        let first = tick();
+        let retry = tick();
+        assert_eq!(first["receipt_id"], retry["receipt_id"]);
+        let scheduled_for = first["emitted_events"][0]["scheduled_for"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(
+            scheduled_for.ends_with("-01-01T00:00:00+00:00"),
+            "{scheduled_for}"
+        );
+
+        let at = |raw: &str| {
+            chrono::DateTime::parse_from_rfc3339(raw)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let record = |mut spec: Value| ScheduleRecord {
+            spec: {
+                spec["name"] = json!("s");
+                serde_json::from_value(spec).unwrap()
+            },
+            paused: false,
+            revision: 1,
+            created_at: "2024-01-01T10:00:00Z".into(),
+            updated_at: "2024-01-01T10:00:00Z".into(),
+        };
+        let latest = |trigger: Value, now: &str| {
+            latest_fire(&record(trigger), Tz::UTC, at(now))
+                .unwrap()
+                .map(|due| due.to_rfc3339())
+        };
+        let interval =
+            json!({"kind": "interval", "every_secs": 900, "anchor": "2024-01-01T10:05:00.5Z"});
+        assert_eq!(
+            latest(interval.clone(), "2024-01-01T10:49:00Z").as_deref(),
+            Some("2024-01-01T10:35:00.500+00:00")
+        );
+        assert_eq!(latest(interval, "2024-01-01T10:00:00Z"), None);
+        let delay = json!({"kind": "delay", "delay_secs": 90});
+        assert_eq!(
+            latest(delay.clone(), "2024-01-02T00:00:00Z").as_deref(),
+            Some("2024-01-01T10:01:30+00:00")
+        );
+        assert_eq!(latest(delay, "2024-01-01T10:01:29Z"), None);
+        let once = json!({"kind": "once", "at": "2024-06-01T00:00:00Z"});
+        assert_eq!(latest(once, "2024-05-31T23:59:59Z"), None);
+        let berlin = json!({"kind": "cron", "cron": "0 2 * * *", "timezone": "Europe/Berlin"});
+        assert_eq!(
+            latest(berlin, "2024-01-02T00:59:59Z").as_deref(),
+            Some("2024-01-01T01:00:00+00:00")
+        );
     }
 
     #[test]
@@ -338,6 +883,240 @@ mod tests {
         );
     }
 
+    #[test]
+    fn timer_tick_records_schedule_timezone() {
+        let mut input = sample_input();
+        input.config.timezone = "Europe/Berlin".into();
+        input.event = json!({"kind": "reminder", "id": 2});
+        let out: Value = serde_json::from_slice(&handle_timer_tick(&input).unwrap()).unwrap();
+        let entry: ScheduledEntry =
+            serde_json::from_slice(&host_read(out["state_key"].as_str().unwrap()).unwrap())
+                .unwrap();
+        assert_eq!(entry.timezone, "Europe/Berlin");
+        assert_eq!(entry.default_delay_seconds, Some(30));
+        assert_eq!(out["emitted_events"][0]["timezone"], "Europe/Berlin");
+
+        input.timezone = Some("America/New_York".into());
+        let out: Value = serde_json::from_slice(&handle_timer_tick(&input).unwrap()).unwrap();
+        assert_eq!(out["emitted_events"][0]["timezone"], "America/New_York");
+        let local = out["emitted_events"][0]["occurred_at_local"]
+            .as_str()
+            .unwrap();
+        assert!(
+            local.ends_with("-05:00") || local.ends_with("-04:00"),
+            "{local}"
+        );
+    }
+
+    #[test]
+    fn timer_tick_applies_delay() {
+        let mut input = sample_input();
+        input.event = json!({"kind": "reminder", "id": 3});
+        let out: Value = serde_json::from_slice(&handle_timer_tick(&input).unwrap()).unwrap();
+        let emitted = &out["emitted_events"][0];
+        assert_eq!(emitted["delay_seconds"], 30);
+        let scheduled_for =
+            chrono::DateTime::parse_from_rfc3339(emitted["scheduled_for"].as_str().unwrap())
+                .unwrap();
+        let fire_at =
+            chrono::DateTime::parse_from_rfc3339(emitted["fire_at"].as_str().unwrap()).unwrap();
+        assert_eq!(fire_at - scheduled_for, chrono::Duration::seconds(30));
+        let entry: ScheduledEntry =
+            serde_json::from_slice(&host_read(out["state_key"].as_str().unwrap()).unwrap())
+                .unwrap();
+        assert_eq!(
+            chrono::DateTime::parse_from_rfc3339(&entry.fire_at)
+                .unwrap()
+                .timestamp(),
+            fire_at.timestamp()
+        );
+
+        input.delay_seconds = Some(0);
+        let out: Value = serde_json::from_slice(&handle_timer_tick(&input).unwrap()).unwrap();
+        let emitted = &out["emitted_events"][0];
+        assert_eq!(emitted["fire_at"], emitted["scheduled_for"]);
+    }
+
+    #[test]
+    fn rejects_unknown_timezones() {
+        let mut input = sample_input();
+        input.timezone = Some("Mars/Base".into());
+        let err = handle_timer_tick(&input).unwrap_err();
+        assert!(err.to_string().contains("unknown timezone Mars/Base"));
+
+        let verdict: Value =
+            serde_json::from_slice(&<Component as provider_core::Guest>::validate_config(
+                br#"{"timezone": "Nowhere/City"}"#.to_vec(),
+            ))
+            .unwrap();
+        assert_eq!(verdict["valid"], false);
+    }
+
+    fn schedule_op(op: &str, input: Value) -> Result<Value> {
+        let out = handle_invoke(op, input.to_string().as_bytes())?;
+        Ok(serde_json::from_slice(&out)?)
+    }
+
+    #[test]
+    fn schedule_lifecycle_roundtrips_through_state() {
+        let tenant = "tenant-lifecycle";
+        let nightly = json!({
+            "name": "nightly", "kind": "cron", "cron": "0 2 * * *",
+            "timezone": "Europe/Berlin", "event": {"kind": "report"}
+        });
+        let created = schedule_op(
+            "schedule_create",
+            json!({"tenant": tenant, "schedule": nightly}),
+        )
+        .unwrap();
+        assert_eq!(created["status"], "created");
+        assert_eq!(
+            created["state_key"],
+            "events/timer/schedules/tenant-lifecycle/nightly.json"
+        );
+        assert_eq!(created["schedule"]["revision"], 1);
+        assert!(
+            schedule_op(
+                "schedule_create",
+                json!({"tenant": tenant, "schedule": nightly})
+            )
+            .is_err()
+        );
+
+        let poll = json!({"name": "poll", "kind": "interval", "every_secs": 300});
+        schedule_op(
+            "schedule_create",
+            json!({"tenant": tenant, "schedule": poll}),
+        )
+        .unwrap();
+
+        let updated = schedule_op(
+            "schedule_update",
+            json!({"tenant": tenant, "schedule": {
+                "name": "nightly", "kind": "cron", "cron": "0 3 * * *", "event": {}
+            }}),
+        )
+        .unwrap();
+        assert_eq!(updated["status"], "updated");
+        assert_eq!(updated["schedule"]["cron"], "0 3 * * *");
+        assert_eq!(updated["schedule"]["revision"], 2);
+        assert_eq!(
+            updated["schedule"]["created_at"],
+            created["schedule"]["created_at"]
+        );
+
+        let paused = schedule_op(
+            "schedule_pause",
+            json!({"tenant": tenant, "name": "nightly"}),
+        )
+        .unwrap();
+        assert_eq!(paused["schedule"]["paused"], true);
+
+        let listed = schedule_op("schedule_list", json!({"tenant": tenant})).unwrap();
+        let names: Vec<_> = listed["schedules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| (s["name"].as_str().unwrap(), s["paused"].as_bool().unwrap()))
+            .collect();
+        assert_eq!(names, [("nightly", true), ("poll", false)]);
+
+        let resumed = schedule_op(
+            "schedule_resume",
+            json!({"tenant": tenant, "name": "nightly"}),
+        )
+        .unwrap();
+        assert_eq!(resumed["schedule"]["paused"], false);
+        assert_eq!(resumed["schedule"]["revision"], 4);
+
+        let deleted =
+            schedule_op("schedule_delete", json!({"tenant": tenant, "name": "poll"})).unwrap();
+        assert_eq!(deleted["status"], "deleted");
+        assert!(host_read("events/timer/schedules/tenant-lifecycle/poll.json").is_none());
+        let listed = schedule_op("schedule_list", json!({"tenant": tenant})).unwrap();
+        assert_eq!(listed["schedules"].as_array().unwrap().len(), 1);
+        assert!(schedule_op("schedule_delete", json!({"tenant": tenant, "name": "poll"})).is_err());
+
+        let other = schedule_op("schedule_list", json!({"tenant": "tenant-other"})).unwrap();
+        assert_eq!(other["schedules"], json!([]));
+    }
+
+    #[test]
+    fn schedule_ops_validate_definitions() {
+        let create = |schedule: Value| {
+            schedule_op(
+                "schedule_create",
+                json!({"tenant": "tenant-invalid", "schedule": schedule}),
+            )
+        };
+        assert!(create(json!({"name": "a/b", "kind": "cron", "cron": "@daily"})).is_err());
+        assert!(create(json!({"name": "a", "kind": "cron", "cron": "* *"})).is_err());
+        assert!(create(json!({"name": "a", "kind": "cron", "cron": "61 * * * *"})).is_err());
+        assert!(create(json!({"name": "a", "kind": "cron", "cron": "0 0 30 2 *"})).is_err());
+        assert!(create(json!({"name": "a", "kind": "interval", "every_secs": 0})).is_err());
+        assert!(create(json!({"name": "a", "kind": "once", "at": "soon"})).is_err());
+        assert!(
+            create(json!({"name": "a", "kind": "delay", "delay_secs": 5, "timezone": "Nowhere"}))
+                .is_err()
+        );
+        assert!(create(json!({"name": "a", "kind": "sometimes"})).is_err());
+        assert!(schedule_op("schedule_pause", json!({"tenant": "tenant-invalid"})).is_err());
+        assert!(schedule_op("schedule_rename", json!({})).is_err());
+    }
+
+    #[test]
+    fn schedule_ops_require_and_encode_the_tenant() {
+        let created = schedule_op(
+            "schedule_create",
+            json!({"tenant": "acme/../other", "schedule": {
+                "name": "poll", "kind": "interval", "every_secs": 60
+            }}),
+        )
+        .unwrap();
+        assert_eq!(
+            created["state_key"],
+            "events/timer/schedules/acme%2F%2E%2E%2Fother/poll.json"
+        );
+        let listed = schedule_op("schedule_list", json!({"tenant": "other"})).unwrap();
+        assert_eq!(listed["schedules"], json!([]));
+
+        let err = schedule_op("schedule_list", json!({})).unwrap_err();
+        assert!(err.to_string().contains("requires a tenant"), "{err}");
+        assert!(schedule_op("schedule_list", json!({"tenant": ""})).is_err());
+        // Ticks keep falling back to the `default` tenant.
+        let mut input = sample_input();
+        input.tenant = None;
+        assert!(handle_timer_tick(&input).is_ok());
+    }
+
+    #[test]
+    fn paused_schedules_do_not_tick() {
+        let tenant = "tenant-paused";
+        schedule_op(
+            "schedule_create",
+            json!({"tenant": tenant, "schedule": {
+                "name": "quiet", "kind": "delay", "delay_secs": 60
+            }}),
+        )
+        .unwrap();
+        schedule_op("schedule_pause", json!({"tenant": tenant, "name": "quiet"})).unwrap();
+
+        let mut input = sample_input();
+        input.tenant = Some(tenant.into());
+        input.handler_id = Some("quiet".into());
+        let out: Value = serde_json::from_slice(&handle_timer_tick(&input).unwrap()).unwrap();
+        assert_eq!(out["status"], "paused");
+        assert_eq!(out["emitted_events"], json!([]));
+
+        schedule_op(
+            "schedule_resume",
+            json!({"tenant": tenant, "name": "quiet"}),
+        )
+        .unwrap();
+        let out: Value = serde_json::from_slice(&handle_timer_tick(&input).unwrap()).unwrap();
+        assert_eq!(out["status"], "queued");
+    }
+
     #[test]
     fn pack_builds_with_provider_extension() {
         let pack_root = Path::new(env!("CARGO_MANIFEST_DIR"))
@@ -1,15 +1,25 @@
 #![deny(unsafe_op_in_unsafe_fn)]
 
 use anyhow::{Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
 use chrono::Utc;
 use greentic_interfaces_guest::component::node::{InvokeResult, NodeError};
 use greentic_interfaces_guest::component_entrypoint;
 #[cfg(target_arch = "wasm32")]
-use greentic_interfaces_guest::http_client;
+use greentic_interfaces_guest::http_client_v1_1 as http_client;
 use greentic_interfaces_guest::provider_core;
+#[cfg(target_arch = "wasm32")]
+use greentic_interfaces_guest::state_store;
+use greentic_types::{EnvId, EventEnvelope, EventId, TeamId, TenantCtx, TenantId};
+use provider_webhook::{OutboundEncoding, encode_event};
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
+#[cfg(not(target_arch = "wasm32"))]
+use std::cell::RefCell;
 use std::collections::BTreeMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Mutex, OnceLock};
 use uuid::Uuid;
 
 #[cfg(target_arch = "wasm32")]
@@ -43,13 +53,26 @@ pub fn describe_payload() -> String {
 pub fn handle_message(operation: String, input: String) -> InvokeResult {
     match handle_invoke(&operation, input.as_bytes()) {
         Ok(bytes) => InvokeResult::Ok(String::from_utf8_lossy(&bytes).into_owned()),
-        Err(err) => InvokeResult::Err(NodeError {
+        Err(err) => InvokeResult::Err(node_error(&err)),
+    }
+}
+
+fn node_error(err: &anyhow::Error) -> NodeError {
+    match err.downcast_ref::<DispatchFailure>() {
+        Some(failure) => NodeError {
+            code: failure.code.into(),
+            message: failure.message.clone(),
+            retryable: failure.retryable,
+            backoff_ms: failure.backoff_ms,
+            details: Some(failure.details.to_string()),
+        },
+        None => NodeError {
             code: "invoke_error".into(),
             message: err.to_string(),
             retryable: false,
             backoff_ms: None,
             details: None,
-        }),
+        },
     }
 }
 
@@ -64,6 +87,74 @@ struct ProviderConfig {
     auth: Option<String>,
     #[serde(default)]
     timeout_ms: Option<u64>,
+    /// Query parameters appended to `target_url`; values may reference emitted-event fields
+    /// such as `{{payload.order.id}}` or `{{scope.tenant}}`.
+    #[serde(default)]
+    query: BTreeMap<String, String>,
+    #[serde(default)]
+    encoding: OutputEncoding,
+    #[serde(default)]
+    retry: RetryConfig,
+}
+
+/// Backoff hints for failed dispatches and where exhausted requests are parked.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct RetryConfig {
+    /// Attempts (including the first) before a request is dead-lettered.
+    #[serde(default = "default_max_attempts")]
+    max_attempts: u32,
+    /// Backoff for the first retry; doubles per attempt up to `max_backoff_ms`.
+    #[serde(default = "default_base_backoff_ms")]
+    base_backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    max_backoff_ms: u64,
+    /// State-store key prefix for dead-lettered requests.
+    #[serde(default = "default_dead_letter_prefix")]
+    dead_letter_prefix: String,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            base_backoff_ms: default_base_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            dead_letter_prefix: default_dead_letter_prefix(),
+        }
+    }
+}
+
+fn default_max_attempts() -> u32 {
+    5
+}
+
+fn default_base_backoff_ms() -> u64 {
+    1_000
+}
+
+fn default_max_backoff_ms() -> u64 {
+    300_000
+}
+
+fn default_dead_letter_prefix() -> String {
+    "events-webhook/dead-letter".into()
+}
+
+/// Request body layout for the outbound call.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum OutputEncoding {
+    /// `{"event": <payload>}`.
+    #[default]
+    Event,
+    /// The payload as-is.
+    Payload,
+    /// CloudEvents structured mode (`application/cloudevents+json`).
+    CloudeventsStructured,
+    /// CloudEvents binary mode: attributes in `ce-*` headers, payload as the body.
+    CloudeventsBinary,
+    /// The full emitted event.
+    Envelope,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -73,6 +164,9 @@ struct IngestInput {
     event: Value,
     #[serde(default)]
     handler_id: Option<String>,
+    /// Environment recorded in the emitted scope; defaults to `default`.
+    #[serde(default)]
+    env: Option<String>,
     #[serde(default)]
     tenant: Option<String>,
     #[serde(default)]
@@ -83,12 +177,26 @@ struct IngestInput {
     http: Option<Value>,
     #[serde(default)]
     raw: Option<Value>,
+    /// Base64 of the request body exactly as received; decoded by content type into the
+    /// payload instead of `event` (form callbacks, XML, text, binary).
+    #[serde(default)]
+    raw_body: Option<String>,
+    /// Content type of `raw_body`; falls back to `http.headers["content-type"]`.
+    #[serde(default)]
+    content_type: Option<String>,
+    /// 1-based attempt number; runners increment it when re-invoking after a retryable error.
+    #[serde(default = "first_attempt")]
+    attempt: u32,
 }
 
 fn default_method() -> String {
     "POST".into()
 }
 
+fn first_attempt() -> u32 {
+    1
+}
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 struct Component;
@@ -125,7 +233,17 @@ impl provider_core::Guest for Component {
     fn invoke(op: String, input_json: Vec<u8>) -> Vec<u8> {
         match handle_invoke(&op, &input_json) {
             Ok(res) => res,
-            Err(err) => json!({"error": err.to_string()}).to_string().into_bytes(),
+            Err(err) => {
+                let error = node_error(&err);
+                json!({
+                    "error": error.message,
+                    "code": error.code,
+                    "retryable": error.retryable,
+                    "backoff_ms": error.backoff_ms,
+                })
+                .to_string()
+                .into_bytes()
+            }
         }
     }
 }
@@ -194,48 +312,83 @@ fn handle_invoke(op: &str, input_json: &[u8]) -> Result<Vec<u8>> {
 
 #[allow(dead_code)]
 fn handle_ingest_http(input: &IngestInput) -> Result<Vec<u8>> {
-    let receipt_id = stable_receipt_id(&input.event);
-    let request = build_request(&input.config, &input.event)?;
-    let dispatched = dispatch(&request).is_ok();
-    let now = Utc::now().to_rfc3339();
+    let payload = ingest_payload(input)?;
+    let emitted_event = emitted_event(input, payload);
+    let receipt_id = emitted_event["event_id"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let request = build_request(&input.config, &emitted_event)?;
+    let response = match dispatch(&request) {
+        Some(Ok(resp)) => Some(response_summary(&resp)),
+        Some(Err(failure)) => {
+            return Err(give_up_or_retry(input, &request, &emitted_event, failure)?.into());
+        }
+        None => None,
+    };
+    let dispatched = response.is_some();
+
+    Ok(json!({
+        "receipt_id": receipt_id,
+        "status": if dispatched { "published" } else { "queued" },
+        "dispatched": dispatched,
+        "request": request,
+        "response": response,
+        "emitted_events": [emitted_event],
+    })
+    .to_string()
+    .into_bytes())
+}
 
-    let mut emitted_event = json!({
-        "event_id": receipt_id,
+fn emitted_event(input: &IngestInput, payload: Value) -> Value {
+    let mut event = json!({
+        "event_id": stable_receipt_id(&payload),
         "event_type": "webhook.received",
-        "occurred_at": now,
+        "occurred_at": Utc::now().to_rfc3339(),
         "source": {
             "domain": "events",
             "provider": "events.webhook",
             "handler_id": input.handler_id.clone().unwrap_or_else(|| "default".to_string()),
         },
         "scope": {
+            "env": input.env.clone().unwrap_or_else(|| "default".to_string()),
             "tenant": input.tenant.clone().unwrap_or_else(|| "default".to_string()),
             "team": input.team,
             "correlation_id": input.correlation_id,
         },
-        "payload": input.event,
+        "payload": payload,
     });
     if let Some(http) = &input.http {
-        emitted_event["http"] = http.clone();
+        event["http"] = http.clone();
     }
     if let Some(raw) = &input.raw {
-        emitted_event["raw"] = raw.clone();
+        event["raw"] = raw.clone();
     }
+    event
+}
 
-    Ok(json!({
-        "receipt_id": receipt_id,
-        "status": if dispatched { "published" } else { "queued" },
-        "dispatched": dispatched,
-        "request": request,
-        "response": {
-            "status": if dispatched { 200 } else { 202 },
-            "headers": { "content-type": "application/json" },
-            "body": "accepted"
-        },
-        "emitted_events": [emitted_event],
-    })
-    .to_string()
-    .into_bytes())
+fn ingest_payload(input: &IngestInput) -> Result<Value> {
+    let Some(encoded) = &input.raw_body else {
+        return Ok(input.event.clone());
+    };
+    let raw = STANDARD
+        .decode(encoded)
+        .with_context(|| "raw_body must be base64")?;
+    let content_type = input.content_type.clone().or_else(|| {
+        input
+            .http
+            .as_ref()
+            .and_then(|http| http.get("headers"))
+            .and_then(|headers| headers.as_object())
+            .and_then(|headers| {
+                headers
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+                    .and_then(|(_, value)| value.as_str().map(str::to_string))
+            })
+    });
+    provider_webhook::decode_body(content_type.as_deref(), &raw)
+        .with_context(|| "raw_body could not be decoded")
 }
 
 #[allow(dead_code)]
@@ -244,18 +397,57 @@ struct OutgoingRequest {
     method: String,
     url: String,
     headers: BTreeMap<String, String>,
-    body: Value,
+    /// Absent for methods that carry no body (GET, HEAD, DELETE, OPTIONS).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout_ms: Option<u64>,
 }
 
+/// Upper bound on response body bytes echoed in the output.
+const MAX_RESPONSE_BODY_BYTES: usize = 4096;
+
 #[allow(dead_code)]
 fn build_request(config: &ProviderConfig, event: &Value) -> Result<OutgoingRequest> {
     if config.target_url.trim().is_empty() {
         anyhow::bail!("target_url is required");
     }
+    let method = config.method.to_uppercase();
     let mut headers = config.headers.clone();
-    headers
-        .entry("content-type".into())
-        .or_insert_with(|| "application/json".into());
+    let payload = event.get("payload").cloned().unwrap_or(Value::Null);
+    let body = match config.encoding {
+        OutputEncoding::Event => json!({ "event": payload }),
+        OutputEncoding::Payload => payload,
+        OutputEncoding::Envelope => event.clone(),
+        OutputEncoding::CloudeventsStructured | OutputEncoding::CloudeventsBinary => {
+            let binary = config.encoding == OutputEncoding::CloudeventsBinary;
+            let encoding = if binary {
+                OutboundEncoding::CloudeventsBinary
+            } else {
+                OutboundEncoding::CloudeventsStructured
+            };
+            let encoded = encode_event(encoding, &envelope(event)?)
+                .with_context(|| "event could not be encoded as a CloudEvent")?;
+            for (name, value) in encoded.headers {
+                // Binary mode keeps a configured content type for the payload.
+                if binary && name == "content-type" {
+                    headers.entry(name).or_insert(value);
+                } else {
+                    headers.insert(name, value);
+                }
+            }
+            encoded.body
+        }
+    };
+    let body = if method_has_body(&method) {
+        headers
+            .entry("content-type".into())
+            .or_insert_with(|| "application/json".into());
+        Some(body)
+    } else {
+        headers.remove("content-type");
+        None
+    };
     if let Some(token) = &config.auth {
         headers
             .entry("authorization".into())
@@ -263,10 +455,119 @@ fn build_request(config: &ProviderConfig, event: &Value) -> Result<OutgoingReque
     }
 
     Ok(OutgoingRequest {
-        method: config.method.to_uppercase(),
-        url: config.target_url.clone(),
+        method,
+        url: target_url(config, event)?,
         headers,
-        body: json!({ "event": event }),
+        body,
+        timeout_ms: config.timeout_ms,
+    })
+}
+
+fn method_has_body(method: &str) -> bool {
+    !matches!(method, "GET" | "HEAD" | "DELETE" | "OPTIONS")
+}
+
+/// `target_url` with the rendered `query` parameters appended.
+fn target_url(config: &ProviderConfig, event: &Value) -> Result<String> {
+    if config.query.is_empty() {
+        return Ok(config.target_url.clone());
+    }
+    let mut query = form_urlencoded::Serializer::new(String::new());
+    for (name, template) in &config.query {
+        query.append_pair(name, &render_template(template, event)?);
+    }
+    let separator = if config.target_url.contains('?') {
+        '&'
+    } else {
+        '?'
+    };
+    Ok(format!(
+        "{}{separator}{}",
+        config.target_url,
+        query.finish()
+    ))
+}
+
+/// Replace `{{dotted.path}}` placeholders with values from `event`; missing or null fields
+/// are an error so requests never go out with silently empty parameters.
+fn render_template(template: &str, event: &Value) -> Result<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..]
+            .find("}}")
+            .map(|offset| start + offset)
+            .with_context(|| format!("unclosed placeholder in {template}"))?;
+        let path = rest[start + 2..end].trim();
+        let pointer = format!("/{}", path.replace('.', "/"));
+        let value = match event.pointer(&pointer) {
+            Some(Value::String(text)) => text.clone(),
+            Some(Value::Null) | None => anyhow::bail!("query template references missing {path}"),
+            Some(other) => other.to_string(),
+        };
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(&value);
+        rest = &rest[end + 2..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// Status, headers and a truncated body for the output `response`.
+fn response_summary(resp: &HttpResponse) -> Value {
+    let mut headers = serde_json::Map::new();
+    for (name, value) in &resp.headers {
+        let name = name.to_ascii_lowercase();
+        let joined = match headers.get(&name).and_then(Value::as_str) {
+            Some(existing) => format!("{existing}, {value}"),
+            None => value.clone(),
+        };
+        headers.insert(name, Value::String(joined));
+    }
+    let bytes = resp.body.as_deref().unwrap_or_default();
+    let truncated = bytes.len() > MAX_RESPONSE_BODY_BYTES;
+    let mut body =
+        String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_RESPONSE_BODY_BYTES)]).into_owned();
+    if truncated && body.ends_with(char::REPLACEMENT_CHARACTER) {
+        // Drop a multi-byte character split by the cut.
+        body.pop();
+    }
+    json!({
+        "status": resp.status,
+        "headers": headers,
+        "body": body,
+        "body_truncated": truncated,
+    })
+}
+
+/// The emitted event as an `EventEnvelope`, for [`encode_event`]: the handler becomes the
+/// source and the `handler_id` metadata, the event type doubles as the topic.
+fn envelope(event: &Value) -> Result<EventEnvelope> {
+    let text = |pointer: &str| event.pointer(pointer).and_then(Value::as_str);
+    let handler = text("/source/handler_id").unwrap_or("default");
+    let event_type = text("/event_type").unwrap_or_default();
+    let tenant = TenantCtx::new(
+        EnvId::try_from(text("/scope/env").unwrap_or("default"))?,
+        TenantId::try_from(text("/scope/tenant").unwrap_or("default"))?,
+    )
+    .with_team(text("/scope/team").map(TeamId::try_from).transpose()?);
+    let time = match text("/occurred_at") {
+        Some(time) => chrono::DateTime::parse_from_rfc3339(time)
+            .with_context(|| format!("occurred_at must be RFC 3339, got {time}"))?
+            .with_timezone(&Utc),
+        None => Utc::now(),
+    };
+    Ok(EventEnvelope {
+        id: EventId::new(text("/event_id").unwrap_or_default())?,
+        topic: event_type.to_string(),
+        r#type: event_type.to_string(),
+        source: format!("events.webhook/{handler}"),
+        tenant,
+        subject: None,
+        time,
+        correlation_id: text("/scope/correlation_id").map(str::to_string),
+        payload: event.get("payload").cloned().unwrap_or(Value::Null),
+        metadata: BTreeMap::from([("handler_id".to_string(), handler.to_string())]),
     })
 }
 
@@ -276,39 +577,264 @@ fn stable_receipt_id(event: &Value) -> String {
     Uuid::new_v5(&Uuid::NAMESPACE_OID, &bytes).to_string()
 }
 
+/// Response returned by the host for an outbound request.
+#[derive(Debug, Clone, PartialEq)]
+struct HttpResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Option<Vec<u8>>,
+}
+
+/// Transport error reported by the host (`host-error`).
+#[derive(Debug, Clone, PartialEq)]
+struct SendError {
+    code: String,
+    message: String,
+}
+
+/// Why a dispatch attempt failed, and whether trying again can help.
+#[derive(Debug, Clone, PartialEq)]
+struct AttemptFailure {
+    reason: String,
+    retryable: bool,
+    /// Server-requested delay from `Retry-After`.
+    retry_after_ms: Option<u64>,
+    /// Summary of the error response, when the server answered.
+    response: Option<Value>,
+}
+
+/// Dispatch error surfaced to the runner as a `NodeError`.
+#[derive(Debug, Clone, PartialEq)]
+struct DispatchFailure {
+    code: &'static str,
+    message: String,
+    retryable: bool,
+    backoff_ms: Option<u64>,
+    details: Value,
+}
+
+impl std::fmt::Display for DispatchFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for DispatchFailure {}
+
+/// Send `request` once. Returns `None` when no HTTP transport exists (host builds without a
+/// mock), in which case the request is reported as queued.
 #[allow(dead_code)]
-fn dispatch(request: &OutgoingRequest) -> Result<()> {
-    #[cfg(target_arch = "wasm32")]
-    {
-        let headers: Vec<(String, String)> = request
+fn dispatch(request: &OutgoingRequest) -> Option<Result<HttpResponse, AttemptFailure>> {
+    send(request).map(|result| classify(result, Utc::now()))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn send(request: &OutgoingRequest) -> Option<Result<HttpResponse, SendError>> {
+    let body = match request.body.as_ref().map(serde_json::to_vec).transpose() {
+        Ok(body) => body,
+        Err(err) => {
+            return Some(Err(SendError {
+                code: "invalid-input".into(),
+                message: err.to_string(),
+            }));
+        }
+    };
+    let options = http_client::RequestOptions {
+        timeout_ms: request
+            .timeout_ms
+            .map(|ms| u32::try_from(ms).unwrap_or(u32::MAX)),
+        allow_insecure: None,
+        follow_redirects: None,
+    };
+    let send_req = http_client::Request {
+        method: request.method.clone(),
+        url: request.url.clone(),
+        headers: request
             .headers
             .iter()
             .map(|(k, v)| (k.clone(), v.clone()))
-            .collect();
-        let body = serde_json::to_vec(&request.body)?;
-        let send_req = http_client::Request {
-            method: request.method.clone(),
-            url: request.url.clone(),
-            headers,
-            body: Some(body),
-        };
+            .collect(),
+        body,
+    };
+    Some(
+        http_client::send(&send_req, Some(options), None)
+            .map(|resp| HttpResponse {
+                status: resp.status,
+                headers: resp.headers,
+                body: resp.body,
+            })
+            .map_err(|err| SendError {
+                code: err.code,
+                message: err.message,
+            }),
+    )
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn send(request: &OutgoingRequest) -> Option<Result<HttpResponse, SendError>> {
+    MOCK_SEND.with(|mock| mock.borrow().as_ref().map(|send| send(request)))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type MockSend = Box<dyn Fn(&OutgoingRequest) -> Result<HttpResponse, SendError>>;
+
+#[cfg(not(target_arch = "wasm32"))]
+thread_local! {
+    /// Host-side stand-in for `http_client::send`, installed per test thread.
+    static MOCK_SEND: RefCell<Option<MockSend>> = const { RefCell::new(None) };
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[allow(dead_code)]
+fn set_mock_send(mock: Option<MockSend>) {
+    MOCK_SEND.with(|slot| *slot.borrow_mut() = mock);
+}
 
-        let resp = http_client::send(&send_req, None)
-            .map_err(|err| anyhow::anyhow!("http send failed: {err}"))?;
-        if resp.status >= 200 && resp.status < 400 {
-            return Ok(());
+/// Classify one send result: 2xx/3xx succeed; 5xx, 429 and host timeouts or
+/// unavailability are retryable; everything else is permanent.
+fn classify(
+    result: Result<HttpResponse, SendError>,
+    now: chrono::DateTime<Utc>,
+) -> Result<HttpResponse, AttemptFailure> {
+    match result {
+        Ok(resp) if (200..400).contains(&resp.status) => Ok(resp),
+        Ok(resp) => Err(AttemptFailure {
+            reason: format!("http send returned {}", resp.status),
+            retryable: resp.status == 429 || resp.status >= 500,
+            retry_after_ms: retry_after_ms(&resp.headers, now),
+            response: Some(response_summary(&resp)),
+        }),
+        Err(err) => {
+            let code = err.code.to_ascii_lowercase();
+            Err(AttemptFailure {
+                retryable: matches!(code.as_str(), "timeout" | "rate-limited" | "unavailable")
+                    || err.message.to_ascii_lowercase().contains("timed out"),
+                reason: format!("http send failed: {} ({})", err.message, err.code),
+                retry_after_ms: None,
+                response: None,
+            })
         }
-        anyhow::bail!("http send returned {}", resp.status);
+    }
+}
+
+/// Parse `Retry-After` as delay-seconds or an HTTP-date.
+fn retry_after_ms(headers: &[(String, String)], now: chrono::DateTime<Utc>) -> Option<u64> {
+    let value = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("retry-after"))
+        .map(|(_, value)| value.trim())?;
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(seconds.saturating_mul(1_000));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = at.with_timezone(&Utc) - now;
+    Some(u64::try_from(delay.num_milliseconds()).unwrap_or(0))
+}
+
+/// Exponential backoff for the retry following `attempt`.
+fn backoff_ms(retry: &RetryConfig, attempt: u32) -> u64 {
+    let factor = 1u64
+        .checked_shl(attempt.saturating_sub(1))
+        .unwrap_or(u64::MAX);
+    retry
+        .base_backoff_ms
+        .saturating_mul(factor)
+        .min(retry.max_backoff_ms)
+}
+
+/// Turn a failed attempt into a retry hint, or dead-letter the request once retrying is
+/// pointless or the attempt budget is spent.
+fn give_up_or_retry(
+    input: &IngestInput,
+    request: &OutgoingRequest,
+    event: &Value,
+    failure: AttemptFailure,
+) -> Result<DispatchFailure> {
+    let retry = &input.config.retry;
+    let attempt = input.attempt.max(1);
+    if failure.retryable && attempt < retry.max_attempts {
+        return Ok(DispatchFailure {
+            code: "dispatch_retryable",
+            message: failure.reason,
+            retryable: true,
+            backoff_ms: Some(
+                failure
+                    .retry_after_ms
+                    .unwrap_or_else(|| backoff_ms(retry, attempt)),
+            ),
+            details: json!({
+                "attempt": attempt,
+                "max_attempts": retry.max_attempts,
+                "response": failure.response,
+            }),
+        });
+    }
+
+    // `event_id` is derived from the payload, so identical payloads share it; the random
+    // suffix keeps each dead-lettered delivery in its own entry.
+    let key = format!(
+        "{}/{}/{}/{}",
+        retry.dead_letter_prefix.trim_end_matches('/'),
+        input.tenant.as_deref().unwrap_or("default"),
+        event["event_id"].as_str().unwrap_or_default(),
+        Uuid::new_v4()
+    );
+    persist_dead_letter(
+        &key,
+        &json!({
+            "request": request,
+            "event": event,
+            "attempts": attempt,
+            "error": failure.reason,
+            "response": failure.response,
+            "failed_at": Utc::now().to_rfc3339(),
+        }),
+    )?;
+    Ok(DispatchFailure {
+        code: "dead_lettered",
+        message: format!(
+            "{}; dead-lettered after {attempt} attempt(s)",
+            failure.reason
+        ),
+        retryable: false,
+        backoff_ms: None,
+        details: json!({
+            "dead_letter_key": key,
+            "attempts": attempt,
+            "response": failure.response,
+        }),
+    })
+}
+
+fn persist_dead_letter(key: &str, entry: &Value) -> Result<()> {
+    let bytes = serde_json::to_vec(entry)?;
+    #[cfg(target_arch = "wasm32")]
+    {
+        state_store::write(key, &bytes, None)
+            .map_err(|e| anyhow::anyhow!("state-store write failed: {e:?}"))?;
+        Ok(())
     }
 
     #[cfg(not(target_arch = "wasm32"))]
     {
-        // Host tests run without HTTP; signal as queued/no-op.
-        let _ = request;
-        anyhow::bail!("http client not available on host")
+        let cache = HOST_STATE.get_or_init(|| Mutex::new(BTreeMap::new()));
+        let mut guard = cache.lock().expect("host state mutex poisoned");
+        guard.insert(key.to_string(), bytes);
+        Ok(())
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+#[allow(dead_code)]
+fn host_read(key: &str) -> Option<Vec<u8>> {
+    HOST_STATE
+        .get()
+        .and_then(|lock| lock.lock().ok().and_then(|map| map.get(key).cloned()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+static HOST_STATE: OnceLock<Mutex<BTreeMap<String, Vec<u8>>>> = OnceLock::new();
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -326,21 +852,48 @@ mod tests {
                 headers: BTreeMap::from([("x-test".into(), "1".into())]),
                 auth: Some("token123".into()),
                 timeout_ms: Some(5000),
+                query: BTreeMap::new(),
+                encoding: OutputEncoding::default(),
+                retry: RetryConfig::default(),
             },
             event: json!({"id": 1, "kind": "test"}),
             handler_id: Some("webhook-main".into()),
+            env: Some("dev".into()),
             tenant: Some("tenant-a".into()),
             team: Some("team-1".into()),
             correlation_id: Some("corr-123".into()),
             http: None,
             raw: None,
+            raw_body: None,
+            content_type: None,
+            attempt: 1,
         }
     }
 
+    fn respond(status: u16, headers: &[(&str, &str)]) -> MockSend {
+        let headers: Vec<(String, String)> = headers
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Box::new(move |_| {
+            Ok(HttpResponse {
+                status,
+                headers: headers.clone(),
+                body: None,
+            })
+        })
+    }
+
+    fn dispatch_error(input: &IngestInput) -> NodeError {
+        let err = handle_ingest_http(input).expect_err("dispatch should fail");
+        node_error(&err)
+    }
+
     #[test]
     fn builds_request_with_auth() {
         let input = sample_input();
-        let req = build_request(&input.config, &input.event).expect("build");
+        let event = emitted_event(&input, input.event.clone());
+        let req = build_request(&input.config, &event).expect("build");
         assert_eq!(req.method, "POST");
         assert_eq!(req.url, input.config.target_url);
         assert_eq!(
@@ -349,11 +902,249 @@ mod tests {
         );
         assert_eq!(
             req.body
-                .get("event")
+                .as_ref()
+                .and_then(|b| b.get("event"))
                 .and_then(|v| v.get("id"))
                 .and_then(|v| v.as_i64()),
             Some(1)
         );
+        assert_eq!(req.timeout_ms, Some(5000));
+    }
+
+    #[test]
+    fn builds_request_in_each_encoding() {
+        let mut input = sample_input();
+        let event = emitted_event(&input, input.event.clone());
+        let build = |config: &ProviderConfig| build_request(config, &event).expect("build");
+
+        input.config.encoding = OutputEncoding::Payload;
+        assert_eq!(build(&input.config).body, Some(input.event.clone()));
+
+        input.config.encoding = OutputEncoding::Envelope;
+        let req = build(&input.config);
+        assert_eq!(req.body, Some(event.clone()));
+        assert_eq!(req.headers["content-type"], "application/json");
+
+        input.config.encoding = OutputEncoding::CloudeventsStructured;
+        let req = build(&input.config);
+        assert_eq!(req.headers["content-type"], "application/cloudevents+json");
+        let body = req.body.expect("structured body");
+        assert_eq!(body["specversion"], "1.0");
+        assert_eq!(body["id"], event["event_id"]);
+        assert_eq!(body["type"], "webhook.received");
+        assert_eq!(body["source"], "events.webhook/webhook-main");
+        assert_eq!(body["greentictopic"], "webhook.received");
+        assert_eq!(body["data"], input.event);
+
+        input.config.encoding = OutputEncoding::CloudeventsBinary;
+        input.correlation_id = Some("corr 1".into());
+        let event = emitted_event(&input, input.event.clone());
+        let req = build_request(&input.config, &event).expect("build");
+        assert_eq!(req.headers["content-type"], "application/json");
+        assert_eq!(req.headers["ce-id"], event["event_id"].as_str().unwrap());
+        assert_eq!(req.headers["ce-greenticcorrelation"], "corr%201");
+        assert_eq!(req.body, Some(input.event.clone()));
+
+        let cfg: ProviderConfig = serde_json::from_value(json!({
+            "target_url": "https://example.test/hook",
+            "encoding": "cloudevents_binary"
+        }))
+        .expect("config");
+        assert_eq!(cfg.encoding, OutputEncoding::CloudeventsBinary);
+    }
+
+    #[test]
+    fn cloudevents_decode_with_provider_webhook() {
+        let mut input = sample_input();
+        input.correlation_id = Some("corr 100% \"ünïcode\"".into());
+        let event = emitted_event(&input, input.event.clone());
+        for encoding in [
+            OutputEncoding::CloudeventsStructured,
+            OutputEncoding::CloudeventsBinary,
+        ] {
+            input.config.encoding = encoding;
+            let req = build_request(&input.config, &event).expect("build");
+            let decoded =
+                provider_webhook::decode_cloudevent(&req.headers, &req.body.expect("body"))
+                    .expect("decode");
+            assert_eq!(decoded.id.as_str(), event["event_id"].as_str().unwrap());
+            assert_eq!(decoded.topic, "webhook.received");
+            assert_eq!(decoded.r#type, "webhook.received");
+            assert_eq!(decoded.source, "events.webhook/webhook-main");
+            assert_eq!(decoded.tenant.env.as_str(), "dev");
+            assert_eq!(decoded.tenant.tenant.as_str(), "tenant-a");
+            assert_eq!(
+                decoded.tenant.team.as_ref().map(|team| team.as_str()),
+                Some("team-1")
+            );
+            assert_eq!(decoded.correlation_id, input.correlation_id);
+            assert_eq!(decoded.metadata["handler_id"], "webhook-main");
+            assert_eq!(decoded.payload, input.event);
+        }
+    }
+
+    #[test]
+    fn publishes_and_reports_real_response() {
+        set_mock_send(Some(Box::new(|_| {
+            Ok(HttpResponse {
+                status: 201,
+                headers: vec![
+                    ("Content-Type".into(), "text/plain".into()),
+                    ("Set-Cookie".into(), "a=1".into()),
+                    ("set-cookie".into(), "b=2".into()),
+                ],
+                body: Some("é".repeat(MAX_RESPONSE_BODY_BYTES).into_bytes()),
+            })
+        })));
+        let out = handle_ingest_http(&sample_input()).expect("ingest_http");
+        let json: Value = serde_json::from_slice(&out).expect("json");
+        assert_eq!(json["status"], "published");
+        assert_eq!(json["dispatched"], true);
+        let response = &json["response"];
+        assert_eq!(response["status"], 201);
+        assert_eq!(response["headers"]["content-type"], "text/plain");
+        assert_eq!(response["headers"]["set-cookie"], "a=1, b=2");
+        assert_eq!(response["body_truncated"], true);
+        let body = response["body"].as_str().unwrap();
+        assert_eq!(body, "é".repeat(MAX_RESPONSE_BODY_BYTES / 2));
+    }
+
+    #[test]
+    fn queued_output_has_no_fake_response() {
+        let out = handle_ingest_http(&sample_input()).expect("ingest_http");
+        let json: Value = serde_json::from_slice(&out).expect("json");
+        assert_eq!(json["status"], "queued");
+        assert_eq!(json["response"], Value::Null);
+    }
+
+    #[test]
+    fn bodyless_methods_send_no_body() {
+        let mut input = sample_input();
+        for method in ["get", "DELETE"] {
+            input.config.method = method.into();
+            let event = emitted_event(&input, input.event.clone());
+            let req = build_request(&input.config, &event).expect("build");
+            assert_eq!(req.method, method.to_uppercase());
+            assert_eq!(req.body, None);
+            assert!(!req.headers.contains_key("content-type"));
+            let serialized = serde_json::to_value(&req).unwrap();
+            assert!(serialized.get("body").is_none());
+        }
+        input.config.method = "put".into();
+        let event = emitted_event(&input, input.event.clone());
+        let req = build_request(&input.config, &event).expect("build");
+        assert!(req.body.is_some());
+    }
+
+    #[test]
+    fn renders_query_params_from_event() {
+        let mut input = sample_input();
+        input.config.method = "GET".into();
+        input.config.target_url = "https://example.test/hook?static=1".into();
+        input.config.query = BTreeMap::from([
+            ("kind".into(), "{{payload.kind}}".into()),
+            ("ref".into(), "{{scope.tenant}}/{{payload.id}}".into()),
+        ]);
+        let event = emitted_event(&input, input.event.clone());
+        let req = build_request(&input.config, &event).expect("build");
+        assert_eq!(
+            req.url,
+            "https://example.test/hook?static=1&kind=test&ref=tenant-a%2F1"
+        );
+
+        input.config.query = BTreeMap::from([("x".into(), "{{payload.missing}}".into())]);
+        assert!(build_request(&input.config, &event).is_err());
+        input.config.query = BTreeMap::from([("x".into(), "{{payload.id".into())]);
+        assert!(build_request(&input.config, &event).is_err());
+    }
+
+    #[test]
+    fn server_errors_are_retryable_with_exponential_backoff() {
+        set_mock_send(Some(respond(503, &[])));
+        let mut input = sample_input();
+        let err = dispatch_error(&input);
+        assert_eq!(err.code, "dispatch_retryable");
+        assert!(err.retryable);
+        assert_eq!(err.backoff_ms, Some(1_000));
+
+        input.attempt = 3;
+        assert_eq!(dispatch_error(&input).backoff_ms, Some(4_000));
+
+        input.config.retry.max_backoff_ms = 2_500;
+        assert_eq!(dispatch_error(&input).backoff_ms, Some(2_500));
+    }
+
+    #[test]
+    fn rate_limits_honor_retry_after() {
+        set_mock_send(Some(respond(429, &[("Retry-After", "120")])));
+        let err = dispatch_error(&sample_input());
+        assert!(err.retryable);
+        assert_eq!(err.backoff_ms, Some(120_000));
+
+        let now = chrono::DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let headers = vec![(
+            "retry-after".to_string(),
+            "Wed, 21 Oct 2015 07:28:30 GMT".to_string(),
+        )];
+        assert_eq!(retry_after_ms(&headers, now), Some(30_000));
+    }
+
+    #[test]
+    fn host_timeouts_are_retryable() {
+        set_mock_send(Some(Box::new(|_| {
+            Err(SendError {
+                code: "timeout".into(),
+                message: "deadline exceeded".into(),
+            })
+        })));
+        let err = dispatch_error(&sample_input());
+        assert!(err.retryable);
+        assert_eq!(err.code, "dispatch_retryable");
+    }
+
+    #[test]
+    fn dead_letters_after_max_attempts() {
+        set_mock_send(Some(respond(500, &[])));
+        let mut input = sample_input();
+        input.event = json!({"id": "dead-letter-exhausted"});
+        input.attempt = input.config.retry.max_attempts;
+        let err = dispatch_error(&input);
+        assert_eq!(err.code, "dead_lettered");
+        assert!(!err.retryable);
+        assert_eq!(err.backoff_ms, None);
+
+        let details: Value = serde_json::from_str(err.details.as_deref().unwrap()).unwrap();
+        let key = details["dead_letter_key"].as_str().expect("key");
+        assert!(key.starts_with("events-webhook/dead-letter/tenant-a/"));
+        let entry: Value = serde_json::from_slice(&host_read(key).expect("stored")).unwrap();
+        assert_eq!(entry["attempts"], 5);
+        assert_eq!(entry["response"]["status"], 500);
+        assert_eq!(entry["request"]["url"], "https://example.test/hook");
+        assert_eq!(entry["event"]["payload"], input.event);
+    }
+
+    #[test]
+    fn client_errors_dead_letter_immediately() {
+        set_mock_send(Some(respond(400, &[])));
+        let mut input = sample_input();
+        input.event = json!({"id": "dead-letter-client-error"});
+        let err = dispatch_error(&input);
+        assert_eq!(err.code, "dead_lettered");
+        assert!(err.message.contains("returned 400"));
+
+        let again = dispatch_error(&input);
+        let key = |err: &NodeError| {
+            let details: Value = serde_json::from_str(err.details.as_deref().unwrap()).unwrap();
+            details["dead_letter_key"].as_str().unwrap().to_string()
+        };
+        let (first, second) = (key(&err), key(&again));
+        assert_ne!(
+            first, second,
+            "identical payloads must not overwrite each other"
+        );
+        assert!(host_read(&first).is_some() && host_read(&second).is_some());
     }
 
     #[test]
@@ -387,6 +1178,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ingest_decodes_raw_form_body_into_payload() {
+        let mut input = sample_input();
+        input.event = Value::Null;
+        input.raw_body = Some(STANDARD.encode("From=%2B15551234567&Body=Hi+there"));
+        input.http = Some(json!({
+            "headers": {"Content-Type": "application/x-www-form-urlencoded"}
+        }));
+        let out = handle_ingest_http(&input).expect("ingest_http");
+        let json: Value = serde_json::from_slice(&out).expect("json");
+        let payload = json
+            .get("emitted_events")
+            .and_then(|v| v.as_array())
+            .and_then(|arr| arr.first())
+            .and_then(|v| v.get("payload"))
+            .cloned();
+        assert_eq!(
+            payload,
+            Some(json!({"From": "+15551234567", "Body": "Hi there"}))
+        );
+    }
+
+    #[test]
+    fn ingest_decodes_text_and_binary_bodies() {
+        let payload = |content_type: &str, raw: &[u8]| {
+            let mut input = sample_input();
+            input.raw_body = Some(STANDARD.encode(raw));
+            input.content_type = Some(content_type.into());
+            ingest_payload(&input)
+        };
+        assert_eq!(
+            payload("application/xml", b"<ok/>").expect("xml"),
+            json!("<ok/>")
+        );
+        assert_eq!(
+            payload("image/png", &[0x89, 0x50]).expect("binary"),
+            json!({"encoding": "base64", "content_type": "image/png", "data": "iVA="})
+        );
+        assert!(payload("application/json", b"{").is_err());
+    }
+
     #[test]
     fn pack_builds_with_provider_extension() {
         let pack_root = Path::new(env!("CARGO_MANIFEST_DIR"))
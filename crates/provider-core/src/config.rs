@@ -1,4 +1,5 @@
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 
 /// Shared HTTP endpoint configuration for webhook-style providers.
@@ -11,14 +12,156 @@ pub struct HttpEndpointConfig {
 }
 
 /// Per-route configuration for webhook providers.
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub struct WebhookRoute {
-    /// Route path relative to base (e.g. "/stripe").
+    /// Route path relative to base (e.g. "/stripe"). Segments may be `{param}` captures and
+    /// the last segment may be `*` (e.g. "/{tenant}/{team}/{handler}", "/github/*").
     pub path: String,
     /// Optional reference to a secret containing a signing key.
     pub secret_ref: Option<String>,
     /// Topic prefix to apply when emitting events (e.g. "webhook.stripe").
+    /// May reference path captures, e.g. "webhook.{handler}".
     pub topic_prefix: String,
+    /// HTTP methods accepted by the route (case-insensitive); empty accepts any method.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<String>,
+    /// How the event type appended to `topic_prefix` is derived.
+    /// Defaults to the payload's top-level `type`, falling back to "received".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic_rule: Option<TopicRule>,
+    /// Replay protection; requires the host to supply a dedup store.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replay: Option<ReplayProtection>,
+    /// Signature verification performed in-component with the `secret_ref` key.
+    /// When absent, the host-supplied `signature_validated` flag is trusted instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<SignatureScheme>,
+}
+
+/// Event-type derivation for webhook topics (`<topic_prefix>.<event_type>`).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TopicRule {
+    /// Sources tried in order; the first yielding a non-empty value wins.
+    #[serde(default)]
+    pub sources: Vec<EventTypeSource>,
+    /// Event type used when no source yields a value.
+    #[serde(default = "default_event_type")]
+    pub fallback: String,
+}
+
+impl Default for TopicRule {
+    fn default() -> Self {
+        Self {
+            sources: vec![EventTypeSource::Pointer("/type".into())],
+            fallback: default_event_type(),
+        }
+    }
+}
+
+fn default_event_type() -> String {
+    "received".into()
+}
+
+/// Where a webhook event type is read from.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventTypeSource {
+    /// JSON pointer into the decoded payload (e.g. "/type", "/event/type").
+    Pointer(String),
+    /// Request header, matched case-insensitively (e.g. "X-GitHub-Event").
+    Header(String),
+    /// Fixed event type.
+    Constant(String),
+}
+
+/// Replay protection for a webhook route.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReplayProtection {
+    /// Seconds a delivery key is remembered by the dedup store.
+    #[serde(default = "default_dedup_ttl_secs")]
+    pub ttl_secs: u64,
+    /// Header carrying a unique delivery id (e.g. "X-GitHub-Delivery"). Falls back to
+    /// `Idempotency-Key`, then to a SHA-256 digest of the body. Not allowed on routes with
+    /// a `signature`, which always dedup on the digest of the verified body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_header: Option<String>,
+    /// Maximum skew between a signed timestamp (Stripe, Slack) and now. Keep this below
+    /// `ttl_secs` so replays cannot outlive the dedup window.
+    #[serde(default = "default_signature_tolerance_secs")]
+    pub max_skew_secs: u64,
+}
+
+impl Default for ReplayProtection {
+    fn default() -> Self {
+        Self {
+            ttl_secs: default_dedup_ttl_secs(),
+            key_header: None,
+            max_skew_secs: default_signature_tolerance_secs(),
+        }
+    }
+}
+
+fn default_dedup_ttl_secs() -> u64 {
+    86_400
+}
+
+/// How inbound webhook requests are signed.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "scheme", rename_all = "snake_case")]
+pub enum SignatureScheme {
+    /// HMAC digest of the raw body carried in a single header.
+    Hmac(HmacSignature),
+    /// Stripe `Stripe-Signature: t=<unix>,v1=<hex>` over `"<t>.<body>"`.
+    Stripe {
+        #[serde(default = "default_signature_tolerance_secs")]
+        tolerance_secs: u64,
+    },
+    /// GitHub `X-Hub-Signature-256: sha256=<hex>` over the body.
+    Github,
+    /// Slack `X-Slack-Signature: v0=<hex>` over `"v0:<X-Slack-Request-Timestamp>:<body>"`.
+    Slack {
+        #[serde(default = "default_signature_tolerance_secs")]
+        tolerance_secs: u64,
+    },
+    /// Shopify `X-Shopify-Hmac-Sha256: <base64>` over the body.
+    Shopify,
+}
+
+/// Maximum age (seconds) of a signed timestamp before the request is rejected.
+pub fn default_signature_tolerance_secs() -> u64 {
+    300
+}
+
+/// Generic HMAC signature settings.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HmacSignature {
+    /// Header carrying the digest (matched case-insensitively, e.g. "x-signature").
+    pub header: String,
+    #[serde(default)]
+    pub algorithm: HmacAlgorithm,
+    #[serde(default)]
+    pub encoding: DigestEncoding,
+    /// Optional prefix stripped from the header value before decoding (e.g. "sha256=").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+}
+
+/// Hash function used for HMAC signatures.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HmacAlgorithm {
+    #[default]
+    Sha256,
+    Sha1,
+}
+
+/// Text encoding of a digest inside a header.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestEncoding {
+    #[default]
+    Hex,
+    Base64,
 }
 
 /// Timer/scheduler configuration shared by timer providers.
@@ -32,10 +175,187 @@ pub struct SchedulerConfig {
 pub struct Schedule {
     /// Logical name of the schedule (e.g. "daily-report").
     pub name: String,
-    /// Cron or interval expression supplied by deployer/host.
-    pub cron: String,
+    /// When the schedule fires, tagged by `kind`.
+    #[serde(flatten)]
+    pub kind: ScheduleKind,
     /// Topic to emit when the schedule fires (e.g. "timer.daily.report").
     pub topic: String,
     /// JSON payload that will be emitted with the event.
     pub payload: Value,
+    /// Render the payload's strings as handlebars templates on every fire. Off by default,
+    /// so payloads containing a literal `{{` are emitted unchanged.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub payload_template: bool,
+    /// IANA timezone cron expressions are evaluated in; UTC when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// How fire times falling into a DST gap or overlap are handled.
+    #[serde(default)]
+    pub dst_policy: DstPolicy,
+    /// Which fires missed while the host was down are replayed.
+    #[serde(default)]
+    pub catch_up: CatchUp,
+    /// Per-tenant offset that keeps tenants sharing a trigger from firing at once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jitter: Option<Jitter>,
+    /// Days, in the schedule's timezone, the schedule may or may not fire on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calendar: Option<BusinessCalendar>,
+}
+
+/// Replay of fires missed between the last fire and now.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CatchUp {
+    #[serde(default)]
+    pub policy: CatchUpPolicy,
+    /// Upper bound on replayed fires; the most recent ones are kept.
+    #[serde(default = "default_max_catch_up")]
+    pub max_fires: u32,
+}
+
+impl Default for CatchUp {
+    fn default() -> Self {
+        Self {
+            policy: CatchUpPolicy::default(),
+            max_fires: default_max_catch_up(),
+        }
+    }
+}
+
+fn default_max_catch_up() -> u32 {
+    100
+}
+
+/// Business calendar of a [`Schedule`]: when `include` is non-empty fires land only on days
+/// one of its calendars contains; days any `exclude` calendar contains never fire.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BusinessCalendar {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<CalendarSource>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<CalendarSource>,
+}
+
+/// Days a [`BusinessCalendar`] entry contains.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarSource {
+    /// Inline `YYYY-MM-DD` dates.
+    Dates(Vec<NaiveDate>),
+    /// An iCalendar (RFC 5545) document; every day a `VEVENT` occurrence (`RRULE`, `RDATE`
+    /// and `EXDATE` applied) covers.
+    Ical(String),
+}
+
+/// Deterministic delay of each fire by up to `window_secs`, seeded by the tenant key.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Jitter {
+    /// Offsets fall in `[0, window_secs)`.
+    pub window_secs: u64,
+    #[serde(default)]
+    pub mode: JitterMode,
+}
+
+/// How [`Jitter`] picks a fire's offset.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JitterMode {
+    /// A fresh offset for every fire, derived from the tenant, schedule and fire time.
+    #[default]
+    Random,
+    /// One fixed offset per tenant and schedule, spreading tenants across the window.
+    Spread,
+}
+
+/// Which missed fires [`CatchUp`] replays.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUpPolicy {
+    /// Drop missed fires.
+    #[default]
+    Skip,
+    /// Replay only the most recent missed fire.
+    LatestOnly,
+    /// Replay every missed fire, up to `max_fires`.
+    FireAll,
+}
+
+/// Trigger of a [`Schedule`]. Serialized inline with a `kind` tag; entries without `kind`
+/// but with a `cron` field are read as cron schedules.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(remote = "Self", tag = "kind", rename_all = "snake_case")]
+pub enum ScheduleKind {
+    /// Cron expression (5/6 fields or an `@` macro).
+    Cron { cron: String },
+    /// Every `every_secs` seconds, aligned to `anchor` (the Unix epoch when unset).
+    Interval {
+        every_secs: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        anchor: Option<DateTime<Utc>>,
+    },
+    /// Once, at a fixed instant.
+    Once { at: DateTime<Utc> },
+    /// Once, `delay_secs` after `anchor`. `TimerSource` pins an unset anchor to the time the
+    /// schedule is loaded and keeps it in its config, so a serialized and reloaded source
+    /// fires at the same instant.
+    Delay {
+        delay_secs: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        anchor: Option<DateTime<Utc>>,
+    },
+    /// iCalendar (RFC 5545) recurrence rule such as `FREQ=MONTHLY;BYDAY=2TU`, expanded from
+    /// `dtstart` on the schedule's local clock. `UNTIL` and `COUNT` go in the rule; `exdate`
+    /// lists local occurrences to drop.
+    Rrule {
+        rrule: String,
+        dtstart: NaiveDateTime,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        exdate: Vec<NaiveDateTime>,
+    },
+}
+
+impl ScheduleKind {
+    /// The `kind` tag of this trigger.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScheduleKind::Cron { .. } => "cron",
+            ScheduleKind::Interval { .. } => "interval",
+            ScheduleKind::Once { .. } => "once",
+            ScheduleKind::Delay { .. } => "delay",
+            ScheduleKind::Rrule { .. } => "rrule",
+        }
+    }
+}
+
+impl Serialize for ScheduleKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ScheduleKind::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ScheduleKind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = Value::deserialize(deserializer)?;
+        if let Value::Object(fields) = &mut value
+            && !fields.contains_key("kind")
+            && fields.contains_key("cron")
+        {
+            fields.insert("kind".into(), "cron".into());
+        }
+        ScheduleKind::deserialize(value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Handling of local fire times that a DST transition skips (gap) or repeats (overlap).
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DstPolicy {
+    /// Times in a gap do not fire; repeated times fire on their first occurrence only.
+    Skip,
+    /// Times in a gap fire once when the gap ends; repeated times fire on their first
+    /// occurrence only.
+    #[default]
+    FireOnce,
+    /// Like `fire_once` for gaps; repeated times fire on both occurrences.
+    FireTwice,
 }
@@ -0,0 +1,55 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Interface for remembering recently processed keys so providers can drop replays.
+/// Hosts back this with their state store; tests can use the in-memory implementation.
+pub trait DedupStore {
+    /// Record `key` as seen at `now` for `ttl_secs`.
+    ///
+    /// Returns `true` when the key is new (or its previous window expired) and `false` when it
+    /// was already recorded within the window.
+    fn check_and_record(
+        &self,
+        key: &str,
+        now: DateTime<Utc>,
+        ttl_secs: u64,
+    ) -> Result<bool, crate::ProviderError>;
+}
+
+/// In-memory store for tests/fixtures.
+#[derive(Default)]
+pub struct InMemoryDedupStore {
+    expires_at: Mutex<BTreeMap<String, DateTime<Utc>>>,
+}
+
+impl InMemoryDedupStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl DedupStore for InMemoryDedupStore {
+    fn check_and_record(
+        &self,
+        key: &str,
+        now: DateTime<Utc>,
+        ttl_secs: u64,
+    ) -> Result<bool, crate::ProviderError> {
+        let mut seen = self
+            .expires_at
+            .lock()
+            .map_err(|_| crate::ProviderError::Other("dedup store mutex poisoned".into()))?;
+        seen.retain(|_, expiry| *expiry > now);
+        if seen.contains_key(key) {
+            return Ok(false);
+        }
+        let expiry = i64::try_from(ttl_secs)
+            .ok()
+            .and_then(Duration::try_seconds)
+            .and_then(|ttl| now.checked_add_signed(ttl))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        seen.insert(key.to_string(), expiry);
+        Ok(true)
+    }
+}
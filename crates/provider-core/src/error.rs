@@ -6,6 +6,8 @@ pub enum ProviderError {
     Config(String),
     #[error("authentication error: {0}")]
     Auth(String),
+    #[error("signature verification failed: {0}")]
+    Signature(String),
     #[error("transport error: {0}")]
     Transport(String),
     #[error("unexpected error: {0}")]
@@ -1,10 +1,17 @@
 pub mod config;
+pub mod dedup;
 pub mod error;
 pub mod events;
 pub mod secrets;
 pub mod tenant_secrets;
 
-pub use config::{HttpEndpointConfig, Schedule, SchedulerConfig, WebhookRoute};
+pub use config::{
+    BusinessCalendar, CalendarSource, CatchUp, CatchUpPolicy, DigestEncoding, DstPolicy,
+    EventTypeSource, HmacAlgorithm, HmacSignature, HttpEndpointConfig, Jitter, JitterMode,
+    ReplayProtection, Schedule, ScheduleKind, SchedulerConfig, SignatureScheme, TopicRule,
+    WebhookRoute,
+};
+pub use dedup::{DedupStore, InMemoryDedupStore};
 pub use error::ProviderError;
 pub use events::{new_event, set_idempotency_key};
 pub use tenant_secrets::{events_provider_secret_key, tenant_key};
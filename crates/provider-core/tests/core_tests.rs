@@ -1,5 +1,9 @@
+use chrono::{DateTime, Duration, NaiveDate};
 use provider_core::{
-    HttpEndpointConfig, Schedule, SchedulerConfig, WebhookRoute, new_event, set_idempotency_key,
+    BusinessCalendar, CalendarSource, DedupStore, DigestEncoding, DstPolicy, EventTypeSource,
+    HmacAlgorithm, HmacSignature, HttpEndpointConfig, InMemoryDedupStore, Jitter, JitterMode,
+    ReplayProtection, Schedule, ScheduleKind, SchedulerConfig, SignatureScheme, TopicRule,
+    WebhookRoute, new_event, set_idempotency_key,
 };
 use serde_json::json;
 use std::collections::BTreeMap;
@@ -22,11 +26,20 @@ fn http_endpoint_config_roundtrip() {
                 path: "/stripe".into(),
                 secret_ref: Some("events/webhook/dev/acme/stripe".into()),
                 topic_prefix: "webhook.stripe".into(),
+                methods: vec!["POST".into()],
+                signature: Some(SignatureScheme::Hmac(HmacSignature {
+                    header: "x-signature".into(),
+                    algorithm: HmacAlgorithm::Sha1,
+                    encoding: DigestEncoding::Base64,
+                    prefix: None,
+                })),
+                ..Default::default()
             },
             WebhookRoute {
                 path: "/github".into(),
                 secret_ref: None,
                 topic_prefix: "webhook.github".into(),
+                ..Default::default()
             },
         ],
     };
@@ -34,6 +47,121 @@ fn http_endpoint_config_roundtrip() {
     let value = serde_json::to_string(&cfg).expect("serialize");
     let roundtrip: HttpEndpointConfig = serde_json::from_str(&value).expect("deserialize");
     assert_eq!(cfg, roundtrip);
+
+    let legacy: Schedule = serde_json::from_value(json!({
+        "name": "daily", "cron": "@daily", "topic": "timer.daily", "payload": {}
+    }))
+    .expect("deserialize legacy schedule");
+    assert_eq!(
+        legacy.kind,
+        ScheduleKind::Cron {
+            cron: "@daily".into()
+        }
+    );
+    assert_eq!(legacy.timezone, None);
+    assert_eq!(legacy.dst_policy, DstPolicy::FireOnce);
+}
+
+#[test]
+fn schedule_kinds_are_tagged_inline() {
+    let once: Schedule = serde_json::from_value(json!({
+        "name": "launch", "kind": "once", "at": "2030-01-01T09:00:00+01:00",
+        "topic": "timer.launch", "payload": {}
+    }))
+    .expect("deserialize once");
+    assert_eq!(
+        once.kind,
+        ScheduleKind::Once {
+            at: DateTime::from_timestamp(1_893_484_800, 0).unwrap()
+        }
+    );
+    assert_eq!(once.kind.name(), "once");
+
+    let rrule: Schedule = serde_json::from_value(json!({
+        "name": "board", "kind": "rrule", "rrule": "FREQ=MONTHLY;BYDAY=2TU",
+        "dtstart": "2024-01-09T10:00:00", "exdate": ["2024-08-13T10:00:00"],
+        "topic": "timer.board", "payload": {}
+    }))
+    .expect("deserialize rrule");
+    assert_eq!(rrule.kind.name(), "rrule");
+    assert!(matches!(&rrule.kind, ScheduleKind::Rrule { exdate, .. } if exdate.len() == 1));
+    let value = serde_json::to_value(&rrule).expect("serialize rrule");
+    assert_eq!(value["dtstart"], "2024-01-09T10:00:00");
+
+    let delay = serde_json::to_value(Schedule {
+        name: "later".into(),
+        kind: ScheduleKind::Delay {
+            delay_secs: 90,
+            anchor: None,
+        },
+        topic: "timer.later".into(),
+        payload: json!({}),
+        payload_template: false,
+        timezone: None,
+        dst_policy: DstPolicy::default(),
+        catch_up: Default::default(),
+        jitter: None,
+        calendar: None,
+    })
+    .expect("serialize delay");
+    assert_eq!(delay["kind"], "delay");
+    assert_eq!(delay["delay_secs"], 90);
+    assert!(delay.get("timezone").is_none());
+
+    let unknown = serde_json::from_value::<Schedule>(json!({
+        "name": "x", "kind": "sometimes", "topic": "t", "payload": {}
+    }));
+    assert!(unknown.is_err());
+    let missing = serde_json::from_value::<Schedule>(json!({
+        "name": "x", "kind": "interval", "topic": "t", "payload": {}
+    }));
+    assert!(missing.is_err());
+}
+
+#[test]
+fn webhook_route_signature_defaults() {
+    let route: WebhookRoute = serde_json::from_value(json!({
+        "path": "/custom",
+        "secret_ref": "WEBHOOK_SIGNING_SECRET",
+        "topic_prefix": "webhook.custom",
+        "signature": {"scheme": "hmac", "header": "X-Signature", "prefix": "sha256="}
+    }))
+    .expect("deserialize");
+    assert_eq!(
+        route.signature,
+        Some(SignatureScheme::Hmac(HmacSignature {
+            header: "X-Signature".into(),
+            algorithm: HmacAlgorithm::Sha256,
+            encoding: DigestEncoding::Hex,
+            prefix: Some("sha256=".into()),
+        }))
+    );
+
+    let github: WebhookRoute = serde_json::from_value(json!({
+        "path": "/github",
+        "topic_prefix": "webhook.github",
+        "topic_rule": {"sources": [{"header": "X-GitHub-Event"}, {"constant": "push"}]}
+    }))
+    .expect("deserialize topic rule");
+    assert_eq!(
+        github.topic_rule,
+        Some(TopicRule {
+            sources: vec![
+                EventTypeSource::Header("X-GitHub-Event".into()),
+                EventTypeSource::Constant("push".into()),
+            ],
+            fallback: "received".into(),
+        })
+    );
+
+    let legacy: WebhookRoute = serde_json::from_value(json!({
+        "path": "/github",
+        "secret_ref": null,
+        "topic_prefix": "webhook.github"
+    }))
+    .expect("deserialize legacy route");
+    assert_eq!(legacy.signature, None);
+    assert_eq!(legacy.topic_rule, None);
 }
 
 #[test]
@@ -42,20 +170,49 @@ fn scheduler_config_roundtrip() {
         schedules: vec![
             Schedule {
                 name: "daily".into(),
-                cron: "0 0 * * *".into(),
+                kind: ScheduleKind::Cron {
+                    cron: "0 0 * * *".into(),
+                },
                 topic: "timer.daily.report".into(),
                 payload: json!({"kind":"daily"}),
+                payload_template: false,
+                timezone: Some("Europe/Berlin".into()),
+                dst_policy: DstPolicy::FireTwice,
+                catch_up: Default::default(),
+                jitter: None,
+                calendar: Some(BusinessCalendar {
+                    include: Vec::new(),
+                    exclude: vec![
+                        CalendarSource::Dates(vec![NaiveDate::from_ymd_opt(2024, 12, 25).unwrap()]),
+                        CalendarSource::Ical(
+                            "BEGIN:VEVENT\nDTSTART;VALUE=DATE:20240101\nEND:VEVENT".into(),
+                        ),
+                    ],
+                }),
             },
             Schedule {
                 name: "hourly".into(),
-                cron: "0 * * * *".into(),
+                kind: ScheduleKind::Cron {
+                    cron: "0 * * * *".into(),
+                },
                 topic: "timer.hourly.sync".into(),
                 payload: json!({"kind":"hourly"}),
+                payload_template: false,
+                timezone: None,
+                dst_policy: DstPolicy::default(),
+                catch_up: Default::default(),
+                jitter: Some(Jitter {
+                    window_secs: 600,
+                    mode: JitterMode::Spread,
+                }),
+                calendar: None,
             },
         ],
     };
 
     let value = serde_json::to_string(&cfg).expect("serialize");
+    assert!(value.contains(r#""jitter":{"window_secs":600,"mode":"spread"}"#));
+    assert!(value.contains(r#""calendar":{"exclude":[{"dates":["2024-12-25"]},{"ical":"#));
     let roundtrip: SchedulerConfig = serde_json::from_str(&value).expect("deserialize");
     assert_eq!(cfg, roundtrip);
 }
@@ -96,3 +253,30 @@ fn idempotency_key_helper_sets_metadata() {
     set_idempotency_key(&mut metadata, "def");
     assert_eq!(metadata.get("idempotency_key"), Some(&"def".to_string()));
 }
+
+#[test]
+fn in_memory_dedup_expires_after_ttl() {
+    let store = InMemoryDedupStore::new();
+    let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    assert!(store.check_and_record("a", now, 60).unwrap());
+    assert!(
+        !store
+            .check_and_record("a", now + Duration::seconds(59), 60)
+            .unwrap()
+    );
+    assert!(store.check_and_record("b", now, 60).unwrap());
+    assert!(
+        store
+            .check_and_record("a", now + Duration::seconds(60), 60)
+            .unwrap()
+    );
+}
+
+#[test]
+fn replay_protection_defaults() {
+    let replay: ReplayProtection =
+        serde_json::from_value(json!({"key_header": "X-GitHub-Delivery"})).unwrap();
+    assert_eq!(replay.ttl_secs, 86_400);
+    assert_eq!(replay.max_skew_secs, 300);
+    assert_eq!(replay.key_header.as_deref(), Some("X-GitHub-Delivery"));
+}
@@ -0,0 +1,135 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use provider_core::ProviderError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Attachment bytes above this size are never inlined into inbound events; only
+/// their metadata (and any blob reference set by the host) is kept.
+pub const MAX_INLINE_ATTACHMENT_BYTES: usize = 256 * 1024;
+
+/// Largest attachment MS Graph accepts inline in a `sendMail` request; bigger files
+/// need an upload session.
+pub const MAX_GRAPH_ATTACHMENT_BYTES: usize = 3 * 1024 * 1024;
+
+/// File attached to an inbound or outbound email.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmailAttachment {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    pub content_type: String,
+    /// Decoded size in bytes.
+    pub size: u64,
+    /// `Content-ID` without angle brackets, as referenced by `cid:` URLs in HTML.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_id: Option<String>,
+    /// Rendered inside the body rather than listed as a download.
+    #[serde(default)]
+    pub inline: bool,
+    /// Absent when the bytes were dropped for size and no blob reference was set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<AttachmentContent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentContent {
+    /// Raw bytes, serialized as standard base64.
+    Bytes(#[serde(with = "base64_bytes")] Vec<u8>),
+    /// Host-resolvable reference (object-store key, provider attachment id, ...).
+    BlobRef(String),
+}
+
+impl EmailAttachment {
+    /// Build an attachment carrying its bytes, with `size` taken from them.
+    pub fn from_bytes(
+        filename: impl Into<String>,
+        content_type: impl Into<String>,
+        bytes: Vec<u8>,
+    ) -> Self {
+        Self {
+            filename: Some(filename.into()),
+            content_type: content_type.into(),
+            size: bytes.len() as u64,
+            content_id: None,
+            inline: false,
+            content: Some(AttachmentContent::Bytes(bytes)),
+        }
+    }
+
+    /// Copy for an inbound event: bytes above [`MAX_INLINE_ATTACHMENT_BYTES`] are dropped.
+    pub(crate) fn for_event(&self) -> Self {
+        let mut attachment = self.clone();
+        if matches!(&attachment.content, Some(AttachmentContent::Bytes(bytes)) if bytes.len() > MAX_INLINE_ATTACHMENT_BYTES)
+        {
+            attachment.content = None;
+        }
+        attachment
+    }
+
+    /// Bytes to send; `size` must match them so the metadata cannot drift from the content.
+    pub(crate) fn bytes(&self) -> Result<&[u8], ProviderError> {
+        match &self.content {
+            Some(AttachmentContent::Bytes(bytes)) if bytes.len() as u64 != self.size => {
+                Err(ProviderError::Config(format!(
+                    "attachment {}: size {} does not match its {} bytes",
+                    self.display_name(),
+                    self.size,
+                    bytes.len()
+                )))
+            }
+            Some(AttachmentContent::Bytes(bytes)) => Ok(bytes),
+            Some(AttachmentContent::BlobRef(reference)) => Err(ProviderError::Config(format!(
+                "attachment {}: blob reference `{reference}` must be resolved to bytes before sending",
+                self.display_name()
+            ))),
+            None => Err(ProviderError::Config(format!(
+                "attachment {}: missing content",
+                self.display_name()
+            ))),
+        }
+    }
+
+    fn display_name(&self) -> &str {
+        self.filename.as_deref().unwrap_or("attachment")
+    }
+
+    /// MS Graph `fileAttachment` resource; files above [`MAX_GRAPH_ATTACHMENT_BYTES`] are
+    /// rejected.
+    pub(crate) fn to_graph(&self) -> Result<Value, ProviderError> {
+        let bytes = self.bytes()?;
+        if bytes.len() > MAX_GRAPH_ATTACHMENT_BYTES {
+            return Err(ProviderError::Config(format!(
+                "attachment {}: {} bytes exceeds the {MAX_GRAPH_ATTACHMENT_BYTES}-byte Graph inline limit; upload it through an upload session",
+                self.display_name(),
+                bytes.len()
+            )));
+        }
+        let mut value = json!({
+            "@odata.type": "#microsoft.graph.fileAttachment",
+            "name": self.display_name(),
+            "contentType": self.content_type,
+            "contentBytes": STANDARD.encode(bytes),
+            "isInline": self.inline,
+        });
+        if let Some(content_id) = &self.content_id {
+            value["contentId"] = json!(content_id);
+        }
+        Ok(value)
+    }
+}
+
+mod base64_bytes {
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        STANDARD.decode(text).map_err(D::Error::custom)
+    }
+}
@@ -0,0 +1,368 @@
+/// Plain-text rendering of an HTML body, used as the `text/plain` alternative when
+/// only HTML is supplied.
+///
+/// Block elements become line breaks, list items get `- ` / `1. ` markers, links
+/// keep their target as `label (url)`, `<pre>` keeps its whitespace and
+/// `script`/`style`/`head` content is dropped. Common character references are
+/// decoded.
+pub fn html_to_text(html: &str) -> String {
+    let mut writer = TextWriter::default();
+    let mut links: Vec<(usize, Option<String>)> = Vec::new();
+    let mut lists: Vec<Option<usize>> = Vec::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        writer.text(&decode_entities(&rest[..start]));
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(tag) = Tag::parse(rest) else {
+            writer.text("<");
+            rest = &rest[1..];
+            continue;
+        };
+        rest = &rest[tag.len..];
+
+        match (tag.name.as_str(), tag.closing) {
+            ("script" | "style" | "head" | "title", false) => {
+                rest = skip_element(rest, &tag.name);
+            }
+            ("br", _) => writer.line_break(),
+            ("p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "table" | "blockquote", _)
+            | ("hr", false) => writer.block(2),
+            ("div" | "tr" | "section" | "article" | "header" | "footer" | "dt" | "dd", _) => {
+                writer.block(1)
+            }
+            ("td" | "th", true) => writer.text(" "),
+            ("ul", false) => {
+                writer.block(if lists.is_empty() { 2 } else { 1 });
+                lists.push(None);
+            }
+            ("ol", false) => {
+                writer.block(if lists.is_empty() { 2 } else { 1 });
+                lists.push(Some(0));
+            }
+            ("ul" | "ol", true) => {
+                lists.pop();
+                writer.block(if lists.is_empty() { 2 } else { 1 });
+            }
+            ("li", false) => {
+                writer.block(1);
+                let indent = "  ".repeat(lists.len().saturating_sub(1));
+                let marker = match lists.last_mut() {
+                    Some(Some(counter)) => {
+                        *counter += 1;
+                        format!("{indent}{counter}. ")
+                    }
+                    _ => format!("{indent}- "),
+                };
+                writer.marker(&marker);
+            }
+            ("pre", false) => {
+                writer.block(2);
+                writer.pre += 1;
+            }
+            ("pre", true) => {
+                writer.pre = writer.pre.saturating_sub(1);
+                writer.block(2);
+            }
+            ("a", false) => {
+                links.push((writer.mark(), tag.attribute("href")));
+            }
+            ("a", true) => {
+                if let Some((start, Some(href))) = links.pop() {
+                    let label = writer.out[start..].trim();
+                    let external = ["http://", "https://"]
+                        .iter()
+                        .any(|scheme| href.starts_with(scheme));
+                    if external && label != href && !label.is_empty() {
+                        writer.text(&format!(" ({href})"));
+                    }
+                }
+            }
+            ("img", false) => {
+                if let Some(alt) = tag.attribute("alt").filter(|alt| !alt.trim().is_empty()) {
+                    writer.text(&format!("[{}]", alt.trim()));
+                }
+            }
+            _ => {}
+        }
+    }
+    writer.text(&decode_entities(rest));
+    writer.out.trim_end().to_string()
+}
+
+#[derive(Default)]
+struct TextWriter {
+    out: String,
+    pending_space: bool,
+    pending_breaks: usize,
+    pre: usize,
+    /// Output before this index is final: trimming never reaches back past it.
+    floor: usize,
+}
+
+impl TextWriter {
+    fn text(&mut self, text: &str) {
+        if self.pre > 0 {
+            if !text.is_empty() {
+                self.flush();
+                self.out.push_str(text);
+            }
+            return;
+        }
+        for c in text.chars() {
+            if c.is_whitespace() && c != '\u{a0}' {
+                self.pending_space = true;
+            } else {
+                self.flush();
+                self.out.push(if c == '\u{a0}' { ' ' } else { c });
+            }
+        }
+    }
+
+    /// Text that must not be collapsed, such as a list marker.
+    fn marker(&mut self, marker: &str) {
+        self.flush();
+        self.out.push_str(marker);
+    }
+
+    /// Flush and return the current end of the output, which later trimming keeps.
+    fn mark(&mut self) -> usize {
+        self.flush();
+        self.floor = self.out.len();
+        self.floor
+    }
+
+    fn line_break(&mut self) {
+        self.pending_space = false;
+        self.pending_breaks += 1;
+    }
+
+    /// Ensure at least `lines` line breaks before the next text.
+    fn block(&mut self, lines: usize) {
+        self.pending_space = false;
+        self.pending_breaks = self.pending_breaks.max(lines);
+    }
+
+    fn flush(&mut self) {
+        if self.pending_breaks > 0 {
+            if !self.out.is_empty() {
+                let trailing = self.out.len() - self.out.trim_end_matches('\n').len();
+                while self.out.len() > self.floor && self.out.ends_with(' ') {
+                    self.out.pop();
+                }
+                let missing = self.pending_breaks.saturating_sub(trailing);
+                self.out.extend(std::iter::repeat_n('\n', missing));
+            }
+            self.pending_breaks = 0;
+        } else if self.pending_space && !self.out.is_empty() && !self.out.ends_with(['\n', ' ']) {
+            self.out.push(' ');
+        }
+        self.pending_space = false;
+    }
+}
+
+struct Tag {
+    name: String,
+    closing: bool,
+    attributes: String,
+    /// Bytes consumed, including `<` and `>`.
+    len: usize,
+}
+
+impl Tag {
+    /// `<name ...>`, `</name>` or `<!...>` at the start of `text`; `None` when the
+    /// `<` does not open a tag and should be kept as text.
+    fn parse(text: &str) -> Option<Self> {
+        let inner = &text[1..];
+        let closing = inner.starts_with('/');
+        let first = inner.trim_start_matches('/').chars().next()?;
+        if !(first.is_ascii_alphabetic() || first == '!' || first == '?') {
+            return None;
+        }
+        let mut quote = None;
+        let end = inner.char_indices().find_map(|(index, c)| {
+            match (quote, c) {
+                (Some(open), c) if c == open => quote = None,
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '>') => return Some(index),
+                _ => {}
+            }
+            None
+        })?;
+        let body = inner[..end].trim_start_matches('/');
+        let name_len = body
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(body.len());
+        Some(Self {
+            name: body[..name_len].to_ascii_lowercase(),
+            closing,
+            attributes: body[name_len..].to_string(),
+            len: end + 2,
+        })
+    }
+
+    fn attribute(&self, name: &str) -> Option<String> {
+        let mut rest = self.attributes.as_str();
+        loop {
+            rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+            if rest.is_empty() {
+                return None;
+            }
+            let key_len = rest
+                .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+                .unwrap_or(rest.len());
+            let key = &rest[..key_len];
+            rest = rest[key_len..].trim_start();
+            let mut value = "";
+            if let Some(after) = rest.strip_prefix('=') {
+                let after = after.trim_start();
+                let (found, remaining) = match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let inner = &after[1..];
+                        let close = inner.find(quote).unwrap_or(inner.len());
+                        (&inner[..close], inner.get(close + 1..).unwrap_or(""))
+                    }
+                    _ => {
+                        let close = after.find(char::is_whitespace).unwrap_or(after.len());
+                        (&after[..close], &after[close..])
+                    }
+                };
+                value = found;
+                rest = remaining;
+            }
+            if key.eq_ignore_ascii_case(name) {
+                return Some(decode_entities(value));
+            }
+        }
+    }
+}
+
+/// Skip to just past `</name>`, or to the end when the element is never closed.
+fn skip_element<'a>(text: &'a str, name: &str) -> &'a str {
+    let lower = text.to_ascii_lowercase();
+    let close = format!("</{name}");
+    match lower.find(&close) {
+        Some(start) => match text[start..].find('>') {
+            Some(end) => &text[start + end + 1..],
+            None => "",
+        },
+        None => "",
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| Some((entity(&rest[1..=end])?, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "bull" => '•',
+        "middot" => '·',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "euro" => '€',
+        "pound" => '£',
+        "deg" => '°',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_blocks_lists_and_links() {
+        let html = r#"<!DOCTYPE html>
+<html><head><title>Ignored</title><style>p { color: red }</style></head>
+<body>
+  <h1>Nightly   report</h1>
+  <p>All <b>12</b> jobs passed &mdash; see <a href="https://ci.example.com/run/7">the run</a>.<br>
+  Next run at 02:00&nbsp;UTC.</p>
+  <ul><li>build</li><li>test<ol><li>unit</li><li>live</li></ol></li></ul>
+  <!-- internal note -->
+  <pre>  exit 0
+  ok</pre>
+  <p><a href="https://example.com">https://example.com</a> &amp; <a href="mailto:ops@example.com">ops</a>
+  <img src="cid:logo" alt="Greentic"> 3 &lt; 4 &#x2713; &unknown; a & b</p>
+  <script>alert("x")</script>
+</body></html>"#;
+        assert_eq!(
+            html_to_text(html),
+            "Nightly report\n\n\
+             All 12 jobs passed — see the run (https://ci.example.com/run/7).\n\
+             Next run at 02:00 UTC.\n\n\
+             - build\n\
+             - test\n  1. unit\n  2. live\n\n\
+             \x20 exit 0\n  ok\n\n\
+             https://example.com & ops [Greentic] 3 < 4 ✓ &unknown; a & b"
+        );
+    }
+
+    #[test]
+    fn handles_fragments_and_stray_brackets() {
+        assert_eq!(html_to_text("plain text"), "plain text");
+        assert_eq!(html_to_text("a < b > c"), "a < b > c");
+        assert_eq!(
+            html_to_text("<div>one</div><div>two</div>unclosed <b"),
+            "one\ntwo\nunclosed <b"
+        );
+        assert_eq!(html_to_text("<td>a</td><td>b</td>"), "a b");
+        assert_eq!(html_to_text(""), "");
+    }
+
+    #[test]
+    fn links_survive_trimmed_preformatted_text() {
+        assert_eq!(
+            html_to_text("<pre>x   <a href=\"https://q\"><br>y</a></pre>"),
+            "x   \ny (https://q)"
+        );
+    }
+}
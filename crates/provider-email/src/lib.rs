@@ -1,3 +1,5 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE;
 use chrono::{DateTime, Utc};
 use provider_core::secrets::{SecretProvider, resolve_secret};
 use provider_core::{ProviderError, new_event};
@@ -5,6 +7,20 @@ use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::collections::BTreeMap;
 
+mod attachment;
+mod html_text;
+mod mime;
+mod rfc822;
+mod threading;
+
+pub use attachment::{
+    AttachmentContent, EmailAttachment, MAX_GRAPH_ATTACHMENT_BYTES, MAX_INLINE_ATTACHMENT_BYTES,
+};
+pub use html_text::html_to_text;
+pub use mime::parse_inbound_email;
+use rfc822::Rfc822Message;
+pub use threading::ReplyReference;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum EmailProvider {
@@ -12,7 +28,8 @@ pub enum EmailProvider {
     Gmail,
 }
 
-/// Minimal inbound email representation supplied by host/poller.
+/// Minimal inbound email representation supplied by host/poller, or parsed from a
+/// raw message with [`parse_inbound_email`].
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct InboundEmail {
     pub provider: EmailProvider,
@@ -27,11 +44,23 @@ pub struct InboundEmail {
     pub bcc: Vec<String>,
     pub received_at: DateTime<Utc>,
     pub body: String,
+    /// HTML alternative of `body`, when the message has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html_body: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<EmailAttachment>,
     #[serde(default)]
     pub headers: BTreeMap<String, String>,
+    /// Provider thread id when the host has one (Gmail `threadId`, MS Graph
+    /// `conversationId`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
 }
 
-/// Build an EventEnvelope for an inbound email.
+/// Build an EventEnvelope for an inbound email. Attachments are listed with their
+/// metadata; bytes above [`MAX_INLINE_ATTACHMENT_BYTES`] are left out of the payload.
+/// The payload carries a normalized `thread_id` (also in metadata) and a
+/// `reply_reference` that outbound events can pass back as `in_reply_to`.
 pub fn map_inbound_email(
     tenant: greentic_types::TenantCtx,
     email: &InboundEmail,
@@ -48,6 +77,8 @@ pub fn map_inbound_email(
     );
     metadata.insert("folder_or_label".into(), email.folder_or_label.clone());
     metadata.insert("message_id".into(), email.message_id.clone());
+    let thread_id = threading::thread_id(email);
+    metadata.insert("thread_id".into(), thread_id.clone());
 
     for (k, v) in email.headers.iter() {
         metadata.insert(format!("header:{}", k.to_lowercase()), v.clone());
@@ -67,25 +98,48 @@ pub fn map_inbound_email(
             "cc": email.cc,
             "bcc": email.bcc,
             "body": email.body,
+            "html_body": email.html_body,
+            "attachments": email.attachments.iter().map(EmailAttachment::for_event).collect::<Vec<_>>(),
             "received_at": email.received_at,
             "headers": email.headers,
+            "thread_id": thread_id,
+            "reply_reference": ReplyReference::from_inbound(email),
         }),
         metadata,
     )
 }
 
-/// Generic outbound email request payload.
+/// Generic outbound email request payload. At least one of `text` and `html` is
+/// required; a legacy `body` field is read as `html`.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct OutboundEmail {
     pub provider: EmailProvider,
     pub to: Vec<String>,
     pub subject: String,
-    pub body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, alias = "body", skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
     #[serde(default)]
     pub cc: Vec<String>,
     #[serde(default)]
     pub bcc: Vec<String>,
     pub from_override: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<EmailAttachment>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_reply_to: Option<ReplyReference>,
+}
+
+impl OutboundEmail {
+    /// The content the former `body` field held: the HTML body, or the text body when
+    /// there is no HTML.
+    pub fn body(&self) -> &str {
+        self.html
+            .as_deref()
+            .or(self.text.as_deref())
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -93,6 +147,11 @@ pub struct EmailSendRequest {
     pub provider: EmailProvider,
     pub payload: Value,
     pub secret_events: Vec<greentic_types::EventEnvelope>,
+    /// Provider message being replied to. For MS Graph the payload is then a
+    /// `createReply` body for `POST /messages/{id}/createReply` (followed by a send
+    /// of the returned draft) instead of a `sendMail` body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to_message_id: Option<String>,
 }
 
 /// Resolve provider-specific secrets and emit metadata-only events.
@@ -133,6 +192,17 @@ pub fn ensure_email_secrets(
 }
 
 /// Translate an outbound EventEnvelope into a provider-specific request representation.
+///
+/// The payload carries `text`, `html` (legacy alias `body`) or, for Gmail only, both.
+/// MS Graph gets a `sendMail` body whose content type is `HTML` or `Text`; it has no
+/// multipart alternative, so a payload with both is rejected rather than silently
+/// dropping the text; Gmail gets the `users.messages.send` body `{"raw": base64url(rfc822)}`,
+/// dated with the event time, as multipart/alternative when HTML is present (with
+/// [`html_to_text`] supplying a missing text part). An `in_reply_to`
+/// [`ReplyReference`] turns the Graph request into a `createReply` (its `message_id` must
+/// be a Graph message id, not a `<...>` Internet Message-ID) and adds
+/// `threadId` plus `In-Reply-To`/`References` headers for Gmail; Gmail only threads
+/// the reply when its subject matches the original's.
 pub fn build_send_request(
     event: &greentic_types::EventEnvelope,
     secrets: &dyn SecretProvider,
@@ -141,13 +211,18 @@ pub fn build_send_request(
     let payload = event.payload.clone();
     let to = expect_array_strings(&payload, "to")?;
     let subject = expect_string(&payload, "subject")?;
-    let body = expect_string(&payload, "body")?;
+    let text = optional_string(&payload, "text");
+    let html = optional_string(&payload, "html").or_else(|| optional_string(&payload, "body"));
+    if text.is_none() && html.is_none() {
+        return Err(ProviderError::Config(
+            "missing string field text, html or body".into(),
+        ));
+    }
     let cc = optional_array_strings(&payload, "cc");
     let bcc = optional_array_strings(&payload, "bcc");
-    let from_override = payload
-        .get("from")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
+    let from_override = optional_string(&payload, "from");
+    let attachments = optional_attachments(&payload)?;
+    let in_reply_to = optional_reply_reference(&payload)?;
 
     let secret_events = ensure_email_secrets(
         provider.clone(),
@@ -157,35 +232,77 @@ pub fn build_send_request(
     )?;
 
     match provider {
-        EmailProvider::MsGraph => Ok(EmailSendRequest {
-            provider,
-            payload: json!({
-                "message": {
-                    "subject": subject,
-                    "body": { "contentType": "HTML", "content": body },
-                    "toRecipients": to.iter().map(|addr| json!({"emailAddress": {"address": addr}})).collect::<Vec<_>>(),
-                    "ccRecipients": cc.iter().map(|addr| json!({"emailAddress": {"address": addr}})).collect::<Vec<_>>(),
-                    "bccRecipients": bcc.iter().map(|addr| json!({"emailAddress": {"address": addr}})).collect::<Vec<_>>(),
-                    "from": from_override.as_ref().map(|addr| json!({"emailAddress": {"address": addr}})),
-                },
-                "saveToSentItems": false
-            }),
-            secret_events,
-        }),
-        EmailProvider::Gmail => Ok(EmailSendRequest {
-            provider,
-            payload: json!({
-                "message": {
-                    "subject": subject,
-                    "body": body,
-                    "to": to,
-                    "cc": cc,
-                    "bcc": bcc,
-                    "from": from_override,
+        EmailProvider::MsGraph => {
+            if let Some(reference) = &in_reply_to
+                && reference.message_id.starts_with('<')
+            {
+                return Err(ProviderError::Config(format!(
+                    "msgraph replies need the Graph message id, not the Internet Message-ID {}",
+                    reference.message_id
+                )));
+            }
+            let body = match (&html, &text) {
+                (Some(_), Some(_)) => {
+                    return Err(ProviderError::Config(
+                        "msgraph sends a single body; provide either text or html".into(),
+                    ));
                 }
-            }),
-            secret_events,
-        }),
+                (Some(html), None) => json!({ "contentType": "HTML", "content": html }),
+                (None, text) => json!({ "contentType": "Text", "content": text }),
+            };
+            let message = json!({
+                "subject": subject,
+                "body": body,
+                "toRecipients": to.iter().map(|addr| json!({"emailAddress": {"address": addr}})).collect::<Vec<_>>(),
+                "ccRecipients": cc.iter().map(|addr| json!({"emailAddress": {"address": addr}})).collect::<Vec<_>>(),
+                "bccRecipients": bcc.iter().map(|addr| json!({"emailAddress": {"address": addr}})).collect::<Vec<_>>(),
+                "from": from_override.as_ref().map(|addr| json!({"emailAddress": {"address": addr}})),
+                "attachments": attachments.iter().map(EmailAttachment::to_graph).collect::<Result<Vec<_>, _>>()?,
+            });
+            let payload = match in_reply_to {
+                Some(_) => json!({ "message": message }),
+                None => json!({ "message": message, "saveToSentItems": false }),
+            };
+            Ok(EmailSendRequest {
+                provider,
+                payload,
+                secret_events,
+                reply_to_message_id: in_reply_to.map(|reference| reference.message_id),
+            })
+        }
+        EmailProvider::Gmail => {
+            let references = in_reply_to
+                .as_ref()
+                .map(ReplyReference::reply_references)
+                .unwrap_or_default();
+            let text = text.or_else(|| html.as_deref().map(html_to_text));
+            let message = Rfc822Message {
+                from: from_override.as_deref(),
+                to: &to,
+                cc: &cc,
+                bcc: &bcc,
+                subject: &subject,
+                date: Some(event.time),
+                in_reply_to: in_reply_to
+                    .as_ref()
+                    .and_then(|reference| reference.internet_message_id.as_deref()),
+                references: &references,
+                text: text.as_deref(),
+                html: html.as_deref(),
+                attachments: &attachments,
+            }
+            .render()?;
+            let mut payload = json!({ "raw": URL_SAFE.encode(message) });
+            if let Some(thread_id) = in_reply_to.and_then(|reference| reference.thread_id) {
+                payload["threadId"] = json!(thread_id);
+            }
+            Ok(EmailSendRequest {
+                provider,
+                payload,
+                secret_events,
+                reply_to_message_id: None,
+            })
+        }
     }
 }
 
@@ -218,6 +335,13 @@ fn expect_string(payload: &Value, key: &str) -> Result<String, ProviderError> {
         .ok_or_else(|| ProviderError::Config(format!("missing string field {}", key)))
 }
 
+fn optional_string(payload: &Value, key: &str) -> Option<String> {
+    payload
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
 fn expect_array_strings(payload: &Value, key: &str) -> Result<Vec<String>, ProviderError> {
     payload
         .get(key)
@@ -243,6 +367,23 @@ fn optional_array_strings(payload: &Value, key: &str) -> Vec<String> {
         .unwrap_or_default()
 }
 
+fn optional_attachments(payload: &Value) -> Result<Vec<EmailAttachment>, ProviderError> {
+    match payload.get("attachments") {
+        Some(value) => serde_json::from_value(value.clone())
+            .map_err(|err| ProviderError::Config(format!("invalid attachments: {err}"))),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn optional_reply_reference(payload: &Value) -> Result<Option<ReplyReference>, ProviderError> {
+    match payload.get("in_reply_to") {
+        Some(Value::Null) | None => Ok(None),
+        Some(value) => serde_json::from_value(value.clone())
+            .map(Some)
+            .map_err(|err| ProviderError::Config(format!("invalid in_reply_to: {err}"))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -251,7 +392,7 @@ mod tests {
     use std::collections::BTreeMap;
     use std::collections::BTreeMap as Map;
 
-    fn sample_tenant() -> greentic_types::TenantCtx {
+    pub(crate) fn sample_tenant() -> greentic_types::TenantCtx {
         use greentic_types::{EnvId, TeamId, TenantCtx, TenantId};
 
         let env = EnvId::try_from("dev").unwrap();
@@ -273,7 +414,10 @@ mod tests {
             bcc: vec![],
             received_at: Utc::now(),
             body: "Test".into(),
+            html_body: None,
+            attachments: vec![],
             headers: BTreeMap::from([("X-Test".into(), "1".into())]),
+            thread_id: None,
         };
 
         let event = map_inbound_email(sample_tenant(), &email);
@@ -323,6 +467,343 @@ mod tests {
         assert_eq!(request.secret_events[0].topic, "greentic.secrets.put");
     }
 
+    #[test]
+    fn inbound_events_omit_large_attachment_bytes() {
+        let small = EmailAttachment::from_bytes("a.txt", "text/plain", b"hi".to_vec());
+        let large = EmailAttachment::from_bytes(
+            "big.bin",
+            "application/octet-stream",
+            vec![0; MAX_INLINE_ATTACHMENT_BYTES + 1],
+        );
+        let email = InboundEmail {
+            provider: EmailProvider::Gmail,
+            folder_or_label: "INBOX".into(),
+            message_id: "msg-2".into(),
+            subject: "Files".into(),
+            from: "ops@example.com".into(),
+            to: vec!["team@example.com".into()],
+            cc: vec![],
+            bcc: vec![],
+            received_at: Utc::now(),
+            body: "See attached".into(),
+            html_body: None,
+            attachments: vec![small, large],
+            headers: BTreeMap::new(),
+            thread_id: None,
+        };
+
+        let event = map_inbound_email(sample_tenant(), &email);
+        let attachments = &event.payload["attachments"];
+        assert_eq!(attachments[0]["content"], json!({"bytes": "aGk="}));
+        assert_eq!(attachments[1]["filename"], "big.bin");
+        assert_eq!(attachments[1]["size"], MAX_INLINE_ATTACHMENT_BYTES + 1);
+        assert!(attachments[1].get("content").is_none());
+    }
+
+    #[test]
+    fn emits_attachments_per_provider() {
+        let payload = json!({
+            "to": ["a@example.com"],
+            "subject": "Report",
+            "body": "<p>Attached</p>",
+            "attachments": [
+                {"filename": "report.pdf", "content_type": "application/pdf", "size": 3,
+                 "content": {"bytes": "+/8A"}},
+                {"content_type": "image/png", "size": 1, "content_id": "logo",
+                 "inline": true, "content": {"bytes": "AA=="}}
+            ]
+        });
+        let mut event = greentic_types::EventEnvelope {
+            id: greentic_types::EventId::new("evt-1").unwrap(),
+            topic: "email.out.msgraph".into(),
+            r#type: "t".into(),
+            source: "s".into(),
+            tenant: sample_tenant(),
+            subject: Some("Report".into()),
+            time: Utc::now(),
+            correlation_id: None,
+            payload,
+            metadata: BTreeMap::new(),
+        };
+        let secrets = StaticSecretProvider::empty();
+
+        let graph = build_send_request(&event, &secrets).expect("graph request");
+        let attachments = &graph.payload["message"]["attachments"];
+        assert_eq!(
+            attachments[0],
+            json!({
+                "@odata.type": "#microsoft.graph.fileAttachment",
+                "name": "report.pdf",
+                "contentType": "application/pdf",
+                "contentBytes": "+/8A",
+                "isInline": false,
+            })
+        );
+        assert_eq!(attachments[1]["contentId"], "logo");
+        assert_eq!(attachments[1]["isInline"], true);
+
+        event.topic = "email.out.gmail".into();
+        let gmail = build_send_request(&event, &secrets).expect("gmail request");
+        let raw = URL_SAFE
+            .decode(gmail.payload["raw"].as_str().unwrap())
+            .unwrap();
+        let sent = parse_inbound_email(EmailProvider::Gmail, "SENT", &raw).unwrap();
+        // Gmail assigns the Message-ID, so the parser synthesizes one.
+        assert!(sent.message_id.ends_with("@synthesized.invalid>"));
+        assert_eq!(sent.attachments.len(), 2);
+        assert_eq!(sent.attachments[0].content_id.as_deref(), Some("logo"));
+        assert_eq!(sent.attachments[1].filename.as_deref(), Some("report.pdf"));
+        assert_eq!(
+            sent.attachments[1].content,
+            Some(AttachmentContent::Bytes(vec![0xfb, 0xff, 0x00]))
+        );
+
+        event.payload["attachments"] = json!([
+            {"filename": "big.zip", "content_type": "application/zip", "size": 9,
+             "content": {"blob_ref": "s3://bucket/big.zip"}}
+        ]);
+        let err = build_send_request(&event, &secrets).unwrap_err();
+        assert!(matches!(err, ProviderError::Config(msg) if msg.contains("s3://bucket/big.zip")));
+    }
+
+    #[test]
+    fn rejects_mismatched_and_oversized_attachments() {
+        let mut event = greentic_types::EventEnvelope {
+            id: greentic_types::EventId::new("evt-1").unwrap(),
+            topic: "email.out.gmail".into(),
+            r#type: "t".into(),
+            source: "s".into(),
+            tenant: sample_tenant(),
+            subject: Some("Report".into()),
+            time: Utc::now(),
+            correlation_id: None,
+            payload: json!({
+                "to": ["a@example.com"],
+                "subject": "Report",
+                "text": "Attached",
+                "attachments": [
+                    {"filename": "report.pdf", "content_type": "application/pdf", "size": 4,
+                     "content": {"bytes": "+/8A"}}
+                ]
+            }),
+            metadata: BTreeMap::new(),
+        };
+        let secrets = StaticSecretProvider::empty();
+        let err = build_send_request(&event, &secrets).unwrap_err();
+        assert!(matches!(err, ProviderError::Config(msg) if msg.contains("size 4")));
+
+        let big = EmailAttachment::from_bytes(
+            "big.bin",
+            "application/octet-stream",
+            vec![0; MAX_GRAPH_ATTACHMENT_BYTES + 1],
+        );
+        event.payload["attachments"] = json!([big]);
+        assert!(build_send_request(&event, &secrets).is_ok());
+        event.topic = "email.out.msgraph".into();
+        let err = build_send_request(&event, &secrets).unwrap_err();
+        assert!(matches!(err, ProviderError::Config(msg) if msg.contains("upload session")));
+    }
+
+    #[test]
+    fn threads_replies_per_provider() {
+        let mut email = InboundEmail {
+            provider: EmailProvider::Gmail,
+            folder_or_label: "INBOX".into(),
+            message_id: "18e0c2f5a1b2c3d4".into(),
+            subject: "Re: Outage".into(),
+            from: "ops@example.com".into(),
+            to: vec!["team@example.com".into()],
+            cc: vec![],
+            bcc: vec![],
+            received_at: Utc::now(),
+            body: "Still down".into(),
+            html_body: None,
+            attachments: vec![],
+            headers: BTreeMap::from([
+                ("Message-Id".into(), "<c@example.com>".into()),
+                ("In-Reply-To".into(), "<b@example.com>".into()),
+                (
+                    "References".into(),
+                    "<a@example.com>\n <b@example.com>".into(),
+                ),
+            ]),
+            thread_id: None,
+        };
+        let event = map_inbound_email(sample_tenant(), &email);
+        assert_eq!(event.payload["thread_id"], "<a@example.com>");
+        assert_eq!(
+            event.metadata.get("thread_id"),
+            Some(&"<a@example.com>".into())
+        );
+
+        email.thread_id = Some("18e0c2f0ffff0000".into());
+        let event = map_inbound_email(sample_tenant(), &email);
+        assert_eq!(event.payload["thread_id"], "18e0c2f0ffff0000");
+        let reference = event.payload["reply_reference"].clone();
+        assert_eq!(
+            reference,
+            json!({
+                "message_id": "18e0c2f5a1b2c3d4",
+                "internet_message_id": "<c@example.com>",
+                "references": ["<a@example.com>", "<b@example.com>"],
+                "thread_id": "18e0c2f0ffff0000",
+            })
+        );
+
+        let mut reply = greentic_types::EventEnvelope {
+            id: greentic_types::EventId::new("evt-reply").unwrap(),
+            topic: "email.out.gmail".into(),
+            r#type: "t".into(),
+            source: "s".into(),
+            tenant: sample_tenant(),
+            subject: None,
+            time: Utc::now(),
+            correlation_id: None,
+            payload: json!({
+                "to": ["ops@example.com"],
+                "subject": "Re: Outage",
+                "body": "<p>Fixed</p>",
+                "in_reply_to": reference,
+            }),
+            metadata: BTreeMap::new(),
+        };
+        let secrets = StaticSecretProvider::empty();
+        let gmail = build_send_request(&reply, &secrets).expect("gmail reply");
+        assert_eq!(gmail.payload["threadId"], "18e0c2f0ffff0000");
+        assert_eq!(gmail.reply_to_message_id, None);
+        let raw = URL_SAFE
+            .decode(gmail.payload["raw"].as_str().unwrap())
+            .unwrap();
+        let raw = String::from_utf8(raw).unwrap();
+        assert!(raw.contains("In-Reply-To: <c@example.com>\r\n"));
+        assert!(
+            raw.contains("References: <a@example.com>\r\n <b@example.com>\r\n <c@example.com>\r\n")
+        );
+
+        reply.topic = "email.out.msgraph".into();
+        let graph = build_send_request(&reply, &secrets).expect("graph reply");
+        assert_eq!(
+            graph.reply_to_message_id.as_deref(),
+            Some("18e0c2f5a1b2c3d4")
+        );
+        assert!(graph.payload.get("saveToSentItems").is_none());
+        assert_eq!(graph.payload["message"]["subject"], "Re: Outage");
+
+        // Raw messages carry their `<...>` Message-ID, which `createReply` cannot address.
+        reply.payload["in_reply_to"]["message_id"] = json!("<c@example.com>");
+        assert!(matches!(
+            build_send_request(&reply, &secrets),
+            Err(ProviderError::Config(msg)) if msg.contains("Graph message id")
+        ));
+
+        reply.payload["in_reply_to"] = json!({"thread_id": "x"});
+        assert!(matches!(
+            build_send_request(&reply, &secrets),
+            Err(ProviderError::Config(msg)) if msg.starts_with("invalid in_reply_to")
+        ));
+    }
+
+    fn outbound_event(topic: &str, payload: Value) -> greentic_types::EventEnvelope {
+        greentic_types::EventEnvelope {
+            id: greentic_types::EventId::new("evt-1").unwrap(),
+            topic: topic.into(),
+            r#type: "t".into(),
+            source: "s".into(),
+            tenant: sample_tenant(),
+            subject: None,
+            time: Utc::now(),
+            correlation_id: None,
+            payload,
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    /// Text part, HTML part and raw message of the Gmail request for `payload`.
+    fn gmail_parts(payload: Value) -> (String, Option<String>, String) {
+        let event = outbound_event("email.out.gmail", payload);
+        let request = build_send_request(&event, &StaticSecretProvider::empty()).unwrap();
+        let raw = URL_SAFE
+            .decode(request.payload["raw"].as_str().unwrap())
+            .unwrap();
+        let email = parse_inbound_email(EmailProvider::Gmail, "SENT", &raw).unwrap();
+        (email.body, email.html_body, String::from_utf8(raw).unwrap())
+    }
+
+    fn graph_body(payload: Value) -> Result<Value, ProviderError> {
+        let event = outbound_event("email.out.msgraph", payload);
+        build_send_request(&event, &StaticSecretProvider::empty())
+            .map(|request| request.payload["message"]["body"].clone())
+    }
+
+    #[test]
+    fn sends_text_only_payloads_as_plain_text() {
+        let payload = json!({"to": ["a@example.com"], "subject": "Hi", "text": "Line 1\nLine 2"});
+        assert_eq!(
+            graph_body(payload.clone()).unwrap(),
+            json!({"contentType": "Text", "content": "Line 1\nLine 2"})
+        );
+        let (text, html, raw) = gmail_parts(payload);
+        assert_eq!(text, "Line 1\nLine 2");
+        assert_eq!(html, None);
+        assert!(!raw.contains("multipart/alternative"));
+    }
+
+    #[test]
+    fn derives_the_text_alternative_of_html_only_payloads() {
+        let payload = json!({"to": ["a@example.com"], "subject": "Hi",
+            "html": "<p>Hello <b>there</b></p><ul><li>one</li></ul>"});
+        assert_eq!(graph_body(payload.clone()).unwrap()["contentType"], "HTML");
+        let (text, html, raw) = gmail_parts(payload);
+        assert_eq!(text, "Hello there\n\n- one");
+        assert_eq!(
+            html.as_deref(),
+            Some("<p>Hello <b>there</b></p><ul><li>one</li></ul>")
+        );
+        assert!(raw.contains("Content-Type: multipart/alternative"));
+    }
+
+    #[test]
+    fn keeps_supplied_text_alongside_html() {
+        let payload = json!({"to": ["a@example.com"], "subject": "Hi",
+            "text": "Custom text", "html": "<p>Hello</p>"});
+        let (text, html, _) = gmail_parts(payload.clone());
+        assert_eq!(text, "Custom text");
+        assert_eq!(html.as_deref(), Some("<p>Hello</p>"));
+        // Graph sends a single body, so it cannot carry both.
+        assert!(matches!(
+            graph_body(payload),
+            Err(ProviderError::Config(msg)) if msg.contains("either text or html")
+        ));
+    }
+
+    #[test]
+    fn requires_text_or_html() {
+        let payload = json!({"to": ["a@example.com"], "subject": "Hi"});
+        assert!(matches!(
+            graph_body(payload),
+            Err(ProviderError::Config(msg)) if msg.contains("text, html or body")
+        ));
+    }
+
+    #[test]
+    fn reads_legacy_body_as_html() {
+        let outbound: OutboundEmail = serde_json::from_value(json!({
+            "provider": "gmail", "to": ["a@example.com"], "subject": "Hi",
+            "body": "<p>legacy</p>", "from_override": null
+        }))
+        .unwrap();
+        assert_eq!(outbound.html.as_deref(), Some("<p>legacy</p>"));
+        assert_eq!(outbound.text, None);
+        assert_eq!(outbound.body(), "<p>legacy</p>");
+
+        let text_only = OutboundEmail {
+            html: None,
+            text: Some("plain".into()),
+            ..outbound
+        };
+        assert_eq!(text_only.body(), "plain");
+    }
+
     #[test]
     fn rejects_unknown_topic() {
         let payload = json!({"to": ["a@example.com"], "subject": "Hi", "body": "text"});
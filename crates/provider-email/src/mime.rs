@@ -0,0 +1,877 @@
+use crate::{AttachmentContent, EmailAttachment, EmailProvider, InboundEmail, html_to_text};
+use base64::Engine;
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use chrono::{DateTime, Utc};
+use encoding_rs::{Encoding, UTF_8};
+use provider_core::ProviderError;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+const DEFAULT_CONTENT_TYPE: &str = "text/plain; charset=us-ascii";
+const MAX_DEPTH: usize = 32;
+
+/// Mail in the wild pads, wraps and truncates base64 inconsistently.
+const LENIENT_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+        .with_decode_allow_trailing_bits(true),
+);
+
+/// Parse a raw RFC 5322/MIME message (IMAP fetch, SMTP DATA, `.eml` file) into an
+/// [`InboundEmail`] ready for [`crate::map_inbound_email`].
+///
+/// `body` is the first inline `text/plain` part and `html_body` the first inline
+/// `text/html` part; an HTML-only message gets `body` rendered from its HTML with
+/// [`html_to_text`]. Every other leaf part, and any part with a filename, becomes an
+/// attachment holding its decoded bytes; attachments whose transfer encoding does not decode are skipped. Text is
+/// decoded from its transfer encoding and charset, with line endings normalised to
+/// `\n`. Headers are unfolded and RFC 2047 encoded words decoded; the first occurrence
+/// of a repeated header wins. A message without `Message-ID` gets a stable
+/// `<sha256@synthesized.invalid>` id hashed from its `Date`, `From` and body, so
+/// redeliveries dedupe. `received_at` is taken from `Date`, falling back to the parse
+/// time; hosts that know the server receipt time (IMAP `INTERNALDATE`) should
+/// overwrite it. Malformed messages are [`ProviderError::Other`].
+pub fn parse_inbound_email(
+    provider: EmailProvider,
+    folder_or_label: &str,
+    raw: &[u8],
+) -> Result<InboundEmail, ProviderError> {
+    parse_message(provider, folder_or_label, raw)
+        .map_err(|err| ProviderError::Other(format!("invalid email message: {err}")))
+}
+
+fn parse_message(
+    provider: EmailProvider,
+    folder_or_label: &str,
+    raw: &[u8],
+) -> Result<InboundEmail, String> {
+    let message = Part::parse(raw);
+    let message_id = message
+        .header("Message-ID")
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| synthesized_message_id(&message));
+    let received_at = message
+        .header("Date")
+        .and_then(parse_date)
+        .unwrap_or_else(Utc::now);
+
+    let mut contents = Contents::default();
+    contents.collect(&message, DEFAULT_CONTENT_TYPE, 0)?;
+    let body = contents
+        .text
+        .or_else(|| contents.html.as_deref().map(html_to_text))
+        .unwrap_or_default();
+
+    let mut headers = BTreeMap::new();
+    for (name, value) in &message.headers {
+        headers
+            .entry(name.clone())
+            .or_insert_with(|| decode_header(value.trim()));
+    }
+
+    let addresses = |name: &str| {
+        message
+            .header(name)
+            .map(parse_addresses)
+            .unwrap_or_default()
+    };
+    Ok(InboundEmail {
+        provider,
+        folder_or_label: folder_or_label.to_string(),
+        message_id,
+        subject: message
+            .header("Subject")
+            .map(decode_header)
+            .unwrap_or_default(),
+        from: addresses("From").into_iter().next().unwrap_or_default(),
+        to: addresses("To"),
+        cc: addresses("Cc"),
+        bcc: addresses("Bcc"),
+        received_at,
+        body,
+        html_body: contents.html,
+        attachments: contents.attachments,
+        headers,
+        thread_id: None,
+    })
+}
+
+/// Stand-in `Message-ID` for mail that lacks one: the same `Date`, `From` and body always
+/// give the same id.
+fn synthesized_message_id(message: &Part<'_>) -> String {
+    let mut hasher = Sha256::new();
+    for name in ["Date", "From"] {
+        hasher.update(message.header(name).unwrap_or_default().trim().as_bytes());
+        hasher.update(b"\n");
+    }
+    hasher.update(message.body);
+    format!("<{}@synthesized.invalid>", hex::encode(hasher.finalize()))
+}
+
+/// One entity of the message: the top-level message or a MIME body part.
+struct Part<'a> {
+    headers: Vec<(String, String)>,
+    body: &'a [u8],
+}
+
+impl<'a> Part<'a> {
+    fn parse(raw: &'a [u8]) -> Self {
+        let mut headers: Vec<(String, String)> = Vec::new();
+        let mut offset = 0;
+        for line in raw.split_inclusive(|byte| *byte == b'\n') {
+            offset += line.len();
+            let content = strip_line_break(line);
+            if content.is_empty() {
+                return Self {
+                    headers,
+                    body: &raw[offset..],
+                };
+            }
+            if matches!(content[0], b' ' | b'\t') {
+                if let Some((_, value)) = headers.last_mut() {
+                    value.push_str(&String::from_utf8_lossy(content));
+                }
+                continue;
+            }
+            // Lines without a valid field name (e.g. an mbox `From ` separator) are skipped.
+            let Some(colon) = content.iter().position(|byte| *byte == b':') else {
+                continue;
+            };
+            let name = String::from_utf8_lossy(&content[..colon]);
+            if name.is_empty() || name.contains(|c: char| c.is_ascii_whitespace()) {
+                continue;
+            }
+            headers.push((
+                name.into_owned(),
+                String::from_utf8_lossy(&content[colon + 1..]).into_owned(),
+            ));
+        }
+        Self {
+            headers,
+            body: &raw[raw.len()..],
+        }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(candidate, _)| candidate.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim())
+    }
+}
+
+/// A structured header value such as `Content-Type` or `Content-Disposition`.
+struct ParamHeader {
+    /// Lowercased main value, e.g. `multipart/alternative` or `attachment`.
+    value: String,
+    /// Lowercased parameter names, with RFC 2231 continuations and charsets resolved.
+    params: BTreeMap<String, String>,
+}
+
+impl ParamHeader {
+    fn parse(raw: &str) -> Self {
+        let mut segments = split_unquoted(raw, ';').into_iter();
+        let value = segments
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+
+        let mut params = BTreeMap::new();
+        // name -> (section, encoded, value) for RFC 2231 `name*0*=...` style parameters.
+        let mut extended: BTreeMap<String, Vec<(u32, bool, String)>> = BTreeMap::new();
+        for segment in segments {
+            let Some((name, value)) = segment.split_once('=') else {
+                continue;
+            };
+            let name = name.trim().to_ascii_lowercase();
+            let value = unquote(value.trim());
+            match name.split_once('*') {
+                None => {
+                    params.insert(name, value);
+                }
+                Some((base, suffix)) => {
+                    let encoded = suffix.ends_with('*') || suffix.is_empty();
+                    let section = suffix.trim_end_matches('*').parse().unwrap_or(0);
+                    extended
+                        .entry(base.to_string())
+                        .or_default()
+                        .push((section, encoded, value));
+                }
+            }
+        }
+        for (name, mut sections) in extended {
+            sections.sort_by_key(|(section, _, _)| *section);
+            params.insert(name, join_rfc2231(&sections));
+        }
+        Self { value, params }
+    }
+}
+
+fn join_rfc2231(sections: &[(u32, bool, String)]) -> String {
+    let mut charset = "us-ascii";
+    let mut bytes = Vec::new();
+    for (index, (_, encoded, value)) in sections.iter().enumerate() {
+        let mut value = value.as_str();
+        if index == 0 && *encoded {
+            let mut fields = value.splitn(3, '\'');
+            if let (Some(declared), Some(_language), Some(rest)) =
+                (fields.next(), fields.next(), fields.next())
+            {
+                if !declared.is_empty() {
+                    charset = declared;
+                }
+                value = rest;
+            }
+        }
+        if *encoded {
+            bytes.extend(percent_decode(value.as_bytes()));
+        } else {
+            bytes.extend_from_slice(value.as_bytes());
+        }
+    }
+    decode_charset(&bytes, charset)
+}
+
+/// Body texts and attachments gathered from the MIME tree.
+#[derive(Default)]
+struct Contents {
+    text: Option<String>,
+    html: Option<String>,
+    attachments: Vec<EmailAttachment>,
+}
+
+impl Contents {
+    fn collect(&mut self, part: &Part<'_>, default_type: &str, depth: usize) -> Result<(), String> {
+        if depth > MAX_DEPTH {
+            return Err("MIME parts nested too deeply".into());
+        }
+        let mut content_type =
+            ParamHeader::parse(part.header("Content-Type").unwrap_or(default_type));
+        if !content_type.value.contains('/') {
+            content_type = ParamHeader::parse(DEFAULT_CONTENT_TYPE);
+        }
+
+        if content_type.value.starts_with("multipart/") {
+            let boundary = content_type
+                .params
+                .get("boundary")
+                .filter(|boundary| !boundary.is_empty())
+                .ok_or_else(|| format!("{} part without boundary", content_type.value))?;
+            let child_type = if content_type.value == "multipart/digest" {
+                "message/rfc822"
+            } else {
+                DEFAULT_CONTENT_TYPE
+            };
+            for child in split_multipart(part.body, boundary) {
+                self.collect(&Part::parse(child), child_type, depth + 1)?;
+            }
+            return Ok(());
+        }
+
+        let bytes = decode_transfer(part.body, part.header("Content-Transfer-Encoding"));
+        let disposition = part.header("Content-Disposition").map(ParamHeader::parse);
+        let filename = disposition
+            .as_ref()
+            .and_then(|disposition| disposition.params.get("filename"))
+            .or_else(|| content_type.params.get("name"))
+            .map(|name| decode_header(name));
+        let attachment = filename.is_some()
+            || disposition
+                .as_ref()
+                .is_some_and(|disposition| disposition.value == "attachment");
+        let slot = match content_type.value.as_str() {
+            "text/plain" => Some(&mut self.text),
+            "text/html" => Some(&mut self.html),
+            _ => None,
+        };
+        match slot {
+            Some(slot) if !attachment => {
+                let bytes = bytes?;
+                if slot.is_none() {
+                    let charset = content_type
+                        .params
+                        .get("charset")
+                        .map(String::as_str)
+                        .unwrap_or("us-ascii");
+                    *slot = Some(decode_charset(&bytes, charset).replace("\r\n", "\n"));
+                }
+            }
+            _ => {
+                // One corrupt attachment should not cost the reader the whole message.
+                let Ok(bytes) = bytes else {
+                    return Ok(());
+                };
+                let content_id = part
+                    .header("Content-ID")
+                    .map(|id| id.trim_start_matches('<').trim_end_matches('>').to_string())
+                    .filter(|id| !id.is_empty());
+                let inline = match &disposition {
+                    Some(disposition) => disposition.value == "inline",
+                    None => content_id.is_some(),
+                };
+                self.attachments.push(EmailAttachment {
+                    filename,
+                    content_type: content_type.value,
+                    size: bytes.len() as u64,
+                    content_id,
+                    inline,
+                    content: Some(AttachmentContent::Bytes(bytes)),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Body parts between `--boundary` delimiter lines, without the line break that
+/// belongs to the following delimiter. An unterminated final part is kept.
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{boundary}");
+    let mut parts = Vec::new();
+    let mut start = None;
+    let mut offset = 0;
+    for line in body.split_inclusive(|byte| *byte == b'\n') {
+        let line_start = offset;
+        offset += line.len();
+        let Some(rest) = line.strip_prefix(delimiter.as_bytes()) else {
+            continue;
+        };
+        let rest = rest.trim_ascii_end();
+        let closing = rest.starts_with(b"--");
+        if !closing && !rest.is_empty() {
+            continue;
+        }
+        if let Some(part_start) = start.take() {
+            let mut end = line_start;
+            if end > part_start && body[end - 1] == b'\n' {
+                end -= 1;
+                if end > part_start && body[end - 1] == b'\r' {
+                    end -= 1;
+                }
+            }
+            parts.push(&body[part_start..end]);
+        }
+        if closing {
+            return parts;
+        }
+        start = Some(offset);
+    }
+    if let Some(part_start) = start {
+        parts.push(&body[part_start..]);
+    }
+    parts
+}
+
+fn decode_transfer(body: &[u8], encoding: Option<&str>) -> Result<Vec<u8>, String> {
+    match encoding.map(str::to_ascii_lowercase).as_deref() {
+        Some("base64") => decode_base64(body).map_err(|err| format!("invalid base64 body: {err}")),
+        Some("quoted-printable") => Ok(decode_quoted_printable(body)),
+        _ => Ok(body.to_vec()),
+    }
+}
+
+fn decode_base64(data: &[u8]) -> Result<Vec<u8>, base64::DecodeError> {
+    let cleaned: Vec<u8> = data
+        .iter()
+        .copied()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+    LENIENT_BASE64.decode(cleaned)
+}
+
+fn decode_quoted_printable(body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(body.len());
+    for line in body.split_inclusive(|byte| *byte == b'\n') {
+        let hard_break = line.ends_with(b"\n");
+        // Trailing whitespace is transport padding (RFC 2045 §6.7 rule 3).
+        let content = strip_line_break(line).trim_ascii_end();
+        let (content, soft_break) = match content.strip_suffix(b"=") {
+            Some(content) => (content, true),
+            None => (content, false),
+        };
+        let mut index = 0;
+        while index < content.len() {
+            if content[index] == b'='
+                && let Some(byte) = content.get(index + 1..index + 3).and_then(hex_pair)
+            {
+                out.push(byte);
+                index += 3;
+                continue;
+            }
+            out.push(content[index]);
+            index += 1;
+        }
+        if hard_break && !soft_break {
+            out.extend_from_slice(b"\r\n");
+        }
+    }
+    out
+}
+
+fn decode_charset(bytes: &[u8], charset: &str) -> String {
+    Encoding::for_label(charset.trim().as_bytes())
+        .unwrap_or(UTF_8)
+        .decode(bytes)
+        .0
+        .into_owned()
+}
+
+/// Decode RFC 2047 encoded words in an unstructured header value. Adjacent words
+/// are joined before charset decoding so multi-byte characters may span them.
+pub(crate) fn decode_header(value: &str) -> String {
+    let mut out = String::new();
+    let mut pending: Option<(String, Vec<u8>)> = None;
+    let mut rest = value;
+    while let Some(start) = rest.find("=?") {
+        let Some((charset, bytes, len)) = parse_encoded_word(&rest[start..]) else {
+            flush_encoded(&mut out, &mut pending);
+            out.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            continue;
+        };
+        let before = &rest[..start];
+        if pending.is_none() || !before.chars().all(|c| c.is_ascii_whitespace()) {
+            flush_encoded(&mut out, &mut pending);
+            out.push_str(before);
+        }
+        match &mut pending {
+            Some((current, buffer)) if current.eq_ignore_ascii_case(&charset) => {
+                buffer.extend(bytes)
+            }
+            _ => {
+                flush_encoded(&mut out, &mut pending);
+                pending = Some((charset, bytes));
+            }
+        }
+        rest = &rest[start + len..];
+    }
+    flush_encoded(&mut out, &mut pending);
+    out.push_str(rest);
+    out
+}
+
+fn flush_encoded(out: &mut String, pending: &mut Option<(String, Vec<u8>)>) {
+    if let Some((charset, bytes)) = pending.take() {
+        out.push_str(&decode_charset(&bytes, &charset));
+    }
+}
+
+/// `=?charset[*language]?B|Q?text?=` at the start of `text`, as (charset, bytes, length).
+fn parse_encoded_word(text: &str) -> Option<(String, Vec<u8>, usize)> {
+    let inner = text.strip_prefix("=?")?;
+    let (charset, inner) = inner.split_once('?')?;
+    let (encoding, inner) = inner.split_once('?')?;
+    let end = inner.find("?=")?;
+    let payload = &inner[..end];
+    if charset.is_empty() || charset.contains(|c: char| c.is_ascii_whitespace()) {
+        return None;
+    }
+    if payload.contains(|c: char| c.is_ascii_whitespace()) {
+        return None;
+    }
+    let bytes = match encoding {
+        "B" | "b" => decode_base64(payload.as_bytes()).ok()?,
+        "Q" | "q" => decode_q(payload.as_bytes()),
+        _ => return None,
+    };
+    let len = 2 + charset.len() + 1 + encoding.len() + 1 + end + 2;
+    let charset = charset.split('*').next().unwrap_or(charset);
+    Some((charset.to_string(), bytes, len))
+}
+
+fn decode_q(payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len());
+    let mut index = 0;
+    while index < payload.len() {
+        match payload[index] {
+            b'_' => out.push(b' '),
+            b'=' => {
+                if let Some(byte) = payload.get(index + 1..index + 3).and_then(hex_pair) {
+                    out.push(byte);
+                    index += 3;
+                    continue;
+                }
+                out.push(b'=');
+            }
+            byte => out.push(byte),
+        }
+        index += 1;
+    }
+    out
+}
+
+/// Bare addresses from an address-list header: display names, comments, group
+/// names and source routes are dropped.
+fn parse_addresses(value: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    let mut plain = String::new();
+    let mut angled: Option<String> = None;
+    let mut in_angle = false;
+    let mut in_quote = false;
+    let mut comment_depth = 0usize;
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if comment_depth > 0 {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '(' => comment_depth += 1,
+                ')' => comment_depth -= 1,
+                _ => {}
+            }
+        } else if in_quote {
+            plain.push(c);
+            match c {
+                '\\' => plain.extend(chars.next()),
+                '"' => in_quote = false,
+                _ => {}
+            }
+        } else if in_angle {
+            match c {
+                '>' => in_angle = false,
+                _ => angled.get_or_insert_with(String::new).push(c),
+            }
+        } else {
+            match c {
+                '"' => {
+                    in_quote = true;
+                    plain.push(c);
+                }
+                '(' => comment_depth = 1,
+                '<' => {
+                    in_angle = true;
+                    angled = Some(String::new());
+                }
+                ':' => plain.clear(),
+                ',' | ';' => push_address(&mut found, &mut plain, &mut angled),
+                _ => plain.push(c),
+            }
+        }
+    }
+    push_address(&mut found, &mut plain, &mut angled);
+    found
+}
+
+fn push_address(found: &mut Vec<String>, plain: &mut String, angled: &mut Option<String>) {
+    let text = angled.take().unwrap_or_else(|| plain.clone());
+    plain.clear();
+    let text = text.trim();
+    let address = match text.strip_prefix('@') {
+        Some(_) => text.rsplit(':').next().unwrap_or_default().trim(),
+        None => text,
+    };
+    if !address.is_empty() {
+        found.push(address.to_string());
+    }
+}
+
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    // Drop a trailing zone comment such as `(UTC)` that some mailers append.
+    let value = match value.rfind('(') {
+        Some(index) if value.ends_with(')') => value[..index].trim_end(),
+        _ => value,
+    };
+    DateTime::parse_from_rfc2822(value)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+fn split_unquoted(value: &str, separator: char) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut in_quote = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (index, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quote => escaped = true,
+            '"' => in_quote = !in_quote,
+            _ if c == separator && !in_quote => {
+                segments.push(&value[start..index]);
+                start = index + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    segments.push(&value[start..]);
+    segments
+}
+
+fn unquote(value: &str) -> String {
+    let Some(inner) = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    else {
+        return value.to_string();
+    };
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.extend(chars.next()),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn percent_decode(value: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(value.len());
+    let mut index = 0;
+    while index < value.len() {
+        if value[index] == b'%'
+            && let Some(byte) = value.get(index + 1..index + 3).and_then(hex_pair)
+        {
+            out.push(byte);
+            index += 3;
+            continue;
+        }
+        out.push(value[index]);
+        index += 1;
+    }
+    out
+}
+
+fn hex_pair(pair: &[u8]) -> Option<u8> {
+    let digit = |byte: u8| (byte as char).to_digit(16);
+    Some((digit(pair[0])? * 16 + digit(pair[1])?) as u8)
+}
+
+fn strip_line_break(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALTERNATIVE: &[u8] = b"Return-Path: <bounce@example.com>\r
+Message-ID: <CAF=abc123@mail.example.com>\r
+Date: Tue, 05 Mar 2024 09:30:00 +0100 (CET)\r
+From: =?UTF-8?Q?Ren=C3=A9e_M=C3=BCller?= <renee@example.com>\r
+To: \"Ops, Team\" <ops@example.com>, dev@example.com (Dev list),\r
+ Undisclosed: ;\r
+Cc: Friends: ann@example.com, <@relay.example.com:bob@example.com>;\r
+Subject: =?UTF-8?B?w5xiZXI=?= =?UTF-8?B?c2ljaHQg4oKs?=\r
+ report\r
+X-Custom: =?iso-8859-1?q?caf=E9?=\r
+MIME-Version: 1.0\r
+Content-Type: multipart/alternative; boundary=\"b1=_part\"\r
+\r
+This is a multi-part message in MIME format.\r
+--b1=_part\r
+Content-Type: text/plain; charset=\"utf-8\"\r
+Content-Transfer-Encoding: quoted-printable\r
+\r
+Gr=C3=BC=C3=9Fe aus M=C3=BCnchen, this line is long enough that it is soft=\r
+ wrapped.   \r
+Total: 5 =E2=82=AC\r
+--b1=_part\r
+Content-Type: text/html; charset=utf-8\r
+Content-Transfer-Encoding: base64\r
+\r
+PHA+R3LDvMOfZTwvcD4=\r
+--b1=_part--\r
+epilogue\r
+";
+
+    #[test]
+    fn parses_multipart_alternative_message() {
+        let email = parse_inbound_email(EmailProvider::Gmail, "INBOX", ALTERNATIVE).unwrap();
+        assert_eq!(email.message_id, "<CAF=abc123@mail.example.com>");
+        assert_eq!(email.subject, "Übersicht € report");
+        assert_eq!(email.from, "renee@example.com");
+        assert_eq!(email.to, ["ops@example.com", "dev@example.com"]);
+        assert_eq!(email.cc, ["ann@example.com", "bob@example.com"]);
+        assert!(email.bcc.is_empty());
+        assert_eq!(
+            email.received_at,
+            DateTime::parse_from_rfc3339("2024-03-05T08:30:00Z").unwrap()
+        );
+        assert_eq!(
+            email.body,
+            "Grüße aus München, this line is long enough that it is soft wrapped.\nTotal: 5 €"
+        );
+        assert_eq!(email.html_body.as_deref(), Some("<p>Grüße</p>"));
+        assert_eq!(email.headers["From"], "Renée Müller <renee@example.com>");
+        assert_eq!(email.headers["X-Custom"], "café");
+        assert_eq!(email.headers["Return-Path"], "<bounce@example.com>");
+
+        let event = crate::map_inbound_email(crate::tests::sample_tenant(), &email);
+        assert_eq!(event.topic, "email.in.gmail.INBOX");
+        assert_eq!(event.payload["html_body"], "<p>Grüße</p>");
+    }
+
+    #[test]
+    fn walks_nested_parts_and_skips_attachments() {
+        let raw = b"Message-ID: <nested@example.com>\n\
+Subject: =?windows-1252?Q?Invoice_=96_March?=\n\
+From: billing@example.com\n\
+To: customer@example.com\n\
+Content-Type: multipart/mixed; boundary=outer\n\
+\n\
+--outer\n\
+Content-Type: text/plain; name=\"notes.txt\"\n\
+Content-Disposition: attachment; filename*=utf-8''n%C3%B6tes.txt\n\
+\n\
+attached text, not the body\n\
+--outer\n\
+Content-Type: multipart/related; boundary=\"inner\"\n\
+\n\
+--inner\n\
+Content-Type: text/html; charset=iso-8859-1\n\
+Content-Transfer-Encoding: 8bit\n\
+\n\
+<p>Fa\xe7ade</p>\n\
+--inner\n\
+Content-Type: image/png\n\
+Content-ID: <logo@example.com>\n\
+Content-Transfer-Encoding: base64\n\
+\n\
+iVBORw0KGgo=\n\
+--inner--\n\
+--outer--\n";
+        let email = parse_inbound_email(EmailProvider::MsGraph, "inbox", raw).unwrap();
+        assert_eq!(email.subject, "Invoice – March");
+        assert_eq!(email.html_body.as_deref(), Some("<p>Façade</p>"));
+        assert_eq!(email.body, "Façade");
+
+        assert_eq!(email.attachments.len(), 2);
+        let notes = &email.attachments[0];
+        assert_eq!(notes.filename.as_deref(), Some("nötes.txt"));
+        assert_eq!(notes.content_type, "text/plain");
+        assert!(!notes.inline);
+        assert_eq!(
+            notes.content,
+            Some(AttachmentContent::Bytes(
+                b"attached text, not the body".to_vec()
+            ))
+        );
+        let logo = &email.attachments[1];
+        assert_eq!(logo.filename, None);
+        assert_eq!(logo.content_id.as_deref(), Some("logo@example.com"));
+        assert!(logo.inline);
+        assert_eq!(logo.size, 8);
+    }
+
+    #[test]
+    fn plain_messages_default_to_text() {
+        let raw =
+            b"Message-ID: <plain@example.com>\r\nFrom: a@example.com\r\n\r\nHello\r\nthere\r\n";
+        let email = parse_inbound_email(EmailProvider::Gmail, "INBOX", raw).unwrap();
+        assert_eq!(email.body, "Hello\nthere\n");
+        assert_eq!(email.html_body, None);
+        assert_eq!(email.subject, "");
+        assert!(email.to.is_empty());
+
+        let no_boundary = parse_inbound_email(
+            EmailProvider::Gmail,
+            "INBOX",
+            b"Message-ID: <x@y>\r\nContent-Type: multipart/mixed\r\n\r\n",
+        );
+        assert!(matches!(no_boundary, Err(ProviderError::Other(msg)) if msg.contains("boundary")));
+    }
+
+    #[test]
+    fn synthesizes_stable_message_ids() {
+        let id = |raw: &[u8]| {
+            parse_inbound_email(EmailProvider::Gmail, "INBOX", raw)
+                .unwrap()
+                .message_id
+        };
+        let raw = b"Date: Tue, 5 Mar 2024 08:30:00 +0000\r\nFrom: a@example.com\r\n\
+Subject: hi\r\n\r\nbody\r\n";
+        let synthesized = id(raw);
+        assert!(synthesized.starts_with('<'), "{synthesized}");
+        assert!(
+            synthesized.ends_with("@synthesized.invalid>"),
+            "{synthesized}"
+        );
+        assert_eq!(id(raw), synthesized);
+        // Only Date, From and the body feed the id.
+        assert_eq!(
+            id(
+                b"Date: Tue, 5 Mar 2024 08:30:00 +0000\r\nFrom: a@example.com\r\n\
+Subject: re-sent\r\n\r\nbody\r\n"
+            ),
+            synthesized
+        );
+        assert_ne!(
+            id(b"Date: Tue, 5 Mar 2024 08:30:00 +0000\r\nFrom: a@example.com\r\n\r\nother\r\n"),
+            synthesized
+        );
+        assert_ne!(
+            id(b"Date: Tue, 5 Mar 2024 08:31:00 +0000\r\nFrom: a@example.com\r\n\r\nbody\r\n"),
+            synthesized
+        );
+    }
+
+    #[test]
+    fn skips_attachments_that_do_not_decode() {
+        let raw = b"Message-ID: <broken@example.com>\n\
+Content-Type: multipart/mixed; boundary=b\n\
+\n\
+--b\n\
+Content-Type: text/plain\n\
+\n\
+See attached.\n\
+--b\n\
+Content-Type: application/pdf; name=broken.pdf\n\
+Content-Transfer-Encoding: base64\n\
+\n\
+%%% not base64 %%%\n\
+--b\n\
+Content-Type: text/csv; name=ok.csv\n\
+Content-Transfer-Encoding: base64\n\
+\n\
+YSxiCg==\n\
+--b--\n";
+        let email = parse_inbound_email(EmailProvider::Gmail, "INBOX", raw).unwrap();
+        assert_eq!(email.body, "See attached.");
+        assert_eq!(email.attachments.len(), 1);
+        assert_eq!(email.attachments[0].filename.as_deref(), Some("ok.csv"));
+        assert_eq!(
+            email.attachments[0].content,
+            Some(AttachmentContent::Bytes(b"a,b\n".to_vec()))
+        );
+
+        let broken_body =
+            b"Message-ID: <b@example.com>\nContent-Transfer-Encoding: base64\n\n%%%\n";
+        assert!(matches!(
+            parse_inbound_email(EmailProvider::Gmail, "INBOX", broken_body),
+            Err(ProviderError::Other(_))
+        ));
+    }
+
+    #[test]
+    fn decodes_encoded_words() {
+        assert_eq!(decode_header("plain text"), "plain text");
+        assert_eq!(decode_header("=?ISO-8859-1?Q?a?= b"), "a b");
+        assert_eq!(
+            decode_header("=?ISO-8859-1?Q?a?=  \t =?ISO-8859-1?Q?b?="),
+            "ab"
+        );
+        assert_eq!(decode_header("=?UTF-8?Q?a?= =?ISO-8859-1?Q?=E9?="), "aé");
+        // A UTF-8 sequence split across two words.
+        assert_eq!(decode_header("=?UTF-8?Q?=E2=82?= =?UTF-8?Q?=AC?="), "€");
+        assert_eq!(decode_header("=?UTF-8*en?Q?hi?="), "hi");
+        assert_eq!(decode_header("price =? 5"), "price =? 5");
+        assert_eq!(decode_header("=?UTF-8?X?abc?="), "=?UTF-8?X?abc?=");
+    }
+
+    #[test]
+    fn resolves_rfc2231_parameters() {
+        let header = ParamHeader::parse(
+            "attachment; filename*0*=UTF-8''%E2%82%AC%20rates; filename*1=\".pdf\"; size=10",
+        );
+        assert_eq!(header.value, "attachment");
+        assert_eq!(header.params["filename"], "€ rates.pdf");
+        assert_eq!(header.params["size"], "10");
+    }
+}
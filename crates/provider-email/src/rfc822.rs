@@ -0,0 +1,397 @@
+use crate::EmailAttachment;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{DateTime, Utc};
+use provider_core::ProviderError;
+use std::fmt::Write;
+
+/// Encoded line length limit for quoted-printable and base64 bodies (RFC 2045).
+const LINE_LIMIT: usize = 76;
+/// Soft limit for header lines before folding (RFC 5322 §2.1.1).
+const HEADER_LIMIT: usize = 78;
+/// Raw bytes per RFC 2047 encoded word, keeping each word under 75 characters.
+const WORD_BYTES: usize = 45;
+
+/// Outgoing message rendered as RFC 5322/MIME with CRLF line endings.
+///
+/// Text bodies are always quoted-printable and attachments base64, so the fixed
+/// `=_<subtype>` boundaries can never occur inside an encoded part. `Message-ID`
+/// is left for the sending service to assign.
+#[derive(Debug, Default)]
+pub(crate) struct Rfc822Message<'a> {
+    pub from: Option<&'a str>,
+    pub to: &'a [String],
+    pub cc: &'a [String],
+    pub bcc: &'a [String],
+    pub subject: &'a str,
+    pub date: Option<DateTime<Utc>>,
+    pub in_reply_to: Option<&'a str>,
+    pub references: &'a [String],
+    pub text: Option<&'a str>,
+    pub html: Option<&'a str>,
+    pub attachments: &'a [EmailAttachment],
+}
+
+impl Rfc822Message<'_> {
+    pub fn render(&self) -> Result<Vec<u8>, ProviderError> {
+        let mut out = String::new();
+        if let Some(date) = self.date {
+            push_header(&mut out, "Date", &date.to_rfc2822());
+        }
+        if let Some(from) = self.from {
+            push_addresses(&mut out, "From", &[from]);
+        }
+        push_addresses(&mut out, "To", self.to);
+        push_addresses(&mut out, "Cc", self.cc);
+        push_addresses(&mut out, "Bcc", self.bcc);
+        push_header(&mut out, "Subject", &subject_value(&clean(self.subject)));
+        if let Some(in_reply_to) = self.in_reply_to {
+            push_header(&mut out, "In-Reply-To", &clean(in_reply_to));
+        }
+        if !self.references.is_empty() {
+            let references: Vec<_> = self.references.iter().map(|id| clean(id)).collect();
+            push_header(&mut out, "References", &references.join("\r\n "));
+        }
+        out.push_str("MIME-Version: 1.0\r\n");
+        out.push_str(&self.body()?);
+        Ok(out.into_bytes())
+    }
+
+    /// `mixed[related[alternative[text, html], inline...], attachments...]`, with
+    /// each level only present when it has something to wrap.
+    fn body(&self) -> Result<String, ProviderError> {
+        let mut entity = match (self.text, self.html) {
+            (Some(text), Some(html)) => multipart(
+                "alternative",
+                vec![text_part("plain", text), text_part("html", html)],
+            ),
+            (None, Some(html)) => text_part("html", html),
+            (text, None) => text_part("plain", text.unwrap_or_default()),
+        };
+        let (inline, attached): (Vec<_>, Vec<_>) = self
+            .attachments
+            .iter()
+            .partition(|attachment| attachment.inline);
+        for (subtype, attachments) in [("related", inline), ("mixed", attached)] {
+            if attachments.is_empty() {
+                continue;
+            }
+            let mut parts = vec![entity];
+            for attachment in attachments {
+                parts.push(attachment_part(attachment)?);
+            }
+            entity = multipart(subtype, parts);
+        }
+        Ok(entity)
+    }
+}
+
+fn multipart(subtype: &str, parts: Vec<String>) -> String {
+    let boundary = format!("=_{subtype}");
+    let mut out = format!("Content-Type: multipart/{subtype}; boundary=\"{boundary}\"\r\n\r\n");
+    for part in parts {
+        let _ = write!(out, "--{boundary}\r\n{part}\r\n");
+    }
+    let _ = write!(out, "--{boundary}--\r\n");
+    out
+}
+
+fn text_part(subtype: &str, text: &str) -> String {
+    format!(
+        "Content-Type: text/{subtype}; charset=utf-8\r\n\
+         Content-Transfer-Encoding: quoted-printable\r\n\r\n{}",
+        quoted_printable(text)
+    )
+}
+
+fn attachment_part(attachment: &EmailAttachment) -> Result<String, ProviderError> {
+    let bytes = attachment.bytes()?;
+    let mut content_type = clean(&attachment.content_type);
+    let disposition = if attachment.inline {
+        "inline"
+    } else {
+        "attachment"
+    };
+    let mut out = String::new();
+    match attachment.filename.as_deref().map(clean) {
+        Some(name) if is_plain_ascii(&name) => {
+            let name = name.replace('\\', "\\\\").replace('"', "\\\"");
+            let _ = write!(content_type, "; name=\"{name}\"");
+            push_header(&mut out, "Content-Type", &content_type);
+            push_header(
+                &mut out,
+                "Content-Disposition",
+                &format!("{disposition}; filename=\"{name}\""),
+            );
+        }
+        Some(name) => {
+            // RFC 2231 for the standard parameter, RFC 2047 for clients that only read `name`.
+            let _ = write!(content_type, ";\r\n name=\"{}\"", encode_words(&name));
+            push_header(&mut out, "Content-Type", &content_type);
+            push_header(
+                &mut out,
+                "Content-Disposition",
+                &format!(
+                    "{disposition};\r\n filename*=utf-8''{}",
+                    percent_encode(&name)
+                ),
+            );
+        }
+        None => {
+            push_header(&mut out, "Content-Type", &content_type);
+            push_header(&mut out, "Content-Disposition", disposition);
+        }
+    }
+    if let Some(content_id) = &attachment.content_id {
+        push_header(&mut out, "Content-ID", &format!("<{}>", clean(content_id)));
+    }
+    out.push_str("Content-Transfer-Encoding: base64\r\n\r\n");
+    let encoded = STANDARD.encode(bytes);
+    let lines: Vec<_> = encoded
+        .as_bytes()
+        .chunks(LINE_LIMIT)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    out.push_str(&lines.join("\r\n"));
+    Ok(out)
+}
+
+fn push_header(out: &mut String, name: &str, value: &str) {
+    let _ = write!(out, "{name}: {value}\r\n");
+}
+
+/// Comma-separated address list, folded before lines pass [`HEADER_LIMIT`].
+fn push_addresses(out: &mut String, name: &str, addresses: &[impl AsRef<str>]) {
+    if addresses.is_empty() {
+        return;
+    }
+    let mut value = String::new();
+    let mut width = name.len() + 2;
+    for (index, address) in addresses.iter().enumerate() {
+        let address = format_address(address.as_ref());
+        if index > 0 {
+            if width + 2 + address.len() > HEADER_LIMIT {
+                value.push_str(",\r\n ");
+                width = 1;
+            } else {
+                value.push_str(", ");
+                width += 2;
+            }
+        }
+        width += address.len();
+        value.push_str(&address);
+    }
+    push_header(out, name, &value);
+}
+
+/// `Display Name <addr>` with a non-ASCII display name gets it RFC 2047 encoded.
+fn format_address(address: &str) -> String {
+    let address = clean(address);
+    if is_plain_ascii(&address) {
+        return address;
+    }
+    match address.rsplit_once('<') {
+        Some((name, addr)) if addr.ends_with('>') && !name.trim().is_empty() => {
+            let name = name.trim().trim_matches('"');
+            format!("{} <{addr}", encode_words(name))
+        }
+        _ => address,
+    }
+}
+
+/// Subject text, folded at spaces before lines pass [`HEADER_LIMIT`] when it is
+/// plain ASCII and RFC 2047 encoded otherwise.
+fn subject_value(text: &str) -> String {
+    if !is_plain_ascii(text) || text.contains("=?") {
+        return encode_words(text);
+    }
+    let mut value = String::new();
+    let mut width = "Subject: ".len();
+    for (index, word) in text.split(' ').enumerate() {
+        if index > 0 {
+            // Never fold before an empty word: a whitespace-only line is not allowed.
+            if !word.is_empty() && width + 1 + word.len() > HEADER_LIMIT {
+                value.push_str("\r\n ");
+                width = 1;
+            } else {
+                value.push(' ');
+                width += 1;
+            }
+        }
+        width += word.len();
+        value.push_str(word);
+    }
+    value
+}
+
+/// RFC 2047 `B` encoded words for non-ASCII text, folded between words.
+fn encode_words(text: &str) -> String {
+    if is_plain_ascii(text) && !text.contains("=?") {
+        return text.to_string();
+    }
+    let mut words = Vec::new();
+    let mut chunk = String::new();
+    for c in text.chars() {
+        if chunk.len() + c.len_utf8() > WORD_BYTES {
+            words.push(format!("=?utf-8?B?{}?=", STANDARD.encode(&chunk)));
+            chunk.clear();
+        }
+        chunk.push(c);
+    }
+    if !chunk.is_empty() {
+        words.push(format!("=?utf-8?B?{}?=", STANDARD.encode(&chunk)));
+    }
+    words.join("\r\n ")
+}
+
+fn quoted_printable(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            out.push_str("\r\n");
+        }
+        let bytes = line.strip_suffix('\r').unwrap_or(line).as_bytes();
+        let mut width = 0;
+        for (position, byte) in bytes.iter().copied().enumerate() {
+            let trailing = position + 1 == bytes.len();
+            let literal = matches!(byte, b'!'..=b'<' | b'>'..=b'~')
+                || (matches!(byte, b' ' | b'\t') && !trailing);
+            let token = if literal { 1 } else { 3 };
+            // Leave room for the `=` of a soft line break.
+            if width + token > LINE_LIMIT - 1 {
+                out.push_str("=\r\n");
+                width = 0;
+            }
+            if literal {
+                out.push(byte as char);
+            } else {
+                let _ = write!(out, "={byte:02X}");
+            }
+            width += token;
+        }
+    }
+    out
+}
+
+fn percent_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            out.push(byte as char);
+        } else {
+            let _ = write!(out, "%{byte:02X}");
+        }
+    }
+    out
+}
+
+fn is_plain_ascii(text: &str) -> bool {
+    text.bytes().all(|byte| matches!(byte, b' '..=b'~'))
+}
+
+/// Header values must not carry line breaks of their own (header injection).
+fn clean(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AttachmentContent, parse_inbound_email};
+
+    #[test]
+    fn round_trips_alternative_reply_with_attachments() {
+        let to = ["Zoë Ops <ops@example.com>".to_string()];
+        let cc = [
+            "first.long.address@example.com".to_string(),
+            "second.long.address@example.com".to_string(),
+            "third@example.com".to_string(),
+        ];
+        let references = [
+            "<root@mail.example.com>".to_string(),
+            "<parent@mail.example.com>".to_string(),
+        ];
+        let mut logo = EmailAttachment::from_bytes("logo.png", "image/png", vec![0x89, b'P']);
+        logo.content_id = Some("logo@greentic".into());
+        logo.inline = true;
+        let attachments = [
+            logo,
+            EmailAttachment::from_bytes("Prüfbericht 2024.pdf", "application/pdf", b"%PDF".into()),
+        ];
+        let message = Rfc822Message {
+            from: Some("Greentic <noreply@example.com>"),
+            to: &to,
+            cc: &cc,
+            subject: "Re: Übersicht der Wartungsfenster für März und April 2024",
+            date: DateTime::from_timestamp(1_709_627_400, 0),
+            in_reply_to: Some("<parent@mail.example.com>"),
+            references: &references,
+            text: Some("Hallo Zoë,\n\nthe = sign and trailing space \nare both encoded.\n"),
+            html: Some("<p>Hallo Zoë,</p>\r\n<p><img src=\"cid:logo@greentic\"></p>"),
+            attachments: &attachments,
+            ..Default::default()
+        };
+        let rendered = message.render().expect("render");
+        let parsed = parse_inbound_email(crate::EmailProvider::Gmail, "SENT", &rendered)
+            .expect("parse rendered message");
+        assert_eq!(parsed.subject, message.subject);
+        assert_eq!(parsed.to, ["ops@example.com"]);
+        assert_eq!(parsed.body, message.text.unwrap());
+        assert_eq!(
+            parsed.html_body.as_deref(),
+            Some("<p>Hallo Zoë,</p>\n<p><img src=\"cid:logo@greentic\"></p>")
+        );
+        assert_eq!(
+            parsed.attachments[0].content_id.as_deref(),
+            Some("logo@greentic")
+        );
+        assert_eq!(
+            parsed.attachments[1].filename.as_deref(),
+            Some("Prüfbericht 2024.pdf")
+        );
+        assert_eq!(
+            parsed.attachments[1].content,
+            Some(AttachmentContent::Bytes(b"%PDF".to_vec()))
+        );
+    }
+
+    #[test]
+    fn strips_header_line_breaks_and_wraps_quoted_printable() {
+        let to = ["a@example.com".to_string()];
+        let message = Rfc822Message {
+            to: &to,
+            subject: "hi\r\nBcc: victim@example.com",
+            text: Some(&"x".repeat(200)),
+            ..Default::default()
+        };
+        let rendered = String::from_utf8(message.render().unwrap()).unwrap();
+        assert!(rendered.contains("Subject: hi  Bcc: victim@example.com\r\n"));
+        assert!(!rendered.contains("\r\nBcc:"));
+        let body = rendered.split("\r\n\r\n").nth(1).unwrap();
+        assert!(body.split("\r\n").all(|line| line.len() <= LINE_LIMIT));
+        assert_eq!(body.replace("=\r\n", ""), "x".repeat(200));
+    }
+
+    #[test]
+    fn folds_long_ascii_subjects() {
+        let to = ["a@example.com".to_string()];
+        let subject = "Nightly maintenance report for the production cluster: all 12 jobs passed, \
+                       2 warnings and no failures";
+        let message = Rfc822Message {
+            to: &to,
+            subject,
+            text: Some("ok"),
+            ..Default::default()
+        };
+        let rendered = message.render().unwrap();
+        let text = String::from_utf8(rendered.clone()).unwrap();
+        let headers = text.split("\r\n\r\n").next().unwrap();
+        assert!(headers.split("\r\n").all(|line| line.len() <= HEADER_LIMIT));
+        assert!(headers.contains(
+            "Subject: Nightly maintenance report for the production cluster: all 12 jobs\r\n passed,"
+        ));
+
+        let parsed = parse_inbound_email(crate::EmailProvider::Gmail, "SENT", &rendered).unwrap();
+        assert_eq!(parsed.subject, subject);
+    }
+}
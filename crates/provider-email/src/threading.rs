@@ -0,0 +1,94 @@
+use crate::InboundEmail;
+use serde::{Deserialize, Serialize};
+
+/// The message an outbound email replies to, as captured from an inbound event's
+/// `reply_reference` payload field.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReplyReference {
+    /// Provider message id (MS Graph message id, Gmail message id, or the
+    /// `Message-ID` header for parsed raw messages).
+    pub message_id: String,
+    /// RFC 5322 `Message-ID` of the original, used for `In-Reply-To`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub internet_message_id: Option<String>,
+    /// `References` of the original, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+    /// Provider thread id (Gmail `threadId`, MS Graph `conversationId`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
+}
+
+impl ReplyReference {
+    pub fn from_inbound(email: &InboundEmail) -> Self {
+        Self {
+            message_id: email.message_id.clone(),
+            internet_message_id: internet_message_id(email),
+            references: header(email, "References")
+                .map(message_ids)
+                .unwrap_or_default(),
+            thread_id: email.thread_id.clone(),
+        }
+    }
+
+    /// `References` for the reply: the original's chain plus the original itself.
+    pub(crate) fn reply_references(&self) -> Vec<String> {
+        let mut references = self.references.clone();
+        if let Some(id) = &self.internet_message_id
+            && !references.contains(id)
+        {
+            references.push(id.clone());
+        }
+        references
+    }
+}
+
+/// Thread id for an inbound email: the provider's own when the host supplied one,
+/// otherwise the root of its `References`/`In-Reply-To` chain, so every message of
+/// a conversation maps to the same id.
+pub(crate) fn thread_id(email: &InboundEmail) -> String {
+    if let Some(thread_id) = &email.thread_id {
+        return thread_id.clone();
+    }
+    ["References", "In-Reply-To"]
+        .into_iter()
+        .filter_map(|name| header(email, name))
+        .find_map(|value| message_ids(value).into_iter().next())
+        .or_else(|| internet_message_id(email))
+        .unwrap_or_else(|| email.message_id.clone())
+}
+
+fn internet_message_id(email: &InboundEmail) -> Option<String> {
+    header(email, "Message-ID")
+        .and_then(|value| message_ids(value).into_iter().next())
+        .or_else(|| {
+            email
+                .message_id
+                .starts_with('<')
+                .then(|| email.message_id.clone())
+        })
+}
+
+fn header<'a>(email: &'a InboundEmail, name: &str) -> Option<&'a str> {
+    email
+        .headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// `<id>` tokens of a `Message-ID`/`References`/`In-Reply-To` value; some mailers
+/// separate them with commas or omit the brackets.
+fn message_ids(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+        .map(|token| {
+            if token.starts_with('<') {
+                token.to_string()
+            } else {
+                format!("<{token}>")
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,117 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE;
+use chrono::DateTime;
+use provider_core::secrets::StaticSecretProvider;
+use provider_email::{EmailProvider, build_send_request};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+fn sample_tenant() -> greentic_types::TenantCtx {
+    use greentic_types::{EnvId, TenantCtx, TenantId};
+
+    TenantCtx::new(
+        EnvId::try_from("dev").unwrap(),
+        TenantId::try_from("acme").unwrap(),
+    )
+}
+
+fn gmail_event(payload: Value) -> greentic_types::EventEnvelope {
+    greentic_types::EventEnvelope {
+        id: greentic_types::EventId::new("evt-gmail-1").unwrap(),
+        topic: "email.out.gmail".into(),
+        r#type: "com.greentic.email.generic.v1".into(),
+        source: "flow".into(),
+        tenant: sample_tenant(),
+        subject: None,
+        time: DateTime::from_timestamp(1_709_627_400, 0).unwrap(),
+        correlation_id: None,
+        payload,
+        metadata: BTreeMap::new(),
+    }
+}
+
+/// Decode the `raw` field and compare it against `fixtures/email/<name>`, which is
+/// stored with LF line endings; `UPDATE_GOLDEN=1` rewrites the file instead.
+fn assert_raw_golden(payload: &Value, name: &str) {
+    let raw = URL_SAFE
+        .decode(payload["raw"].as_str().expect("raw field"))
+        .expect("base64url raw message");
+    let text = String::from_utf8(raw).expect("utf-8 message");
+    assert!(
+        !text.replace("\r\n", "").contains('\n'),
+        "bare LF in raw message"
+    );
+    let text = text.replace("\r\n", "\n");
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../fixtures/email")
+        .join(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, &text).expect("write golden file");
+    }
+    let golden = std::fs::read_to_string(&path).expect("read golden file");
+    assert_eq!(text, golden, "{name}");
+}
+
+#[test]
+fn gmail_send_payload_is_raw_rfc822() {
+    let event = gmail_event(json!({
+        "to": ["Jürgen Ops <ops@example.com>", "dev@example.com"],
+        "cc": ["audit@example.com"],
+        "bcc": ["archive@example.com"],
+        "from": "noreply@example.com",
+        "subject": "Nightly report – 5 March",
+        "html": "<h1>Report</h1>\n<p>All 12 jobs passed.</p>",
+        "attachments": [
+            {"filename": "report.csv", "content_type": "text/csv", "size": 22,
+             "content": {"bytes": "am9iLHN0YXR1cwpuaWdodGx5LG9rCg=="}}
+        ]
+    }));
+    let secrets = StaticSecretProvider::empty();
+    let request = build_send_request(&event, &secrets).expect("request");
+    assert_eq!(request.provider, EmailProvider::Gmail);
+    let object = request.payload.as_object().unwrap();
+    assert_eq!(object.keys().collect::<Vec<_>>(), ["raw"]);
+    assert!(!object["raw"].as_str().unwrap().contains(['+', '/']));
+    assert_raw_golden(&request.payload, "gmail_send.eml");
+}
+
+#[test]
+fn gmail_text_only_payload_is_single_part() {
+    let event = gmail_event(json!({
+        "to": ["ops@example.com"],
+        "subject": "Disk usage",
+        "text": "Volume /data is 91% full.\nCleanup runs at 03:00.\n",
+    }));
+    let request = build_send_request(&event, &StaticSecretProvider::empty()).expect("request");
+    assert_raw_golden(&request.payload, "gmail_send_text.eml");
+}
+
+#[test]
+fn gmail_reply_payload_is_multipart_with_inline_parts() {
+    let event = gmail_event(json!({
+        "to": ["Zoë Ops <ops@example.com>"],
+        "cc": [
+            "first.long.address@example.com",
+            "second.long.address@example.com",
+            "third@example.com"
+        ],
+        "from": "Greentic <noreply@example.com>",
+        "subject": "Re: Übersicht der Wartungsfenster für März und April 2024",
+        "text": "Hallo Zoë,\n\nthe = sign and trailing space \nare both encoded.\n",
+        "html": "<p>Hallo Zoë,</p>\r\n<p><img src=\"cid:logo@greentic\"></p>",
+        "attachments": [
+            {"filename": "logo.png", "content_type": "image/png", "size": 2,
+             "content_id": "logo@greentic", "inline": true, "content": {"bytes": "iVA="}},
+            {"filename": "Prüfbericht 2024.pdf", "content_type": "application/pdf", "size": 4,
+             "content": {"bytes": "JVBERg=="}}
+        ],
+        "in_reply_to": {
+            "message_id": "msg-parent",
+            "internet_message_id": "<parent@mail.example.com>",
+            "references": ["<root@mail.example.com>"]
+        }
+    }));
+    let request = build_send_request(&event, &StaticSecretProvider::empty()).expect("request");
+    assert_raw_golden(&request.payload, "alternative_reply.eml");
+}
@@ -116,7 +116,10 @@ fn live_msgraph_integration_smoke() -> Result<(), Box<dyn Error>> {
         bcc: vec![],
         received_at: chrono::Utc::now(),
         body: "This is a live smoke test".into(),
+        html_body: None,
+        attachments: vec![],
         headers: BTreeMap::new(),
+        thread_id: None,
     };
     let event = map_inbound_email(sample_tenant(), &inbound);
     assert!(event.topic.starts_with("email.in.msgraph"));
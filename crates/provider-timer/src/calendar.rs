@@ -0,0 +1,198 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use provider_core::{BusinessCalendar, CalendarSource, ProviderError};
+use std::collections::BTreeSet;
+
+use crate::ical::{Event, parse_events};
+use crate::trigger::Trigger;
+
+/// Days searched for an allowed fire before a calendar is considered exhausted.
+const SEARCH_DAYS: i64 = 100 * 366;
+
+/// A schedule's [`BusinessCalendar`], parsed and bound to the schedule's timezone.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Calendar {
+    timezone: Tz,
+    include: Vec<Days>,
+    exclude: Vec<Days>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Days {
+    Dates(BTreeSet<NaiveDate>),
+    Events(Vec<Event>),
+}
+
+impl Days {
+    fn compile(source: &CalendarSource) -> Result<Self, ProviderError> {
+        Ok(match source {
+            CalendarSource::Dates(dates) => Days::Dates(dates.iter().copied().collect()),
+            CalendarSource::Ical(text) => Days::Events(parse_events(text)?),
+        })
+    }
+
+    fn contains(&self, date: NaiveDate, timezone: Tz) -> bool {
+        match self {
+            Days::Dates(dates) => dates.contains(&date),
+            Days::Events(events) => events.iter().any(|event| event.covers(date, timezone)),
+        }
+    }
+}
+
+impl Calendar {
+    /// Parse `calendar`'s sources; dates are read in `timezone`.
+    pub fn compile(calendar: &BusinessCalendar, timezone: Tz) -> Result<Self, ProviderError> {
+        let compile = |sources: &[CalendarSource]| {
+            sources
+                .iter()
+                .map(Days::compile)
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            timezone,
+            include: compile(&calendar.include)?,
+            exclude: compile(&calendar.exclude)?,
+        })
+    }
+
+    /// Whether fires may land on the local `date`.
+    pub fn allows(&self, date: NaiveDate) -> bool {
+        (self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|days| days.contains(date, self.timezone)))
+            && !self
+                .exclude
+                .iter()
+                .any(|days| days.contains(date, self.timezone))
+    }
+
+    /// First fire of `trigger` strictly after `after` on an allowed day.
+    pub fn next_fire_after(
+        &self,
+        trigger: &Trigger,
+        after: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let horizon = after.checked_add_signed(Duration::days(SEARCH_DAYS))?;
+        let mut cursor = after;
+        loop {
+            let fire = trigger
+                .next_fire_after(cursor)
+                .filter(|fire| *fire <= horizon)?;
+            let date = fire.with_timezone(&self.timezone).date_naive();
+            if self.allows(date) {
+                return Some(fire);
+            }
+            // Skip the rest of a rejected day rather than walking each of its fires.
+            cursor = date
+                .succ_opt()
+                .and_then(|next| {
+                    self.timezone
+                        .from_local_datetime(&next.and_time(NaiveTime::MIN))
+                        .earliest()
+                })
+                .map(|midnight| midnight.with_timezone(&Utc) - Duration::nanoseconds(1))
+                .filter(|end_of_day| *end_of_day > fire)
+                .unwrap_or(fire);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cron::CronExpr;
+    use provider_core::DstPolicy;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn weekdays_at_nine(timezone: Tz) -> Trigger {
+        Trigger::Cron {
+            cron: CronExpr::parse("0 9 * * MON-FRI").unwrap(),
+            timezone,
+            dst_policy: DstPolicy::default(),
+        }
+    }
+
+    #[test]
+    fn exclusions_skip_holidays() {
+        let berlin = chrono_tz::Europe::Berlin;
+        let calendar = Calendar::compile(
+            &BusinessCalendar {
+                include: Vec::new(),
+                exclude: vec![
+                    CalendarSource::Dates(vec![date("2024-12-24"), date("2024-12-31")]),
+                    CalendarSource::Ical(
+                        "BEGIN:VEVENT\nDTSTART;VALUE=DATE:20201225\nDTEND;VALUE=DATE:20201227\n\
+                         RRULE:FREQ=YEARLY\nEND:VEVENT"
+                            .into(),
+                    ),
+                ],
+            },
+            berlin,
+        )
+        .unwrap();
+        let trigger = weekdays_at_nine(berlin);
+        let next = |after| {
+            calendar
+                .next_fire_after(&trigger, at(after))
+                .unwrap()
+                .to_rfc3339()
+        };
+        // Mon 23rd fires; Tue 24th, Wed 25th and Thu 26th are excluded.
+        assert_eq!(next("2024-12-22T00:00:00Z"), "2024-12-23T08:00:00+00:00");
+        assert_eq!(next("2024-12-23T08:00:00Z"), "2024-12-27T08:00:00+00:00");
+        assert_eq!(next("2024-12-27T08:00:00Z"), "2024-12-30T08:00:00+00:00");
+        assert_eq!(next("2024-12-30T08:00:00Z"), "2025-01-01T08:00:00+00:00");
+    }
+
+    #[test]
+    fn inclusions_restrict_fires_to_listed_days() {
+        let calendar = Calendar::compile(
+            &BusinessCalendar {
+                include: vec![CalendarSource::Dates(vec![
+                    date("2024-03-15"),
+                    date("2024-03-16"),
+                ])],
+                exclude: vec![CalendarSource::Dates(vec![date("2024-03-16")])],
+            },
+            Tz::UTC,
+        )
+        .unwrap();
+        let every_minute = Trigger::Cron {
+            cron: CronExpr::parse("* * * * *").unwrap(),
+            timezone: Tz::UTC,
+            dst_policy: DstPolicy::default(),
+        };
+        assert_eq!(
+            calendar.next_fire_after(&every_minute, at("2024-01-01T00:00:00Z")),
+            Some(at("2024-03-15T00:00:00Z"))
+        );
+        assert_eq!(
+            calendar.next_fire_after(&every_minute, at("2024-03-15T23:59:00Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_ical() {
+        let err = Calendar::compile(
+            &BusinessCalendar {
+                include: vec![CalendarSource::Ical("BEGIN:VEVENT\nEND:VEVENT".into())],
+                exclude: Vec::new(),
+            },
+            Tz::UTC,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ProviderError::Config(msg) if msg.contains("DTSTART")));
+    }
+}
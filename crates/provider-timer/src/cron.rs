@@ -0,0 +1,567 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
+use provider_core::ProviderError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Years searched for the next fire time before an expression is considered exhausted.
+const SEARCH_YEARS: i32 = 100;
+
+const MONTH_NAMES: &[&str] = &[
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const DAY_NAMES: &[&str] = &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// Parsed cron expression.
+///
+/// Accepts 5 fields (`min hour dom month dow`, seconds fixed at 0), 6 fields with leading
+/// seconds, and the `@yearly`/`@annually`, `@monthly`, `@weekly`, `@daily`/`@midnight` and
+/// `@hourly` macros. Fields take `*`, values, `a-b` ranges, `/step`s, comma lists and
+/// month/day names. Day-of-month also takes `?`, `L` (last day), `L-n`, `LW` (last weekday)
+/// and `nW` (weekday nearest day n); day-of-week takes `?`, `nL` (last weekday n of the month)
+/// and `n#k` (k-th weekday n). As in Vixie cron, when both day fields are restricted a day
+/// matching either fires.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CronExpr {
+    source: String,
+    seconds: u64,
+    minutes: u64,
+    hours: u64,
+    months: u64,
+    days_of_month: DaysOfMonth,
+    days_of_week: DaysOfWeek,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct DaysOfMonth {
+    unrestricted: bool,
+    days: u64,
+    /// `L` is offset 0, `L-n` offset n.
+    last_offsets: Vec<u32>,
+    last_weekday: bool,
+    nearest_weekday: Vec<u32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct DaysOfWeek {
+    unrestricted: bool,
+    /// Bit 0 is Sunday.
+    days: u64,
+    last: Vec<u32>,
+    nth: Vec<(u32, u32)>,
+}
+
+impl CronExpr {
+    /// Parse an expression, rejecting syntax errors and expressions that can never fire.
+    pub fn parse(expr: &str) -> Result<Self, ProviderError> {
+        let parsed = parse_expr(expr.trim()).map_err(|detail| {
+            ProviderError::Config(format!("invalid cron expression `{expr}`: {detail}"))
+        })?;
+        let probe = NaiveDate::from_ymd_opt(2000, 1, 1)
+            .expect("valid date")
+            .and_time(NaiveTime::MIN);
+        if parsed.next_local_after(probe).is_none() {
+            return Err(ProviderError::Config(format!(
+                "invalid cron expression `{expr}`: never fires"
+            )));
+        }
+        Ok(parsed)
+    }
+
+    /// The expression as written.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// First fire time strictly after `after`.
+    pub fn next_fire_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.next_local_after(after.naive_utc())
+            .map(|next| next.and_utc())
+    }
+
+    /// The next `n` fire times from now.
+    pub fn upcoming(&self, n: usize) -> Vec<DateTime<Utc>> {
+        self.upcoming_after(Utc::now(), n)
+    }
+
+    /// The next `n` fire times strictly after `after`.
+    pub fn upcoming_after(&self, after: DateTime<Utc>, n: usize) -> Vec<DateTime<Utc>> {
+        std::iter::successors(self.next_fire_after(after), |prev| {
+            self.next_fire_after(*prev)
+        })
+        .take(n)
+        .collect()
+    }
+
+    /// First matching wall-clock time strictly after `after`, ignoring time zones.
+    pub(crate) fn next_local_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after.with_nanosecond(0)? + Duration::seconds(1);
+        let last_year = start.year() + SEARCH_YEARS;
+        let mut date = start.date();
+        let mut from = start.time();
+        while date.year() <= last_year {
+            if !has_bit(self.months, date.month()) {
+                date = first_of_next_month(date)?;
+                from = NaiveTime::MIN;
+                continue;
+            }
+            if self.matches_day(date)
+                && let Some(time) = self.first_time_from(from)
+            {
+                return Some(date.and_time(time));
+            }
+            date = date.succ_opt()?;
+            from = NaiveTime::MIN;
+        }
+        None
+    }
+
+    pub(crate) fn matches_day(&self, date: NaiveDate) -> bool {
+        let dom = &self.days_of_month;
+        let dow = &self.days_of_week;
+        if dom.unrestricted || dow.unrestricted {
+            self.matches_dom(date) && self.matches_dow(date)
+        } else {
+            self.matches_dom(date) || self.matches_dow(date)
+        }
+    }
+
+    fn matches_dom(&self, date: NaiveDate) -> bool {
+        let spec = &self.days_of_month;
+        let day = date.day();
+        let last = days_in_month(date.year(), date.month());
+        has_bit(spec.days, day)
+            || spec
+                .last_offsets
+                .iter()
+                .any(|offset| last.checked_sub(*offset) == Some(day))
+            || (spec.last_weekday && day == last_weekday(date.year(), date.month()))
+            || spec
+                .nearest_weekday
+                .iter()
+                .any(|target| nearest_weekday(date.year(), date.month(), *target) == Some(day))
+    }
+
+    fn matches_dow(&self, date: NaiveDate) -> bool {
+        let spec = &self.days_of_week;
+        let weekday = date.weekday().num_days_from_sunday();
+        let day = date.day();
+        has_bit(spec.days, weekday)
+            || (spec.last.contains(&weekday) && day + 7 > days_in_month(date.year(), date.month()))
+            || spec
+                .nth
+                .iter()
+                .any(|(wd, nth)| *wd == weekday && (day - 1) / 7 + 1 == *nth)
+    }
+
+    fn first_time_from(&self, from: NaiveTime) -> Option<NaiveTime> {
+        let mut hour = next_bit(self.hours, from.hour())?;
+        loop {
+            let min_from = if hour == from.hour() {
+                from.minute()
+            } else {
+                0
+            };
+            let mut minute = next_bit(self.minutes, min_from);
+            while let Some(m) = minute {
+                let sec_from = if hour == from.hour() && m == from.minute() {
+                    from.second()
+                } else {
+                    0
+                };
+                if let Some(s) = next_bit(self.seconds, sec_from) {
+                    return NaiveTime::from_hms_opt(hour, m, s);
+                }
+                minute = next_bit(self.minutes, m + 1);
+            }
+            hour = next_bit(self.hours, hour + 1)?;
+        }
+    }
+}
+
+impl FromStr for CronExpr {
+    type Err = ProviderError;
+
+    fn from_str(expr: &str) -> Result<Self, Self::Err> {
+        Self::parse(expr)
+    }
+}
+
+impl fmt::Display for CronExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn parse_expr(expr: &str) -> Result<CronExpr, String> {
+    let expanded = match expr.to_ascii_lowercase().as_str() {
+        "@yearly" | "@annually" => "0 0 0 1 1 *",
+        "@monthly" => "0 0 0 1 * *",
+        "@weekly" => "0 0 0 * * 0",
+        "@daily" | "@midnight" => "0 0 0 * * *",
+        "@hourly" => "0 0 * * * *",
+        other if other.starts_with('@') => return Err(format!("unknown macro {other}")),
+        _ => expr,
+    };
+    let fields: Vec<&str> = expanded.split_whitespace().collect();
+    let (seconds, rest) = match fields.len() {
+        5 => ("0", &fields[..]),
+        6 => (fields[0], &fields[1..]),
+        n => return Err(format!("expected 5 or 6 fields, found {n}")),
+    };
+    Ok(CronExpr {
+        source: expr.to_string(),
+        seconds: parse_field(seconds, "second", 0, 59, &[])?,
+        minutes: parse_field(rest[0], "minute", 0, 59, &[])?,
+        hours: parse_field(rest[1], "hour", 0, 23, &[])?,
+        days_of_month: parse_days_of_month(rest[2])?,
+        months: parse_field(rest[3], "month", 1, 12, MONTH_NAMES)?,
+        days_of_week: parse_days_of_week(rest[4])?,
+    })
+}
+
+/// Parse a plain field (`*`, values, ranges, steps, lists) into a bitmask.
+fn parse_field(text: &str, name: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let mut mask = 0;
+    for part in text.split(',') {
+        mask |= parse_part(part, name, min, max, names)?;
+    }
+    Ok(mask)
+}
+
+fn parse_part(part: &str, name: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let (base, step) = match part.split_once('/') {
+        Some((base, step)) => {
+            let step: u32 = step
+                .parse()
+                .map_err(|_| format!("invalid {name} step `{step}`"))?;
+            if step == 0 {
+                return Err(format!("{name} step must be positive"));
+            }
+            (base, Some(step))
+        }
+        None => (part, None),
+    };
+    let (start, end) = match base {
+        "*" => (min, max),
+        _ => match base.split_once('-') {
+            Some((lo, hi)) => (
+                parse_value(lo, name, min, max, names)?,
+                parse_value(hi, name, min, max, names)?,
+            ),
+            None => {
+                let value = parse_value(base, name, min, max, names)?;
+                (value, if step.is_some() { max } else { value })
+            }
+        },
+    };
+    if start > end {
+        return Err(format!("{name} range `{base}` is reversed"));
+    }
+    let step = step.unwrap_or(1) as usize;
+    Ok((start..=end).step_by(step).fold(0, |mask, v| mask | 1 << v))
+}
+
+fn parse_value(text: &str, name: &str, min: u32, max: u32, names: &[&str]) -> Result<u32, String> {
+    let upper = text.to_ascii_uppercase();
+    if let Some(idx) = names.iter().position(|n| *n == upper) {
+        return Ok(idx as u32 + min);
+    }
+    let value: u32 = text
+        .parse()
+        .map_err(|_| format!("invalid {name} `{text}`"))?;
+    if value < min || value > max {
+        return Err(format!("{name} {value} outside {min}-{max}"));
+    }
+    Ok(value)
+}
+
+fn parse_days_of_month(text: &str) -> Result<DaysOfMonth, String> {
+    let mut spec = DaysOfMonth {
+        unrestricted: text.starts_with('*') || text == "?",
+        ..Default::default()
+    };
+    if text == "?" {
+        spec.days = parse_field("*", "day-of-month", 1, 31, &[])?;
+        return Ok(spec);
+    }
+    for part in text.split(',') {
+        let upper = part.to_ascii_uppercase();
+        if upper == "L" {
+            spec.last_offsets.push(0);
+        } else if upper == "LW" {
+            spec.last_weekday = true;
+        } else if let Some(offset) = upper.strip_prefix("L-") {
+            let offset = offset
+                .parse::<u32>()
+                .ok()
+                .filter(|offset| *offset < 31)
+                .ok_or_else(|| format!("invalid day-of-month offset `{part}`"))?;
+            spec.last_offsets.push(offset);
+        } else if let Some(day) = upper.strip_suffix('W') {
+            spec.nearest_weekday
+                .push(parse_value(day, "day-of-month", 1, 31, &[])?);
+        } else {
+            spec.days |= parse_part(part, "day-of-month", 1, 31, &[])?;
+        }
+    }
+    Ok(spec)
+}
+
+fn parse_days_of_week(text: &str) -> Result<DaysOfWeek, String> {
+    let mut spec = DaysOfWeek {
+        unrestricted: text.starts_with('*') || text == "?",
+        ..Default::default()
+    };
+    if text == "?" {
+        spec.days = parse_field("*", "day-of-week", 0, 6, &[])?;
+        return Ok(spec);
+    }
+    for part in text.split(',') {
+        let upper = part.to_ascii_uppercase();
+        if let Some((day, nth)) = upper.split_once('#') {
+            let nth = nth
+                .parse::<u32>()
+                .ok()
+                .filter(|nth| (1..=5).contains(nth))
+                .ok_or_else(|| format!("invalid day-of-week occurrence `{part}`"))?;
+            spec.nth.push((weekday(day)?, nth));
+        } else if let Some(day) = upper.strip_suffix('L').filter(|day| !day.is_empty()) {
+            spec.last.push(weekday(day)?);
+        } else {
+            // 7 is an alias for Sunday.
+            let mask = parse_part(part, "day-of-week", 0, 7, DAY_NAMES)?;
+            spec.days |= (mask & 0x7f) | (mask >> 7 & 1);
+        }
+    }
+    Ok(spec)
+}
+
+fn weekday(text: &str) -> Result<u32, String> {
+    parse_value(text, "day-of-week", 0, 7, DAY_NAMES).map(|day| day % 7)
+}
+
+fn has_bit(mask: u64, bit: u32) -> bool {
+    bit < 64 && mask & (1 << bit) != 0
+}
+
+fn next_bit(mask: u64, from: u32) -> Option<u32> {
+    if from >= 64 {
+        return None;
+    }
+    let remaining = mask >> from << from;
+    (remaining != 0).then(|| remaining.trailing_zeros())
+}
+
+fn first_of_next_month(date: NaiveDate) -> Option<NaiveDate> {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+}
+
+pub(crate) fn days_in_month(year: i32, month: u32) -> u32 {
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(first_of_next_month)
+        .and_then(|next| next.pred_opt())
+        .map(|last| last.day())
+        .unwrap_or(31)
+}
+
+fn last_weekday(year: i32, month: u32) -> u32 {
+    let last = days_in_month(year, month);
+    match weekday_of(year, month, last) {
+        6 => last - 1,
+        0 => last - 2,
+        _ => last,
+    }
+}
+
+/// Weekday closest to `target` without leaving the month (Quartz `W` semantics).
+fn nearest_weekday(year: i32, month: u32, target: u32) -> Option<u32> {
+    let last = days_in_month(year, month);
+    if target > last {
+        return None;
+    }
+    Some(match weekday_of(year, month, target) {
+        6 if target == 1 => 3,
+        6 => target - 1,
+        0 if target == last => target - 2,
+        0 => target + 1,
+        _ => target,
+    })
+}
+
+fn weekday_of(year: i32, month: u32, day: u32) -> u32 {
+    NaiveDate::from_ymd_opt(year, month, day)
+        .map(|date| date.weekday().num_days_from_sunday())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn next(expr: &str, after: &str) -> String {
+        CronExpr::parse(expr)
+            .unwrap()
+            .next_fire_after(at(after))
+            .unwrap()
+            .to_rfc3339()
+    }
+
+    fn upcoming(expr: &str, after: &str, n: usize) -> Vec<String> {
+        CronExpr::parse(expr)
+            .unwrap()
+            .upcoming_after(at(after), n)
+            .iter()
+            .map(|t| t.to_rfc3339())
+            .collect()
+    }
+
+    #[test]
+    fn five_field_ranges_steps_and_names() {
+        assert_eq!(
+            upcoming("*/20 9-10 * * MON-FRI", "2024-01-05T10:30:00Z", 3),
+            [
+                "2024-01-05T10:40:00+00:00",
+                "2024-01-08T09:00:00+00:00",
+                "2024-01-08T09:20:00+00:00",
+            ]
+        );
+        assert_eq!(
+            next("0 12 1 jan,jul *", "2024-01-01T12:00:00Z"),
+            "2024-07-01T12:00:00+00:00"
+        );
+        assert_eq!(
+            next("0 0 * * 7", "2024-01-01T00:00:00Z"),
+            "2024-01-07T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn six_field_expressions_include_seconds() {
+        assert_eq!(
+            upcoming("15,45 */30 * * * *", "2024-01-01T00:00:15Z", 3),
+            [
+                "2024-01-01T00:00:45+00:00",
+                "2024-01-01T00:30:15+00:00",
+                "2024-01-01T00:30:45+00:00",
+            ]
+        );
+    }
+
+    #[test]
+    fn macros_expand() {
+        let after = "2024-02-14T10:15:00Z";
+        assert_eq!(next("@hourly", after), "2024-02-14T11:00:00+00:00");
+        assert_eq!(next("@daily", after), "2024-02-15T00:00:00+00:00");
+        assert_eq!(next("@midnight", after), "2024-02-15T00:00:00+00:00");
+        assert_eq!(next("@weekly", after), "2024-02-18T00:00:00+00:00");
+        assert_eq!(next("@monthly", after), "2024-03-01T00:00:00+00:00");
+        assert_eq!(next("@yearly", after), "2025-01-01T00:00:00+00:00");
+        assert_eq!(next("@annually", after), "2025-01-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn last_day_modifiers() {
+        assert_eq!(
+            upcoming("0 0 L * *", "2024-01-15T00:00:00Z", 3),
+            [
+                "2024-01-31T00:00:00+00:00",
+                "2024-02-29T00:00:00+00:00",
+                "2024-03-31T00:00:00+00:00",
+            ]
+        );
+        assert_eq!(
+            next("0 0 L-2 * *", "2023-02-01T00:00:00Z"),
+            "2023-02-26T00:00:00+00:00"
+        );
+        // 2025-08-31 is a Sunday.
+        assert_eq!(
+            next("0 0 LW * *", "2025-08-01T00:00:00Z"),
+            "2025-08-29T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn nearest_weekday_modifier() {
+        // 2024-06-15 is a Saturday and 2024-06-01 a Saturday at the month start.
+        assert_eq!(
+            next("0 0 15W * *", "2024-06-01T00:00:00Z"),
+            "2024-06-14T00:00:00+00:00"
+        );
+        assert_eq!(
+            next("0 0 1W * *", "2024-05-31T00:00:00Z"),
+            "2024-06-03T00:00:00+00:00"
+        );
+        // 2024-03-31 is a Sunday at the month end.
+        assert_eq!(
+            next("0 0 31W * *", "2024-03-01T00:00:00Z"),
+            "2024-03-29T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn weekday_occurrence_modifiers() {
+        assert_eq!(
+            next("0 0 * * 5L", "2024-01-01T00:00:00Z"),
+            "2024-01-26T00:00:00+00:00"
+        );
+        assert_eq!(
+            upcoming("0 9 ? * MON#2", "2024-01-01T00:00:00Z", 2),
+            ["2024-01-08T09:00:00+00:00", "2024-02-12T09:00:00+00:00"]
+        );
+    }
+
+    #[test]
+    fn restricted_day_fields_match_either() {
+        assert_eq!(
+            upcoming("0 0 1 * MON", "2024-01-02T00:00:00Z", 2),
+            ["2024-01-08T00:00:00+00:00", "2024-01-15T00:00:00+00:00"]
+        );
+        assert_eq!(
+            next("0 0 1 * *", "2024-01-02T00:00:00Z"),
+            "2024-02-01T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn leap_day_waits_for_leap_year() {
+        assert_eq!(
+            next("0 0 29 2 *", "2024-03-01T00:00:00Z"),
+            "2028-02-29T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for expr in [
+            "",
+            "* * *",
+            "* * * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "0 0 32 * *",
+            "0 0 * 13 *",
+            "0 0 * * 8",
+            "5-1 * * * *",
+            "*/0 * * * *",
+            "0 0 * * MON#6",
+            "0 0 L-31 * *",
+            "0 0 * * FOO",
+            "@reboot",
+            "0 0 30 2 *",
+        ] {
+            let err = CronExpr::parse(expr).expect_err(expr);
+            assert!(matches!(err, ProviderError::Config(_)), "{expr}: {err}");
+        }
+    }
+}
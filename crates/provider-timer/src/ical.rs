@@ -0,0 +1,444 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use provider_core::ProviderError;
+
+use crate::rrule::RRule;
+
+/// An iCalendar `DATE` or `DATE-TIME` value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IcalTime {
+    Date(NaiveDate),
+    /// A local time without a zone, read in whatever timezone it is evaluated in.
+    Floating(NaiveDateTime),
+    Utc(DateTime<Utc>),
+    /// A local time with a `TZID`.
+    Zoned(NaiveDateTime, Tz),
+}
+
+impl IcalTime {
+    /// Parse `YYYYMMDD`, `YYYYMMDDTHHMMSS` or `YYYYMMDDTHHMMSSZ`. A `TZID` naming an unknown
+    /// zone leaves the time floating.
+    pub fn parse(value: &str, tzid: Option<&str>) -> Result<Self, String> {
+        let invalid = || format!("invalid date or date-time `{value}`");
+        if value.len() == 8 {
+            return NaiveDate::parse_from_str(value, "%Y%m%d")
+                .map(IcalTime::Date)
+                .map_err(|_| invalid());
+        }
+        let (local, utc) = match value.strip_suffix(['Z', 'z']) {
+            Some(local) => (local, true),
+            None => (value, false),
+        };
+        let local = NaiveDateTime::parse_from_str(local, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+        Ok(if utc {
+            IcalTime::Utc(local.and_utc())
+        } else {
+            match tzid.and_then(|tzid| tzid.parse::<Tz>().ok()) {
+                Some(zone) => IcalTime::Zoned(local, zone),
+                None => IcalTime::Floating(local),
+            }
+        })
+    }
+
+    /// The value as a local time in `tz`; dates are taken at midnight.
+    pub fn to_local(&self, tz: Tz) -> NaiveDateTime {
+        match *self {
+            IcalTime::Date(date) => date.and_time(NaiveTime::MIN),
+            IcalTime::Floating(local) => local,
+            IcalTime::Utc(at) => at.with_timezone(&tz).naive_local(),
+            IcalTime::Zoned(local, zone) => zone
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|at| at.with_timezone(&tz).naive_local())
+                .unwrap_or(local),
+        }
+    }
+}
+
+/// A `VEVENT`, reduced to the days its occurrences cover.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    start: IcalTime,
+    end: Option<EventEnd>,
+    rrule: Option<RRule>,
+    rdates: Vec<IcalTime>,
+    exdates: Vec<IcalTime>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EventEnd {
+    At(IcalTime),
+    After(Duration),
+}
+
+impl Event {
+    /// Whether an occurrence of the event, after `EXDATE`s, covers any part of `date`. An
+    /// event without `DTEND` or `DURATION` covers its start day.
+    pub fn covers(&self, date: NaiveDate, tz: Tz) -> bool {
+        let start = self.start.to_local(tz);
+        let length = match self.end {
+            Some(EventEnd::At(end)) => end.to_local(tz) - start,
+            Some(EventEnd::After(duration)) => duration,
+            None if matches!(self.start, IcalTime::Date(_)) => Duration::days(1),
+            None => Duration::zero(),
+        };
+        let hits = |occurrence: NaiveDateTime| {
+            let Some(end) = occurrence.checked_add_signed(length) else {
+                return false;
+            };
+            let last = if end > occurrence && end.time() == NaiveTime::MIN {
+                end.date().pred_opt().unwrap_or(end.date())
+            } else {
+                end.date()
+            };
+            occurrence.date() <= date && date <= last && !self.excluded(occurrence, tz)
+        };
+
+        if std::iter::once(start)
+            .chain(self.rdates.iter().map(|rdate| rdate.to_local(tz)))
+            .any(hits)
+        {
+            return true;
+        }
+        let Some(rule) = &self.rrule else {
+            return false;
+        };
+        // Only occurrences starting less than `length` (plus a day for the midnight edge)
+        // before `date` can reach it, so seek there rather than replaying from DTSTART.
+        let mut cursor = date
+            .and_time(NaiveTime::MIN)
+            .checked_sub_signed(length.max(Duration::zero()))
+            .and_then(|cursor| cursor.checked_sub_signed(Duration::days(1)))
+            .unwrap_or(NaiveDateTime::MIN);
+        while let Some(occurrence) = rule
+            .next_local_after(start, tz, cursor)
+            .filter(|occurrence| occurrence.date() <= date)
+        {
+            if hits(occurrence) {
+                return true;
+            }
+            cursor = occurrence;
+        }
+        false
+    }
+
+    fn excluded(&self, occurrence: NaiveDateTime, tz: Tz) -> bool {
+        self.exdates.iter().any(|exdate| match exdate {
+            IcalTime::Date(date) => *date == occurrence.date(),
+            other => other.to_local(tz) == occurrence,
+        })
+    }
+}
+
+/// Parse the `VEVENT`s of an iCalendar document (or of bare `VEVENT` blocks); other
+/// components are ignored.
+pub fn parse_events(text: &str) -> Result<Vec<Event>, ProviderError> {
+    parse_document(text)
+        .map_err(|detail| ProviderError::Config(format!("invalid iCalendar document: {detail}")))
+}
+
+struct Property<'a> {
+    name: String,
+    params: Vec<(String, String)>,
+    value: &'a str,
+}
+
+impl Property<'_> {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn times(&self) -> Result<Vec<IcalTime>, String> {
+        if self.param("VALUE") == Some("PERIOD") {
+            return Err(format!("{} periods are not supported", self.name));
+        }
+        self.value
+            .split(',')
+            .map(|value| IcalTime::parse(value.trim(), self.param("TZID")))
+            .collect()
+    }
+}
+
+fn parse_document(text: &str) -> Result<Vec<Event>, String> {
+    let unfolded = text
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+    let mut events = Vec::new();
+    let mut stack: Vec<String> = Vec::new();
+    let mut current: Option<Event> = None;
+    let mut start_seen = false;
+
+    for line in unfolded.lines().filter(|line| !line.trim().is_empty()) {
+        let property = parse_line(line)?;
+        match property.name.as_str() {
+            "BEGIN" => {
+                let component = property.value.trim().to_ascii_uppercase();
+                if component == "VEVENT" && stack.last().is_none_or(|c| c == "VCALENDAR") {
+                    current = Some(Event {
+                        start: IcalTime::Date(NaiveDate::MIN),
+                        end: None,
+                        rrule: None,
+                        rdates: Vec::new(),
+                        exdates: Vec::new(),
+                    });
+                    start_seen = false;
+                }
+                stack.push(component);
+                continue;
+            }
+            "END" => {
+                let component = property.value.trim().to_ascii_uppercase();
+                if stack.pop().as_deref() != Some(component.as_str()) {
+                    return Err(format!("unbalanced END:{component}"));
+                }
+                if component == "VEVENT"
+                    && let Some(event) = current.take()
+                {
+                    if !start_seen {
+                        return Err("VEVENT without DTSTART".into());
+                    }
+                    if let Some(EventEnd::After(duration)) = event.end
+                        && event
+                            .start
+                            .to_local(Tz::UTC)
+                            .checked_add_signed(duration)
+                            .is_none()
+                    {
+                        return Err("DURATION runs past the supported date range".into());
+                    }
+                    events.push(event);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        let Some(event) = current
+            .as_mut()
+            .filter(|_| stack.last().is_some_and(|c| c == "VEVENT"))
+        else {
+            continue;
+        };
+        match property.name.as_str() {
+            "DTSTART" => {
+                event.start = single(property.times()?)?;
+                start_seen = true;
+            }
+            "DTEND" => event.end = Some(EventEnd::At(single(property.times()?)?)),
+            "DURATION" => event.end = Some(EventEnd::After(parse_duration(property.value)?)),
+            "RRULE" => {
+                event.rrule = Some(RRule::parse(property.value).map_err(|err| err.to_string())?)
+            }
+            "RDATE" => event.rdates.extend(property.times()?),
+            "EXDATE" => event.exdates.extend(property.times()?),
+            _ => {}
+        }
+    }
+    if let Some(open) = stack.last() {
+        return Err(format!("missing END:{open}"));
+    }
+    Ok(events)
+}
+
+fn single(mut times: Vec<IcalTime>) -> Result<IcalTime, String> {
+    match (times.pop(), times.is_empty()) {
+        (Some(time), true) => Ok(time),
+        _ => Err("expected a single date or date-time".into()),
+    }
+}
+
+/// Split `NAME;PARAM=VALUE:value`, honouring quoted parameter values.
+fn parse_line(line: &str) -> Result<Property<'_>, String> {
+    let mut quoted = false;
+    let mut parts = Vec::new();
+    let mut part_start = 0;
+    for (index, ch) in line.char_indices() {
+        match ch {
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                parts.push(&line[part_start..index]);
+                part_start = index + 1;
+            }
+            ':' if !quoted => {
+                parts.push(&line[part_start..index]);
+                let (name, params) = parts.split_first().expect("name part");
+                let params = params
+                    .iter()
+                    .map(|param| {
+                        let (key, value) = param.split_once('=').unwrap_or((param, ""));
+                        (
+                            key.to_ascii_uppercase(),
+                            value.trim_matches('"').to_string(),
+                        )
+                    })
+                    .collect();
+                return Ok(Property {
+                    name: name.to_ascii_uppercase(),
+                    params,
+                    value: &line[index + 1..],
+                });
+            }
+            _ => {}
+        }
+    }
+    Err(format!("content line without a value: `{line}`"))
+}
+
+/// Parse an RFC 5545 duration such as `P1D`, `P2W` or `PT1H30M`.
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid DURATION `{text}`");
+    let (negative, rest) = match text.trim().strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.trim().trim_start_matches('+')),
+    };
+    let rest = rest.strip_prefix('P').ok_or_else(invalid)?;
+    let mut total = Duration::zero();
+    let mut digits = String::new();
+    let mut in_time = false;
+    for ch in rest.chars() {
+        if ch.is_ascii_digit() {
+            digits.push(ch);
+            continue;
+        }
+        if ch == 'T' {
+            in_time = true;
+            continue;
+        }
+        let amount: i64 = digits.parse().map_err(|_| invalid())?;
+        digits.clear();
+        let part = match (ch, in_time) {
+            ('W', false) => Duration::try_weeks(amount),
+            ('D', false) => Duration::try_days(amount),
+            ('H', true) => Duration::try_hours(amount),
+            ('M', true) => Duration::try_minutes(amount),
+            ('S', true) => Duration::try_seconds(amount),
+            _ => return Err(invalid()),
+        };
+        total = part
+            .and_then(|part| total.checked_add(&part))
+            .ok_or_else(invalid)?;
+    }
+    if !digits.is_empty() {
+        return Err(invalid());
+    }
+    Ok(if negative { -total } else { total })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    const HOLIDAYS: &str = "BEGIN:VCALENDAR\r\n\
+        VERSION:2.0\r\n\
+        PRODID:-//acme//holidays//EN\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:new-year@acme\r\n\
+        SUMMARY:New Year\r\n\
+        DTSTART;VALUE=DATE:20240101\r\n\
+        RRULE:FREQ=YEARLY\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:shutdown@acme\r\n\
+        DTSTART;VALUE=DATE:20241224\r\n\
+        DTEND;VALUE=DATE:20241227\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:thanksgiving@acme\r\n\
+        DTSTART;VALUE=DATE:20231123\r\n\
+        RRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=4TH\r\n\
+        EXDATE;VALUE=DATE:20251127\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:offsite@acme\r\n\
+        DTSTART;TZID=\"America/New_York\":20240610T220000\r\n\
+        DURATION:PT4H\r\n\
+        SUMMARY:Offsite\\; folded over\r\n  two lines\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    #[test]
+    fn events_cover_their_days() {
+        let events = parse_events(HOLIDAYS).unwrap();
+        assert_eq!(events.len(), 4);
+        let covered = |day: &str, tz: Tz| events.iter().any(|e| e.covers(date(day), tz));
+
+        assert!(covered("2024-01-01", Tz::UTC));
+        assert!(covered("2031-01-01", Tz::UTC));
+        assert!(!covered("2024-01-02", Tz::UTC));
+        for day in ["2024-12-24", "2024-12-25", "2024-12-26"] {
+            assert!(covered(day, Tz::UTC), "{day}");
+        }
+        assert!(!covered("2024-12-27", Tz::UTC));
+        assert!(covered("2024-11-28", Tz::UTC));
+        assert!(!covered("2025-11-27", Tz::UTC));
+        assert!(covered("2026-11-26", Tz::UTC));
+
+        // 22:00-02:00 in New York spans two local days there, and falls on the 11th in UTC.
+        assert!(covered("2024-06-10", chrono_tz::America::New_York));
+        assert!(covered("2024-06-11", chrono_tz::America::New_York));
+        assert!(!covered("2024-06-10", Tz::UTC));
+        assert!(covered("2024-06-11", Tz::UTC));
+    }
+
+    #[test]
+    fn recurring_events_seek_to_the_checked_day() {
+        // Replaying every minute since 2000 for each day checked would take minutes.
+        let standup = "BEGIN:VEVENT\n\
+            DTSTART:20000103T090000Z\n\
+            DURATION:PT15M\n\
+            RRULE:FREQ=MINUTELY;BYDAY=MO;BYHOUR=9;BYMINUTE=0\n\
+            END:VEVENT";
+        let events = parse_events(standup).unwrap();
+        let covered = |day: &str| events[0].covers(date(day), Tz::UTC);
+        for day in 0..60 {
+            let day = date("2024-06-10") + Duration::days(day);
+            assert_eq!(
+                events[0].covers(day, Tz::UTC),
+                chrono::Datelike::weekday(&day) == chrono::Weekday::Mon,
+                "{day}"
+            );
+        }
+        assert!(covered("2000-01-03"));
+        assert!(!covered("1999-12-27"));
+
+        let overnight = "BEGIN:VEVENT\n\
+            DTSTART:20200101T230000Z\n\
+            DURATION:PT2H\n\
+            RRULE:FREQ=DAILY;INTERVAL=3\n\
+            END:VEVENT";
+        let events = parse_events(overnight).unwrap();
+        // Occurrences start on 2024-06-11 and 2024-06-14, each running past midnight.
+        assert!(events[0].covers(date("2024-06-11"), Tz::UTC));
+        assert!(events[0].covers(date("2024-06-12"), Tz::UTC));
+        assert!(!events[0].covers(date("2024-06-13"), Tz::UTC));
+        assert!(events[0].covers(date("2024-06-14"), Tz::UTC));
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        for bad in [
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nSUMMARY:x\nEND:VEVENT\nEND:VCALENDAR",
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nDTSTART:2024\nEND:VEVENT\nEND:VCALENDAR",
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nDTSTART:20240101\nRRULE:FREQ=NEVER\nEND:VEVENT\nEND:VCALENDAR",
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nDTSTART:20240101\nEND:VCALENDAR",
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nDTSTART:20240101\nDURATION:P1X\nEND:VEVENT\nEND:VCALENDAR",
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nDTSTART:20240101\nDURATION:P99999999999999W\nEND:VEVENT\nEND:VCALENDAR",
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nDTSTART:20240101\nDURATION:P100000000D\nEND:VEVENT\nEND:VCALENDAR",
+            "no colon here",
+        ] {
+            assert!(
+                matches!(parse_events(bad), Err(ProviderError::Config(_))),
+                "{bad}"
+            );
+        }
+        assert_eq!(parse_duration("P1W2D").unwrap(), Duration::days(9));
+        assert_eq!(parse_duration("-PT90M").unwrap(), -Duration::minutes(90));
+    }
+}
@@ -0,0 +1,126 @@
+use chrono::{DateTime, Duration, Utc};
+use provider_core::{Jitter, JitterMode};
+
+/// Largest `window_secs` [`Trigger::compile`](crate::Trigger::compile) accepts: one day.
+pub const MAX_WINDOW_SECS: u64 = 24 * 60 * 60;
+
+/// Offset added to the fire nominally due at `nominal`, in whole seconds below
+/// `jitter.window_secs`. Stable across hosts and restarts for the same inputs.
+pub fn offset(
+    jitter: &Jitter,
+    tenant_key: &str,
+    schedule_name: &str,
+    nominal: DateTime<Utc>,
+) -> Duration {
+    if jitter.window_secs == 0 {
+        return Duration::zero();
+    }
+    let mut hash = Fnv::default();
+    hash.write(tenant_key.as_bytes());
+    hash.write(&[0]);
+    hash.write(schedule_name.as_bytes());
+    if jitter.mode == JitterMode::Random {
+        hash.write(&[0]);
+        hash.write(&nominal.timestamp().to_be_bytes());
+        hash.write(&nominal.timestamp_subsec_nanos().to_be_bytes());
+    }
+    // `Trigger::compile` rejects wider windows, but `Jitter` is public and may not have
+    // been through it; clamping keeps the offset well inside an i64 of seconds.
+    let window = jitter.window_secs.min(MAX_WINDOW_SECS);
+    Duration::seconds((hash.finish() % window) as i64)
+}
+
+/// 64-bit FNV-1a: fixed across platforms and releases, unlike `DefaultHasher`.
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn offsets_are_deterministic_and_bounded() {
+        let random = Jitter {
+            window_secs: 900,
+            mode: JitterMode::Random,
+        };
+        let midnight = at("2024-01-01T00:00:00Z");
+        let next_day = at("2024-01-02T00:00:00Z");
+        let first = offset(&random, "dev/acme/_", "nightly", midnight);
+        assert_eq!(first, offset(&random, "dev/acme/_", "nightly", midnight));
+        assert!(first >= Duration::zero() && first < Duration::seconds(900));
+
+        let days: Vec<_> = (0..30)
+            .map(|day| {
+                offset(
+                    &random,
+                    "dev/acme/_",
+                    "nightly",
+                    midnight + Duration::days(day),
+                )
+            })
+            .collect();
+        assert!(days.iter().any(|other| *other != first));
+
+        let spread = Jitter {
+            window_secs: 900,
+            mode: JitterMode::Spread,
+        };
+        assert_eq!(
+            offset(&spread, "dev/acme/_", "nightly", midnight),
+            offset(&spread, "dev/acme/_", "nightly", next_day)
+        );
+        assert_eq!(
+            offset(
+                &Jitter {
+                    window_secs: 0,
+                    ..spread
+                },
+                "dev/acme/_",
+                "nightly",
+                midnight
+            ),
+            Duration::zero()
+        );
+    }
+
+    #[test]
+    fn spread_distributes_tenants_across_the_window() {
+        let spread = Jitter {
+            window_secs: 3600,
+            mode: JitterMode::Spread,
+        };
+        let midnight = at("2024-01-01T00:00:00Z");
+        let mut quarters = [0; 4];
+        for tenant in 0..400 {
+            let key = format!("prod/tenant-{tenant}/_");
+            let secs = offset(&spread, &key, "nightly", midnight).num_seconds();
+            quarters[(secs / 900) as usize] += 1;
+        }
+        assert!(quarters.iter().all(|count| *count > 60), "{quarters:?}");
+    }
+}
@@ -1,32 +1,403 @@
-use provider_core::{ProviderError, SchedulerConfig, new_event};
+pub mod calendar;
+pub mod cron;
+pub mod ical;
+pub mod jitter;
+pub mod rrule;
+pub mod template;
+pub mod timezone;
+pub mod trigger;
+
+use chrono::{DateTime, SecondsFormat, SubsecRound, Utc};
+use provider_core::{
+    CatchUpPolicy, ProviderError, Schedule, ScheduleKind, SchedulerConfig, new_event,
+    set_idempotency_key, tenant_key,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, VecDeque};
+
+pub use calendar::Calendar;
+pub use cron::CronExpr;
+pub use rrule::RRule;
+pub use template::FireContext;
+pub use timezone::{next_fire_in, parse_timezone};
+pub use trigger::Trigger;
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "TimerSourceConfig")]
 pub struct TimerSource {
     config: SchedulerConfig,
+    #[serde(skip)]
+    triggers: BTreeMap<String, Trigger>,
+    #[serde(skip)]
+    calendars: BTreeMap<String, Calendar>,
+}
+
+#[derive(Deserialize)]
+struct TimerSourceConfig {
+    config: SchedulerConfig,
+}
+
+impl TryFrom<TimerSourceConfig> for TimerSource {
+    type Error = ProviderError;
+
+    fn try_from(raw: TimerSourceConfig) -> Result<Self, Self::Error> {
+        Self::new(raw.config)
+    }
+}
+
+impl PartialEq for TimerSource {
+    fn eq(&self, other: &Self) -> bool {
+        self.config == other.config
+    }
 }
 
 impl TimerSource {
-    pub fn new(config: SchedulerConfig) -> Self {
-        Self { config }
+    /// Build a source, rejecting schedules with an invalid trigger, timezone, calendar or
+    /// payload template. `delay` schedules without an `anchor` count from now.
+    pub fn new(config: SchedulerConfig) -> Result<Self, ProviderError> {
+        Self::new_at(config, Utc::now())
     }
 
-    pub fn fire(
+    /// Like [`TimerSource::new`], with unanchored `delay` schedules counting from `loaded_at`.
+    /// That instant is written into their `anchor`, so the source serializes with the
+    /// resolved fire time instead of restarting the delay when it is loaded again.
+    pub fn new_at(
+        mut config: SchedulerConfig,
+        loaded_at: DateTime<Utc>,
+    ) -> Result<Self, ProviderError> {
+        for schedule in &mut config.schedules {
+            if let ScheduleKind::Delay { anchor, .. } = &mut schedule.kind {
+                anchor.get_or_insert(loaded_at);
+            }
+        }
+        let mut triggers = BTreeMap::new();
+        let mut calendars = BTreeMap::new();
+        for schedule in &config.schedules {
+            let compiled = Trigger::compile(schedule, loaded_at).and_then(|trigger| {
+                if schedule.payload_template {
+                    template::validate_payload(&schedule.payload)?;
+                }
+                let calendar = match &schedule.calendar {
+                    Some(calendar) => Some(Calendar::compile(
+                        calendar,
+                        parse_timezone(schedule.timezone.as_deref())?,
+                    )?),
+                    None => None,
+                };
+                Ok((trigger, calendar))
+            });
+            let (trigger, calendar) = compiled.map_err(|err| {
+                ProviderError::Config(format!("schedule {}: {err}", schedule.name))
+            })?;
+            triggers.insert(schedule.name.clone(), trigger);
+            if let Some(calendar) = calendar {
+                calendars.insert(schedule.name.clone(), calendar);
+            }
+        }
+        Ok(Self {
+            config,
+            triggers,
+            calendars,
+        })
+    }
+
+    /// First nominal fire time of `schedule_name` strictly after `after`, before any jitter
+    /// and on a day its calendar allows; `None` once a one-shot schedule has fired.
+    pub fn next_fire_after(
         &self,
-        tenant: greentic_types::TenantCtx,
         schedule_name: &str,
-    ) -> Result<greentic_types::EventEnvelope, ProviderError> {
-        let schedule = self
-            .config
+        after: DateTime<Utc>,
+    ) -> Result<Option<DateTime<Utc>>, ProviderError> {
+        let trigger = self.trigger(schedule_name)?;
+        Ok(match self.calendars.get(schedule_name) {
+            Some(calendar) => calendar.next_fire_after(trigger, after),
+            None => trigger.next_fire_after(after),
+        })
+    }
+
+    /// The next `n` nominal fire times of `schedule_name` from now.
+    pub fn upcoming(
+        &self,
+        schedule_name: &str,
+        n: usize,
+    ) -> Result<Vec<DateTime<Utc>>, ProviderError> {
+        let mut upcoming = Vec::with_capacity(n.min(64));
+        let mut cursor = Utc::now();
+        while upcoming.len() < n {
+            let Some(next) = self.next_fire_after(schedule_name, cursor)? else {
+                break;
+            };
+            upcoming.push(next);
+            cursor = next;
+        }
+        Ok(upcoming)
+    }
+
+    /// First time `schedule_name` is due for `tenant` strictly after `after`: the nominal
+    /// fire time plus the schedule's jitter offset for that tenant. Fires keep their nominal
+    /// order as long as the jitter window is shorter than the gap between fires.
+    pub fn next_fire_for(
+        &self,
+        tenant: &greentic_types::TenantCtx,
+        schedule_name: &str,
+        after: DateTime<Utc>,
+    ) -> Result<Option<DateTime<Utc>>, ProviderError> {
+        let schedule = self.schedule(schedule_name)?;
+        let Some(jitter) = &schedule.jitter else {
+            return self.next_fire_after(schedule_name, after);
+        };
+        let key = tenant_key(tenant);
+        // Offsets stay below the window, so nominal fires at or before `after - window`
+        // are due by `after` already.
+        let mut cursor = after
+            .checked_sub_signed(chrono::Duration::seconds(jitter.window_secs as i64))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+        while let Some(nominal) = self.next_fire_after(schedule_name, cursor)? {
+            let due = nominal + jitter::offset(jitter, &key, &schedule.name, nominal);
+            if due > after {
+                return Ok(Some(due));
+            }
+            cursor = nominal;
+        }
+        Ok(None)
+    }
+
+    /// The next `n` times `schedule_name` is due for `tenant` from now, jitter applied.
+    pub fn upcoming_for(
+        &self,
+        tenant: &greentic_types::TenantCtx,
+        schedule_name: &str,
+        n: usize,
+    ) -> Result<Vec<DateTime<Utc>>, ProviderError> {
+        let mut due = Vec::with_capacity(n.min(64));
+        let mut cursor = Utc::now();
+        while due.len() < n {
+            let Some(next) = self.next_fire_for(tenant, schedule_name, cursor)? else {
+                break;
+            };
+            due.push(next);
+            cursor = next;
+        }
+        Ok(due)
+    }
+
+    /// The nominal fire behind a fire due at `due` for `tenant`, with its jitter offset.
+    fn nominal_for(
+        &self,
+        tenant: &greentic_types::TenantCtx,
+        schedule: &Schedule,
+        due: DateTime<Utc>,
+    ) -> Result<Option<(DateTime<Utc>, chrono::Duration)>, ProviderError> {
+        let Some(jitter) = &schedule.jitter else {
+            return Ok(Some((due, chrono::Duration::zero())));
+        };
+        let key = tenant_key(tenant);
+        let mut cursor = due
+            .checked_sub_signed(chrono::Duration::seconds(jitter.window_secs as i64))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+        while let Some(nominal) = self
+            .next_fire_after(&schedule.name, cursor)?
+            .filter(|n| *n <= due)
+        {
+            let offset = jitter::offset(jitter, &key, &schedule.name, nominal);
+            if nominal + offset == due {
+                return Ok(Some((nominal, offset)));
+            }
+            cursor = nominal;
+        }
+        Ok(None)
+    }
+
+    fn trigger(&self, schedule_name: &str) -> Result<&Trigger, ProviderError> {
+        self.triggers
+            .get(schedule_name)
+            .ok_or_else(|| ProviderError::Config(format!("unknown schedule {}", schedule_name)))
+    }
+
+    fn schedule(&self, schedule_name: &str) -> Result<&Schedule, ProviderError> {
+        self.config
             .schedules
             .iter()
             .find(|s| s.name == schedule_name)
-            .ok_or_else(|| ProviderError::Config(format!("unknown schedule {}", schedule_name)))?;
+            .ok_or_else(|| ProviderError::Config(format!("unknown schedule {}", schedule_name)))
+    }
+
+    /// Instants `schedule_name` was due for `tenant` in `(last_fired, now]` that its
+    /// catch-up policy replays, oldest first, jitter applied. At most `max_fires` are
+    /// returned, keeping the most recent.
+    pub fn catch_up(
+        &self,
+        tenant: &greentic_types::TenantCtx,
+        schedule_name: &str,
+        last_fired: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<DateTime<Utc>>, ProviderError> {
+        let catch_up = self.schedule(schedule_name)?.catch_up;
+        let keep = match catch_up.policy {
+            CatchUpPolicy::Skip => 0,
+            CatchUpPolicy::LatestOnly => 1,
+            CatchUpPolicy::FireAll => usize::MAX,
+        }
+        .min(catch_up.max_fires as usize);
+        if keep == 0 {
+            return Ok(Vec::new());
+        }
+        self.last_due(tenant, schedule_name, last_fired, now, keep)
+    }
+
+    /// The latest instant at or before `at` that `schedule_name` was due for `tenant`, jitter
+    /// applied, looking back as far as the Unix epoch; `None` if it never was.
+    pub fn previous_fire_for(
+        &self,
+        tenant: &greentic_types::TenantCtx,
+        schedule_name: &str,
+        at: DateTime<Utc>,
+    ) -> Result<Option<DateTime<Utc>>, ProviderError> {
+        Ok(self
+            .last_due(tenant, schedule_name, DateTime::UNIX_EPOCH, at, 1)?
+            .pop())
+    }
+
+    /// The last `keep` instants `schedule_name` was due for `tenant` in `(after, until]`,
+    /// oldest first. Searches back from `until` over a doubling window instead of walking
+    /// every fire since `after`, so a long gap costs about as much as the fires returned.
+    fn last_due(
+        &self,
+        tenant: &greentic_types::TenantCtx,
+        schedule_name: &str,
+        after: DateTime<Utc>,
+        until: DateTime<Utc>,
+        keep: usize,
+    ) -> Result<Vec<DateTime<Utc>>, ProviderError> {
+        let mut window = chrono::Duration::minutes(1);
+        loop {
+            let start = until
+                .checked_sub_signed(window)
+                .filter(|start| *start > after)
+                .unwrap_or(after);
+            let mut due = VecDeque::new();
+            let mut cursor = start;
+            while let Some(next) = self
+                .next_fire_for(tenant, schedule_name, cursor)?
+                .filter(|next| *next <= until)
+            {
+                if due.len() == keep {
+                    due.pop_front();
+                }
+                due.push_back(next);
+                cursor = next;
+            }
+            if due.len() == keep || start == after {
+                return Ok(due.into());
+            }
+            window = window.checked_add(&window).unwrap_or(chrono::Duration::MAX);
+        }
+    }
+
+    /// Events for the fires [`TimerSource::catch_up`] replays, each stamped with its
+    /// `scheduled_for` instant and `fired_at = now`, numbered on from `last_sequence`.
+    pub fn fire_missed(
+        &self,
+        tenant: greentic_types::TenantCtx,
+        schedule_name: &str,
+        last_fired: DateTime<Utc>,
+        last_sequence: u64,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<greentic_types::EventEnvelope>, ProviderError> {
+        (last_sequence + 1..)
+            .zip(self.catch_up(&tenant, schedule_name, last_fired, now)?)
+            .map(|(sequence, scheduled_for)| {
+                self.fire_scheduled(tenant.clone(), schedule_name, scheduled_for, now, sequence)
+            })
+            .collect()
+    }
+
+    /// Emit `schedule_name` for its latest fire due by now (see
+    /// [`TimerSource::previous_fire_for`]), or for the current second if it has never been
+    /// due, with sequence number 0. Retries within the same fire share an idempotency key;
+    /// hosts counting fires should use [`TimerSource::fire_scheduled`].
+    pub fn fire(
+        &self,
+        tenant: greentic_types::TenantCtx,
+        schedule_name: &str,
+    ) -> Result<greentic_types::EventEnvelope, ProviderError> {
+        let now = Utc::now();
+        let scheduled_for = self
+            .previous_fire_for(&tenant, schedule_name, now)?
+            .unwrap_or(now);
+        self.fire_scheduled(tenant, schedule_name, scheduled_for, now, 0)
+    }
+
+    /// Emit `schedule_name` for the fire due at `scheduled_for` (jitter included, as returned
+    /// by [`TimerSource::next_fire_for`]), emitted at `fired_at`. With `payload_template` set,
+    /// the payload is rendered as a template (see [`template::render_payload`]) with
+    /// `sequence` as the fire's `seq`. `scheduled_for` is truncated to whole seconds once,
+    /// so the metadata, payload and idempotency key all carry the same instant.
+    pub fn fire_scheduled(
+        &self,
+        tenant: greentic_types::TenantCtx,
+        schedule_name: &str,
+        scheduled_for: DateTime<Utc>,
+        fired_at: DateTime<Utc>,
+        sequence: u64,
+    ) -> Result<greentic_types::EventEnvelope, ProviderError> {
+        let schedule = self.schedule(schedule_name)?;
+        let timezone = parse_timezone(schedule.timezone.as_deref())?;
+        let due = scheduled_for;
+        let scheduled_for = scheduled_for.trunc_subsecs(0);
 
         let mut metadata = BTreeMap::new();
         metadata.insert("schedule_name".into(), schedule.name.clone());
-        metadata.insert("cron".into(), schedule.cron.clone());
+        metadata.insert("scheduled_for".into(), scheduled_for.to_rfc3339());
+        metadata.insert("fired_at".into(), fired_at.to_rfc3339());
+        metadata.insert("schedule_kind".into(), schedule.kind.name().into());
+        match &schedule.kind {
+            ScheduleKind::Cron { cron } => {
+                metadata.insert("cron".into(), cron.clone());
+            }
+            ScheduleKind::Interval { every_secs, .. } => {
+                metadata.insert("every_secs".into(), every_secs.to_string());
+            }
+            ScheduleKind::Rrule { rrule, dtstart, .. } => {
+                metadata.insert("rrule".into(), rrule.clone());
+                metadata.insert(
+                    "dtstart".into(),
+                    dtstart.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                );
+            }
+            ScheduleKind::Once { .. } | ScheduleKind::Delay { .. } => {}
+        }
+        if let Trigger::At(at) = self.trigger(schedule_name)? {
+            metadata.insert("at".into(), at.to_rfc3339());
+        }
+        metadata.insert("timezone".into(), timezone.name().to_string());
+        if schedule.jitter.is_some()
+            && let Some((nominal, offset)) = self.nominal_for(&tenant, schedule, due)?
+        {
+            metadata.insert("nominal_fire".into(), nominal.to_rfc3339());
+            metadata.insert("jitter_secs".into(), offset.num_seconds().to_string());
+        }
+        metadata.insert("sequence".into(), sequence.to_string());
+        let payload = if schedule.payload_template {
+            template::render_payload(
+                &schedule.payload,
+                &FireContext {
+                    schedule: &schedule.name,
+                    sequence,
+                    scheduled_for,
+                    timezone,
+                    tenant: &tenant,
+                },
+            )
+            .map_err(|err| ProviderError::Config(format!("schedule {}: {err}", schedule.name)))?
+        } else {
+            schedule.payload.clone()
+        };
+
+        set_idempotency_key(
+            &mut metadata,
+            fire_idempotency_key(&tenant, &schedule.name, scheduled_for),
+        );
 
         Ok(new_event(
             schedule.topic.clone(),
@@ -35,16 +406,36 @@ impl TimerSource {
             tenant,
             Some(schedule.name.clone()),
             None,
-            schedule.payload.clone(),
+            payload,
             metadata,
         ))
     }
 }
 
+/// Idempotency key for one fire: identical for retries of the same tick, distinct across
+/// tenants, schedules and scheduled instants. `scheduled_for` is truncated to whole seconds.
+pub fn fire_idempotency_key(
+    tenant: &greentic_types::TenantCtx,
+    schedule_name: &str,
+    scheduled_for: DateTime<Utc>,
+) -> String {
+    format!(
+        "timer/{}/{}/{}",
+        tenant_key(tenant),
+        schedule_name,
+        scheduled_for
+            .trunc_subsecs(0)
+            .to_rfc3339_opts(SecondsFormat::Secs, true)
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use provider_core::{Schedule, SchedulerConfig};
+    use provider_core::{
+        BusinessCalendar, CalendarSource, CatchUp, DstPolicy, Jitter, JitterMode, Schedule,
+        ScheduleKind, SchedulerConfig,
+    };
     use serde_json::json;
 
     fn tenant() -> greentic_types::TenantCtx {
@@ -60,16 +451,649 @@ mod tests {
         let source = TimerSource::new(SchedulerConfig {
             schedules: vec![Schedule {
                 name: "daily".into(),
-                cron: "0 0 * * *".into(),
+                kind: ScheduleKind::Cron {
+                    cron: "0 0 * * *".into(),
+                },
                 topic: "timer.daily.summary".into(),
                 payload: json!({"kind": "daily"}),
+                payload_template: false,
+                timezone: None,
+                dst_policy: DstPolicy::default(),
+                catch_up: Default::default(),
+                jitter: None,
+                calendar: None,
             }],
-        });
+        })
+        .expect("valid config");
 
         let event = source.fire(tenant(), "daily").expect("event");
         assert_eq!(event.topic, "timer.daily.summary");
         assert_eq!(event.subject, Some("daily".into()));
         assert_eq!(event.payload, json!({"kind": "daily"}));
         assert_eq!(event.metadata.get("cron"), Some(&"0 0 * * *".into()));
+        assert_eq!(event.metadata.get("schedule_kind"), Some(&"cron".into()));
+        assert_eq!(event.metadata.get("timezone"), Some(&"UTC".into()));
+    }
+
+    #[test]
+    fn stamps_kind_for_each_trigger() {
+        let loaded_at = DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let interval = schedule_of(
+            "poll",
+            ScheduleKind::Interval {
+                every_secs: 300,
+                anchor: None,
+            },
+        );
+        let delayed = schedule_of(
+            "reminder",
+            ScheduleKind::Delay {
+                delay_secs: 60,
+                anchor: None,
+            },
+        );
+        let once = schedule_of(
+            "launch",
+            ScheduleKind::Once {
+                at: loaded_at + chrono::Duration::days(1),
+            },
+        );
+        let source = TimerSource::new_at(
+            SchedulerConfig {
+                schedules: vec![interval, delayed, once],
+            },
+            loaded_at,
+        )
+        .unwrap();
+
+        let poll = source.fire(tenant(), "poll").unwrap();
+        assert_eq!(poll.metadata.get("schedule_kind"), Some(&"interval".into()));
+        assert_eq!(poll.metadata.get("every_secs"), Some(&"300".into()));
+        assert_eq!(
+            source.next_fire_after("poll", loaded_at).unwrap(),
+            Some(loaded_at + chrono::Duration::minutes(5))
+        );
+
+        let reminder = source.fire(tenant(), "reminder").unwrap();
+        assert_eq!(
+            reminder.metadata.get("schedule_kind"),
+            Some(&"delay".into())
+        );
+        assert_eq!(
+            reminder.metadata.get("at"),
+            Some(&"2024-05-01T12:01:00+00:00".into())
+        );
+        let launch = source.fire(tenant(), "launch").unwrap();
+        assert_eq!(launch.metadata.get("schedule_kind"), Some(&"once".into()));
+        assert_eq!(source.upcoming("launch", 3).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn delays_keep_their_fire_time_across_reloads() {
+        let loaded_at = DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let reminder = schedule_of(
+            "reminder",
+            ScheduleKind::Delay {
+                delay_secs: 60,
+                anchor: None,
+            },
+        );
+        let source = TimerSource::new_at(
+            SchedulerConfig {
+                schedules: vec![reminder],
+            },
+            loaded_at,
+        )
+        .unwrap();
+        let due = Some(loaded_at + chrono::Duration::minutes(1));
+        assert_eq!(source.next_fire_after("reminder", loaded_at).unwrap(), due);
+
+        let stored = serde_json::to_value(&source).unwrap();
+        assert_eq!(
+            stored["config"]["schedules"][0]["anchor"],
+            "2024-05-01T12:00:00Z"
+        );
+        let reloaded: TimerSource = serde_json::from_value(stored).unwrap();
+        assert_eq!(reloaded, source);
+        assert_eq!(
+            reloaded.next_fire_after("reminder", loaded_at).unwrap(),
+            due
+        );
+    }
+
+    fn schedule(name: &str, cron: &str) -> Schedule {
+        schedule_of(name, ScheduleKind::Cron { cron: cron.into() })
+    }
+
+    fn schedule_of(name: &str, kind: ScheduleKind) -> Schedule {
+        Schedule {
+            name: name.into(),
+            kind,
+            topic: format!("timer.{name}"),
+            payload: json!({}),
+            payload_template: false,
+            timezone: None,
+            dst_policy: DstPolicy::default(),
+            catch_up: Default::default(),
+            jitter: None,
+            calendar: None,
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_cron_at_load_time() {
+        let err = TimerSource::new(SchedulerConfig {
+            schedules: vec![schedule("ok", "@hourly"), schedule("bad", "0 0 31 2 *")],
+        })
+        .unwrap_err();
+        assert!(matches!(err, ProviderError::Config(msg) if msg.contains("schedule bad")));
+
+        let raw = json!({"config": {"schedules": [{
+            "name": "bad", "cron": "61 * * * *", "topic": "timer.bad", "payload": {}
+        }]}});
+        assert!(serde_json::from_value::<TimerSource>(raw).is_err());
+
+        let mut zoned = schedule("zoned", "@daily");
+        zoned.timezone = Some("Europe/Atlantis".into());
+        let err = TimerSource::new(SchedulerConfig {
+            schedules: vec![zoned],
+        })
+        .unwrap_err();
+        assert!(matches!(err, ProviderError::Config(msg) if msg.contains("unknown timezone")));
+    }
+
+    #[test]
+    fn evaluates_schedules_in_their_timezone() {
+        let mut morning = schedule("morning", "0 9 * * *");
+        morning.timezone = Some("Asia/Tokyo".into());
+        let source = TimerSource::new(SchedulerConfig {
+            schedules: vec![morning],
+        })
+        .unwrap();
+        let after = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let next = source.next_fire_after("morning", after).unwrap().unwrap();
+        assert_eq!(next.to_rfc3339(), "2024-01-02T00:00:00+00:00");
+        let event = source.fire(tenant(), "morning").unwrap();
+        assert_eq!(event.metadata.get("timezone"), Some(&"Asia/Tokyo".into()));
+    }
+
+    #[test]
+    fn computes_fire_times_per_schedule() {
+        let source = TimerSource::new(SchedulerConfig {
+            schedules: vec![schedule("quarter", "*/15 * * * *")],
+        })
+        .unwrap();
+        let after = DateTime::parse_from_rfc3339("2024-01-01T10:07:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let next = source.next_fire_after("quarter", after).unwrap().unwrap();
+        assert_eq!(next.to_rfc3339(), "2024-01-01T10:15:00+00:00");
+
+        let upcoming = source.upcoming("quarter", 3).unwrap();
+        assert_eq!(upcoming.len(), 3);
+        assert!(
+            upcoming
+                .windows(2)
+                .all(|w| w[1] - w[0] == chrono::Duration::minutes(15))
+        );
+        assert!(source.upcoming("missing", 1).is_err());
+
+        let roundtrip: TimerSource =
+            serde_json::from_value(serde_json::to_value(&source).unwrap()).unwrap();
+        assert_eq!(roundtrip, source);
+    }
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn hourly_with(policy: CatchUpPolicy, max_fires: u32) -> TimerSource {
+        let mut hourly = schedule("hourly", "@hourly");
+        hourly.catch_up = CatchUp { policy, max_fires };
+        TimerSource::new(SchedulerConfig {
+            schedules: vec![hourly],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn catch_up_applies_policy_and_cap() {
+        let last_fired = utc("2024-01-01T10:00:00Z");
+        let now = utc("2024-01-01T15:30:00Z");
+        let missed = |policy, max| {
+            hourly_with(policy, max)
+                .catch_up(&tenant(), "hourly", last_fired, now)
+                .unwrap()
+                .iter()
+                .map(|t| t.to_rfc3339())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            missed(CatchUpPolicy::FireAll, 100),
+            [
+                "2024-01-01T11:00:00+00:00",
+                "2024-01-01T12:00:00+00:00",
+                "2024-01-01T13:00:00+00:00",
+                "2024-01-01T14:00:00+00:00",
+                "2024-01-01T15:00:00+00:00",
+            ]
+        );
+        assert_eq!(
+            missed(CatchUpPolicy::FireAll, 2),
+            ["2024-01-01T14:00:00+00:00", "2024-01-01T15:00:00+00:00"]
+        );
+        assert_eq!(
+            missed(CatchUpPolicy::LatestOnly, 100),
+            ["2024-01-01T15:00:00+00:00"]
+        );
+        assert!(missed(CatchUpPolicy::LatestOnly, 0).is_empty());
+        assert!(missed(CatchUpPolicy::Skip, 100).is_empty());
+
+        let on_time = hourly_with(CatchUpPolicy::FireAll, 100);
+        let now = utc("2024-01-01T11:00:00Z");
+        assert_eq!(
+            on_time
+                .catch_up(&tenant(), "hourly", last_fired, now)
+                .unwrap(),
+            [now]
+        );
+        assert!(
+            on_time
+                .catch_up(&tenant(), "hourly", now, now)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn catch_up_only_walks_the_replayed_tail() {
+        let mut every_second = schedule_of(
+            "tick",
+            ScheduleKind::Interval {
+                every_secs: 1,
+                anchor: None,
+            },
+        );
+        every_second.catch_up = CatchUp {
+            policy: CatchUpPolicy::FireAll,
+            max_fires: 3,
+        };
+        let source = TimerSource::new(SchedulerConfig {
+            schedules: vec![every_second],
+        })
+        .unwrap();
+        // Ten years of one-second fires would take minutes to enumerate.
+        let missed = source
+            .catch_up(
+                &tenant(),
+                "tick",
+                utc("2014-01-01T00:00:00Z"),
+                utc("2024-01-01T00:00:00.5Z"),
+            )
+            .unwrap();
+        assert_eq!(
+            missed,
+            [
+                utc("2023-12-31T23:59:58Z"),
+                utc("2023-12-31T23:59:59Z"),
+                utc("2024-01-01T00:00:00Z"),
+            ]
+        );
+
+        let nothing_due = hourly_with(CatchUpPolicy::LatestOnly, 10);
+        let missed = nothing_due
+            .catch_up(
+                &tenant(),
+                "hourly",
+                utc("2024-01-01T10:00:00Z"),
+                utc("2024-01-01T10:59:59Z"),
+            )
+            .unwrap();
+        assert!(missed.is_empty());
+    }
+
+    #[test]
+    fn missed_fires_carry_scheduled_for_and_fired_at() {
+        let source = hourly_with(CatchUpPolicy::FireAll, 10);
+        let now = utc("2024-01-01T12:15:00Z");
+        let events = source
+            .fire_missed(tenant(), "hourly", utc("2024-01-01T10:00:00Z"), 7, now)
+            .unwrap();
+        let stamps: Vec<_> = events
+            .iter()
+            .map(|event| {
+                (
+                    event.metadata["scheduled_for"].as_str(),
+                    event.metadata["fired_at"].as_str(),
+                    event.metadata["sequence"].as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            stamps,
+            [
+                (
+                    "2024-01-01T11:00:00+00:00",
+                    "2024-01-01T12:15:00+00:00",
+                    "8"
+                ),
+                (
+                    "2024-01-01T12:00:00+00:00",
+                    "2024-01-01T12:15:00+00:00",
+                    "9"
+                ),
+            ]
+        );
+
+        let live = source.fire(tenant(), "hourly").unwrap();
+        let scheduled_for = utc(&live.metadata["scheduled_for"]);
+        assert_eq!(scheduled_for.timestamp() % 3600, 0);
+        assert!(scheduled_for <= utc(&live.metadata["fired_at"]));
+    }
+
+    #[test]
+    fn retried_fires_share_the_latest_due_instant() {
+        let mut hourly = schedule("hourly", "0 * * * *");
+        hourly.jitter = Some(Jitter {
+            window_secs: 600,
+            mode: JitterMode::Spread,
+        });
+        let source = TimerSource::new(SchedulerConfig {
+            schedules: vec![hourly],
+        })
+        .unwrap();
+        let due = source
+            .next_fire_for(&tenant(), "hourly", utc("2024-01-01T09:59:00Z"))
+            .unwrap()
+            .unwrap();
+        for retry in [
+            chrono::Duration::zero(),
+            chrono::Duration::milliseconds(1250),
+            chrono::Duration::minutes(40),
+        ] {
+            assert_eq!(
+                source
+                    .previous_fire_for(&tenant(), "hourly", due + retry)
+                    .unwrap(),
+                Some(due),
+                "{retry}"
+            );
+        }
+        assert_eq!(
+            source
+                .previous_fire_for(&tenant(), "hourly", due - chrono::Duration::seconds(1))
+                .unwrap()
+                .map(|earlier| due - earlier),
+            Some(chrono::Duration::hours(1))
+        );
+
+        let never = schedule_of(
+            "launch",
+            ScheduleKind::Once {
+                at: utc("2030-01-01T00:00:00Z"),
+            },
+        );
+        let source = TimerSource::new(SchedulerConfig {
+            schedules: vec![never],
+        })
+        .unwrap();
+        assert_eq!(
+            source
+                .previous_fire_for(&tenant(), "launch", utc("2024-01-01T00:00:00Z"))
+                .unwrap(),
+            None
+        );
+
+        let key = |at| fire_idempotency_key(&tenant(), "hourly", utc(at));
+        assert_eq!(key("2024-01-01T10:04:07.250Z"), key("2024-01-01T10:04:07Z"));
+        assert!(key("2024-01-01T10:04:07Z").ends_with("/hourly/2024-01-01T10:04:07Z"));
+    }
+
+    #[test]
+    fn renders_payload_templates_per_fire() {
+        let mut nightly = schedule("nightly", "0 1 * * *");
+        nightly.timezone = Some("America/New_York".into());
+        nightly.payload = json!({
+            "title": "report for {{date yesterday}}",
+            "run": "{{schedule}} #{{seq}} for {{tenant}}",
+        });
+        nightly.payload_template = true;
+        let source = TimerSource::new(SchedulerConfig {
+            schedules: vec![nightly],
+        })
+        .unwrap();
+        let scheduled_for = utc("2024-03-10T06:00:00Z");
+        let event = source
+            .fire_scheduled(tenant(), "nightly", scheduled_for, scheduled_for, 12)
+            .unwrap();
+        assert_eq!(
+            event.payload,
+            json!({"title": "report for 2024-03-09", "run": "nightly #12 for acme"})
+        );
+        assert_eq!(event.metadata.get("sequence"), Some(&"12".into()));
+
+        let mut broken = schedule("broken", "@daily");
+        broken.payload = json!({"title": "{{#each}}"});
+        broken.payload_template = true;
+        let err = TimerSource::new(SchedulerConfig {
+            schedules: vec![broken],
+        })
+        .unwrap_err();
+        assert!(matches!(err, ProviderError::Config(msg) if msg.contains("schedule broken")));
+    }
+
+    #[test]
+    fn payloads_are_literal_unless_templated() {
+        let mut legacy = schedule("legacy", "@daily");
+        legacy.payload = json!({"body": "Hello {{name}}", "raw": "{{#each}}"});
+        let source = TimerSource::new(SchedulerConfig {
+            schedules: vec![legacy.clone()],
+        })
+        .unwrap();
+        let event = source.fire(tenant(), "legacy").unwrap();
+        assert_eq!(event.payload, legacy.payload);
+        assert!(
+            !serde_json::to_value(&legacy)
+                .unwrap()
+                .as_object()
+                .unwrap()
+                .contains_key("payload_template")
+        );
+    }
+
+    #[test]
+    fn calendars_skip_excluded_days() {
+        let mut standup = schedule("standup", "0 9 * * MON-FRI");
+        standup.timezone = Some("America/New_York".into());
+        standup.catch_up = CatchUp {
+            policy: CatchUpPolicy::FireAll,
+            max_fires: 10,
+        };
+        standup.calendar = Some(BusinessCalendar {
+            include: Vec::new(),
+            exclude: vec![
+                CalendarSource::Dates(vec![chrono::NaiveDate::from_ymd_opt(2024, 7, 5).unwrap()]),
+                CalendarSource::Ical(
+                    "BEGIN:VCALENDAR\nBEGIN:VEVENT\nDTSTART;VALUE=DATE:20200704\n\
+                     RRULE:FREQ=YEARLY\nEND:VEVENT\nEND:VCALENDAR"
+                        .into(),
+                ),
+            ],
+        });
+        let source = TimerSource::new(SchedulerConfig {
+            schedules: vec![standup],
+        })
+        .unwrap();
+        let next = |after| {
+            source
+                .next_fire_after("standup", utc(after))
+                .unwrap()
+                .unwrap()
+        };
+        // Wed 3 July fires, Thu 4 (holiday) and Fri 5 (blackout) are skipped.
+        assert_eq!(next("2024-07-02T14:00:00Z"), utc("2024-07-03T13:00:00Z"));
+        assert_eq!(next("2024-07-03T13:00:00Z"), utc("2024-07-08T13:00:00Z"));
+        let missed = source
+            .catch_up(
+                &tenant(),
+                "standup",
+                utc("2024-07-03T13:00:00Z"),
+                utc("2024-07-08T00:00:00Z"),
+            )
+            .unwrap();
+        assert!(missed.is_empty());
+
+        let mut broken = schedule("broken", "@daily");
+        broken.calendar = Some(BusinessCalendar {
+            include: vec![CalendarSource::Ical(
+                "BEGIN:VEVENT\nDTSTART:2024\nEND:VEVENT".into(),
+            )],
+            exclude: Vec::new(),
+        });
+        let err = TimerSource::new(SchedulerConfig {
+            schedules: vec![broken],
+        })
+        .unwrap_err();
+        assert!(matches!(err, ProviderError::Config(msg) if msg.contains("schedule broken")));
+    }
+
+    #[test]
+    fn jitter_offsets_fires_per_tenant() {
+        let mut nightly = schedule("nightly", "0 0 * * *");
+        nightly.jitter = Some(Jitter {
+            window_secs: 1800,
+            mode: JitterMode::Spread,
+        });
+        let source = TimerSource::new(SchedulerConfig {
+            schedules: vec![nightly],
+        })
+        .unwrap();
+        let after = utc("2024-01-01T12:00:00Z");
+        let midnight = utc("2024-01-02T00:00:00Z");
+
+        let due = source
+            .next_fire_for(&tenant(), "nightly", after)
+            .unwrap()
+            .unwrap();
+        let offset = due - midnight;
+        assert!(offset >= chrono::Duration::zero() && offset < chrono::Duration::minutes(30));
+        assert_eq!(
+            source.next_fire_after("nightly", after).unwrap(),
+            Some(midnight)
+        );
+        // Spread keeps the same offset every day, and a fire still pending past its
+        // nominal time is next.
+        let following = source
+            .next_fire_for(&tenant(), "nightly", due)
+            .unwrap()
+            .unwrap();
+        assert_eq!(following - due, chrono::Duration::days(1));
+        if offset > chrono::Duration::zero() {
+            assert_eq!(
+                source
+                    .next_fire_for(&tenant(), "nightly", midnight)
+                    .unwrap(),
+                Some(due)
+            );
+        }
+
+        let offsets: std::collections::BTreeSet<_> = (0..20)
+            .map(|n| {
+                let other = greentic_types::TenantCtx::new(
+                    greentic_types::EnvId::try_from("prod").unwrap(),
+                    greentic_types::TenantId::try_from(format!("tenant-{n}").as_str()).unwrap(),
+                );
+                source
+                    .next_fire_for(&other, "nightly", after)
+                    .unwrap()
+                    .unwrap()
+            })
+            .collect();
+        assert!(offsets.len() > 1);
+
+        let event = source
+            .fire_scheduled(tenant(), "nightly", due, due, 1)
+            .unwrap();
+        assert_eq!(
+            event.metadata.get("nominal_fire"),
+            Some(&midnight.to_rfc3339())
+        );
+        assert_eq!(
+            event.metadata.get("jitter_secs"),
+            Some(&offset.num_seconds().to_string())
+        );
+        assert_eq!(event.metadata["scheduled_for"], due.to_rfc3339());
+    }
+
+    #[test]
+    fn retried_fires_share_an_idempotency_key() {
+        let source = hourly_with(CatchUpPolicy::Skip, 0);
+        let scheduled_for = utc("2024-01-01T10:00:00Z");
+        let fire = |tenant, fired_at| {
+            source
+                .fire_scheduled(tenant, "hourly", scheduled_for, utc(fired_at), 1)
+                .unwrap()
+        };
+        let first = fire(tenant(), "2024-01-01T10:00:01Z");
+        let retry = fire(tenant(), "2024-01-01T10:00:30Z");
+        assert_eq!(
+            first.metadata["idempotency_key"],
+            "timer/dev/acme/_/hourly/2024-01-01T10:00:00Z"
+        );
+        assert_eq!(
+            first.metadata["idempotency_key"],
+            retry.metadata["idempotency_key"]
+        );
+
+        let later = source
+            .fire_scheduled(
+                tenant(),
+                "hourly",
+                utc("2024-01-01T11:00:00Z"),
+                utc("2024-01-01T11:00:00Z"),
+                2,
+            )
+            .unwrap();
+        assert_ne!(
+            first.metadata["idempotency_key"],
+            later.metadata["idempotency_key"]
+        );
+
+        let other_tenant = greentic_types::TenantCtx::new(
+            greentic_types::EnvId::try_from("dev").unwrap(),
+            greentic_types::TenantId::try_from("globex").unwrap(),
+        );
+        let other = fire(other_tenant, "2024-01-01T10:00:01Z");
+        assert_ne!(
+            first.metadata["idempotency_key"],
+            other.metadata["idempotency_key"]
+        );
+
+        // Sub-second instants are truncated once, so metadata and key agree.
+        let fractional = source
+            .fire_scheduled(
+                tenant(),
+                "hourly",
+                utc("2024-01-01T10:00:00.75Z"),
+                utc("2024-01-01T10:00:01Z"),
+                1,
+            )
+            .unwrap();
+        assert_eq!(
+            fractional.metadata["scheduled_for"],
+            first.metadata["scheduled_for"]
+        );
+        assert_eq!(
+            fractional.metadata["idempotency_key"],
+            first.metadata["idempotency_key"]
+        );
     }
 }
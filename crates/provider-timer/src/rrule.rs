@@ -0,0 +1,720 @@
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday};
+use chrono_tz::Tz;
+use provider_core::ProviderError;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::cron::days_in_month;
+use crate::ical::IcalTime;
+
+/// Recurrence frequency, finest first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Frequency {
+    Secondly,
+    Minutely,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A `BYDAY` entry: a weekday, optionally the n-th (negative: from the end) of the month or
+/// year.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ByDay {
+    pub nth: Option<i32>,
+    pub weekday: Weekday,
+}
+
+/// Parsed iCalendar (RFC 5545) recurrence rule.
+///
+/// Supports every rule part: `FREQ`, `INTERVAL`, `COUNT`, `UNTIL`, `BYSECOND`, `BYMINUTE`,
+/// `BYHOUR`, `BYDAY`, `BYMONTHDAY`, `BYYEARDAY`, `BYWEEKNO`, `BYMONTH`, `BYSETPOS` and
+/// `WKST`. Occurrences are expanded in floating local time from a `DTSTART`; a `DTSTART`
+/// the rule does not match is not itself an occurrence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RRule {
+    source: String,
+    freq: Frequency,
+    interval: u32,
+    count: Option<u32>,
+    until: Option<IcalTime>,
+    by_second: Vec<u32>,
+    by_minute: Vec<u32>,
+    by_hour: Vec<u32>,
+    by_day: Vec<ByDay>,
+    by_month_day: Vec<i32>,
+    by_year_day: Vec<i32>,
+    by_week_no: Vec<i32>,
+    by_month: Vec<u32>,
+    by_set_pos: Vec<i32>,
+    wkst: Weekday,
+}
+
+impl RRule {
+    /// Parse a rule, with or without a leading `RRULE:`.
+    pub fn parse(rule: &str) -> Result<Self, ProviderError> {
+        parse_rule(rule.trim())
+            .map_err(|detail| ProviderError::Config(format!("invalid RRULE `{rule}`: {detail}")))
+    }
+
+    /// The rule as written.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Occurrences from `dtstart`, in order, as local times. `tz` places a UTC or zoned
+    /// `UNTIL` on the local timeline.
+    pub fn occurrences(&self, dtstart: NaiveDateTime, tz: Tz) -> Occurrences<'_> {
+        let until = self.until.map(|until| match until {
+            IcalTime::Date(date) => date.and_time(NaiveTime::MIN) + Duration::days(1),
+            other => other.to_local(tz) + Duration::nanoseconds(1),
+        });
+        Occurrences {
+            rule: self,
+            dtstart,
+            until,
+            period: Some(self.first_period(dtstart)),
+            pending: Vec::new(),
+            emitted: 0,
+        }
+    }
+
+    /// First occurrence strictly after the local time `after`. Rules without `COUNT` start
+    /// expanding at the period containing `after` instead of replaying from `dtstart`.
+    pub fn next_local_after(
+        &self,
+        dtstart: NaiveDateTime,
+        tz: Tz,
+        after: NaiveDateTime,
+    ) -> Option<NaiveDateTime> {
+        let mut occurrences = self.occurrences(dtstart, tz);
+        if self.count.is_none() {
+            occurrences.period = self.period_containing(dtstart, after);
+        }
+        occurrences.find(|occurrence| *occurrence > after)
+    }
+
+    /// Start of the period on `dtstart`'s grid that contains `at`.
+    fn period_containing(
+        &self,
+        dtstart: NaiveDateTime,
+        at: NaiveDateTime,
+    ) -> Option<NaiveDateTime> {
+        let first = self.first_period(dtstart);
+        if at <= first {
+            return Some(first);
+        }
+        let interval = i64::from(self.interval);
+        let step_secs = match self.freq {
+            Frequency::Yearly | Frequency::Monthly => {
+                let months = i64::from(at.year() - first.year()) * 12 + i64::from(at.month())
+                    - i64::from(first.month());
+                let months = if self.freq == Frequency::Yearly {
+                    months / 12 / interval * interval * 12
+                } else {
+                    months / interval * interval
+                };
+                return first.checked_add_months(Months::new(u32::try_from(months).ok()?));
+            }
+            Frequency::Weekly => 7 * 86_400,
+            Frequency::Daily => 86_400,
+            Frequency::Hourly => 3_600,
+            Frequency::Minutely => 60,
+            Frequency::Secondly => 1,
+        } * interval;
+        let periods = (at - first).num_seconds() / step_secs;
+        first.checked_add_signed(Duration::seconds(periods.checked_mul(step_secs)?))
+    }
+
+    fn first_period(&self, dtstart: NaiveDateTime) -> NaiveDateTime {
+        let date = dtstart.date();
+        let day = match self.freq {
+            Frequency::Yearly => date.with_ordinal(1),
+            Frequency::Monthly => date.with_day(1),
+            Frequency::Weekly => Some(week_start(date, self.wkst)),
+            _ => Some(date),
+        }
+        .expect("valid period start");
+        let time = match self.freq {
+            Frequency::Hourly => NaiveTime::from_hms_opt(dtstart.hour(), 0, 0),
+            Frequency::Minutely => NaiveTime::from_hms_opt(dtstart.hour(), dtstart.minute(), 0),
+            Frequency::Secondly => {
+                NaiveTime::from_hms_opt(dtstart.hour(), dtstart.minute(), dtstart.second())
+            }
+            _ => Some(NaiveTime::MIN),
+        }
+        .expect("valid period time");
+        day.and_time(time)
+    }
+
+    fn next_period(&self, period: NaiveDateTime) -> Option<NaiveDateTime> {
+        let interval = self.interval;
+        match self.freq {
+            Frequency::Yearly => period.checked_add_months(Months::new(interval.checked_mul(12)?)),
+            Frequency::Monthly => period.checked_add_months(Months::new(interval)),
+            Frequency::Weekly => period.checked_add_signed(Duration::weeks(interval.into())),
+            Frequency::Daily => period.checked_add_signed(Duration::days(interval.into())),
+            Frequency::Hourly => period.checked_add_signed(Duration::hours(interval.into())),
+            Frequency::Minutely => period.checked_add_signed(Duration::minutes(interval.into())),
+            Frequency::Secondly => period.checked_add_signed(Duration::seconds(interval.into())),
+        }
+    }
+
+    /// For sub-daily rules, the first later period worth expanding when `period` falls on a
+    /// day, hour or minute the rule excludes.
+    fn skip_period(&self, period: NaiveDateTime, dtstart: NaiveDateTime) -> Option<NaiveDateTime> {
+        if self.freq > Frequency::Hourly {
+            return None;
+        }
+        let boundary = if !self.matches_day(period.date(), dtstart.date()) {
+            period.date().succ_opt()?.and_time(NaiveTime::MIN)
+        } else if self.freq < Frequency::Hourly && !allows(&self.by_hour, period.hour()) {
+            period.with_minute(0)?.with_second(0)? + Duration::hours(1)
+        } else if self.freq < Frequency::Minutely && !allows(&self.by_minute, period.minute()) {
+            period.with_second(0)? + Duration::minutes(1)
+        } else {
+            return None;
+        };
+        let step = match self.freq {
+            Frequency::Hourly => 3600,
+            Frequency::Minutely => 60,
+            _ => 1,
+        } * i64::from(self.interval);
+        let gap = (boundary - period).num_seconds();
+        let steps = (gap + step - 1) / step;
+        period.checked_add_signed(Duration::seconds(steps.max(1) * step))
+    }
+
+    /// Candidate occurrences of the period starting at `period`, sorted, before `DTSTART`,
+    /// `COUNT` and `UNTIL` are applied.
+    fn expand(&self, period: NaiveDateTime, dtstart: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let first = period.date();
+        let days: Vec<NaiveDate> = match self.freq {
+            Frequency::Yearly => first
+                .iter_days()
+                .take_while(|d| d.year() == first.year())
+                .collect(),
+            Frequency::Monthly => first
+                .iter_days()
+                .take_while(|d| d.month() == first.month())
+                .collect(),
+            Frequency::Weekly => first.iter_days().take(7).collect(),
+            _ => vec![first],
+        };
+        let hours = self.time_values(
+            Frequency::Hourly,
+            &self.by_hour,
+            period.hour(),
+            dtstart.hour(),
+        );
+        let minutes = self.time_values(
+            Frequency::Minutely,
+            &self.by_minute,
+            period.minute(),
+            dtstart.minute(),
+        );
+        let seconds = self.time_values(
+            Frequency::Secondly,
+            &self.by_second,
+            period.second(),
+            dtstart.second(),
+        );
+
+        let mut set = Vec::new();
+        for day in days
+            .into_iter()
+            .filter(|day| self.matches_day(*day, dtstart.date()))
+        {
+            for hour in &hours {
+                for minute in &minutes {
+                    for second in &seconds {
+                        if let Some(time) = NaiveTime::from_hms_opt(*hour, *minute, *second) {
+                            set.push(day.and_time(time));
+                        }
+                    }
+                }
+            }
+        }
+        set.sort();
+        set.dedup();
+        if self.by_set_pos.is_empty() {
+            return set;
+        }
+        let len = set.len() as i32;
+        let mut picked: Vec<_> = self
+            .by_set_pos
+            .iter()
+            .filter_map(|pos| {
+                let index = if *pos > 0 { pos - 1 } else { len + pos };
+                (0..len).contains(&index).then(|| set[index as usize])
+            })
+            .collect();
+        picked.sort();
+        picked.dedup();
+        picked
+    }
+
+    /// Values of one time unit within a period: the period's own value when the frequency is
+    /// at least as fine as `unit` (kept only if `by` allows it), else `by` or DTSTART's value.
+    fn time_values(&self, unit: Frequency, by: &[u32], current: u32, start: u32) -> Vec<u32> {
+        if self.freq <= unit {
+            if allows(by, current) {
+                vec![current]
+            } else {
+                Vec::new()
+            }
+        } else if by.is_empty() {
+            vec![start]
+        } else {
+            let mut values = by.to_vec();
+            values.sort_unstable();
+            values
+        }
+    }
+
+    fn matches_day(&self, date: NaiveDate, dtstart: NaiveDate) -> bool {
+        let implicit = self.by_week_no.is_empty()
+            && self.by_year_day.is_empty()
+            && self.by_month_day.is_empty()
+            && self.by_day.is_empty();
+
+        if !self.by_month.is_empty() {
+            if !self.by_month.contains(&date.month()) {
+                return false;
+            }
+        } else if implicit && self.freq == Frequency::Yearly && date.month() != dtstart.month() {
+            return false;
+        }
+
+        if !self.by_week_no.is_empty() {
+            let (week, weeks) = week_number(date, self.wkst);
+            if !self
+                .by_week_no
+                .iter()
+                .any(|n| *n == week || *n == week - weeks - 1)
+            {
+                return false;
+            }
+        }
+
+        if !self.by_year_day.is_empty() {
+            let ordinal = date.ordinal() as i32;
+            let length = days_in_year(date.year()) as i32;
+            if !self
+                .by_year_day
+                .iter()
+                .any(|n| *n == ordinal || *n == ordinal - length - 1)
+            {
+                return false;
+            }
+        }
+
+        let month_length = days_in_month(date.year(), date.month()) as i32;
+        if !self.by_month_day.is_empty() {
+            let day = date.day() as i32;
+            if !self
+                .by_month_day
+                .iter()
+                .any(|n| *n == day || *n == day - month_length - 1)
+            {
+                return false;
+            }
+        } else if implicit
+            && matches!(self.freq, Frequency::Yearly | Frequency::Monthly)
+            && date.day() != dtstart.day()
+        {
+            return false;
+        }
+
+        if !self.by_day.is_empty() {
+            let monthly = self.freq == Frequency::Monthly
+                || (self.freq == Frequency::Yearly && !self.by_month.is_empty());
+            let (index, length) = if monthly {
+                (date.day() as i32, month_length)
+            } else {
+                (date.ordinal() as i32, days_in_year(date.year()) as i32)
+            };
+            let nth_from_start = (index - 1) / 7 + 1;
+            let nth_from_end = -((length - index) / 7 + 1);
+            let ignore_nth = !self.by_week_no.is_empty();
+            if !self.by_day.iter().any(|by| {
+                by.weekday == date.weekday()
+                    && (ignore_nth
+                        || by
+                            .nth
+                            .is_none_or(|n| n == nth_from_start || n == nth_from_end))
+            }) {
+                return false;
+            }
+        } else if implicit && self.freq == Frequency::Weekly && date.weekday() != dtstart.weekday()
+        {
+            return false;
+        }
+        true
+    }
+}
+
+impl FromStr for RRule {
+    type Err = ProviderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for RRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Iterator over a rule's occurrences; see [`RRule::occurrences`].
+#[derive(Clone, Debug)]
+pub struct Occurrences<'a> {
+    rule: &'a RRule,
+    dtstart: NaiveDateTime,
+    /// Exclusive local upper bound derived from `UNTIL`.
+    until: Option<NaiveDateTime>,
+    period: Option<NaiveDateTime>,
+    /// Remaining occurrences of the current period, latest first.
+    pending: Vec<NaiveDateTime>,
+    emitted: u32,
+}
+
+impl Iterator for Occurrences<'_> {
+    type Item = NaiveDateTime;
+
+    fn next(&mut self) -> Option<NaiveDateTime> {
+        if self.rule.count.is_some_and(|count| self.emitted >= count) {
+            return None;
+        }
+        let mut empty = 0;
+        while self.pending.is_empty() {
+            let period = self.period?;
+            if self.until.is_some_and(|until| period >= until)
+                || empty > max_empty_periods(self.rule.freq)
+            {
+                self.period = None;
+                return None;
+            }
+            if let Some(later) = self.rule.skip_period(period, self.dtstart) {
+                self.period = Some(later);
+                empty += 1;
+                continue;
+            }
+            self.pending = self
+                .rule
+                .expand(period, self.dtstart)
+                .into_iter()
+                .filter(|at| *at >= self.dtstart)
+                .rev()
+                .collect();
+            self.period = self.rule.next_period(period);
+            empty += 1;
+        }
+        let next = self.pending.pop()?;
+        if self.until.is_some_and(|until| next >= until) {
+            self.pending.clear();
+            self.period = None;
+            return None;
+        }
+        self.emitted += 1;
+        Some(next)
+    }
+}
+
+/// Consecutive periods without an occurrence before a rule is considered exhausted: a
+/// 400-year Gregorian cycle, which repeats every calendar pattern.
+fn max_empty_periods(freq: Frequency) -> u32 {
+    match freq {
+        Frequency::Yearly => 400,
+        Frequency::Monthly => 4_800,
+        Frequency::Weekly => 20_871,
+        _ => 146_097,
+    }
+}
+
+fn allows(by: &[u32], value: u32) -> bool {
+    by.is_empty() || by.contains(&value)
+}
+
+fn days_in_year(year: i32) -> u32 {
+    if NaiveDate::from_ymd_opt(year, 2, 29).is_some() {
+        366
+    } else {
+        365
+    }
+}
+
+fn week_start(date: NaiveDate, wkst: Weekday) -> NaiveDate {
+    let back = (7 + date.weekday().num_days_from_monday() - wkst.num_days_from_monday()) % 7;
+    date - Duration::days(back.into())
+}
+
+/// Start of week 1 of `year`: the first week starting on `wkst` with at least four days in
+/// the year.
+fn first_week_start(year: i32, wkst: Weekday) -> NaiveDate {
+    let jan1 = NaiveDate::from_ymd_opt(year, 1, 1).expect("valid year");
+    let start = week_start(jan1, wkst);
+    if (jan1 - start).num_days() > 3 {
+        start + Duration::days(7)
+    } else {
+        start
+    }
+}
+
+/// RFC 5545 week number of `date` and the number of weeks in its week-numbering year.
+fn week_number(date: NaiveDate, wkst: Weekday) -> (i32, i32) {
+    let mut year = date.year();
+    if date < first_week_start(year, wkst) {
+        year -= 1;
+    } else if date >= first_week_start(year + 1, wkst) {
+        year += 1;
+    }
+    let start = first_week_start(year, wkst);
+    let weeks = (first_week_start(year + 1, wkst) - start).num_days() / 7;
+    (((date - start).num_days() / 7 + 1) as i32, weeks as i32)
+}
+
+fn parse_rule(rule: &str) -> Result<RRule, String> {
+    let body = rule
+        .strip_prefix("RRULE:")
+        .or_else(|| rule.strip_prefix("rrule:"))
+        .unwrap_or(rule);
+    let mut parsed = RRule {
+        source: body.to_string(),
+        freq: Frequency::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        by_second: Vec::new(),
+        by_minute: Vec::new(),
+        by_hour: Vec::new(),
+        by_day: Vec::new(),
+        by_month_day: Vec::new(),
+        by_year_day: Vec::new(),
+        by_week_no: Vec::new(),
+        by_month: Vec::new(),
+        by_set_pos: Vec::new(),
+        wkst: Weekday::Mon,
+    };
+    let mut freq = None;
+    for part in body.split(';').filter(|part| !part.is_empty()) {
+        let (name, value) = part
+            .split_once('=')
+            .ok_or_else(|| format!("`{part}` is not NAME=VALUE"))?;
+        match name.to_ascii_uppercase().as_str() {
+            "FREQ" => freq = Some(parse_frequency(value)?),
+            "INTERVAL" => {
+                parsed.interval = value
+                    .parse()
+                    .ok()
+                    .filter(|interval| *interval > 0)
+                    .ok_or_else(|| format!("invalid INTERVAL `{value}`"))?
+            }
+            "COUNT" => {
+                parsed.count = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid COUNT `{value}`"))?,
+                )
+            }
+            "UNTIL" => parsed.until = Some(IcalTime::parse(value, None)?),
+            "BYSECOND" => parsed.by_second = parse_list(value, "BYSECOND", 0, 59)?,
+            "BYMINUTE" => parsed.by_minute = parse_list(value, "BYMINUTE", 0, 59)?,
+            "BYHOUR" => parsed.by_hour = parse_list(value, "BYHOUR", 0, 23)?,
+            "BYDAY" => {
+                parsed.by_day = value
+                    .split(',')
+                    .map(parse_by_day)
+                    .collect::<Result<_, _>>()?
+            }
+            "BYMONTHDAY" => parsed.by_month_day = parse_signed_list(value, "BYMONTHDAY", 31)?,
+            "BYYEARDAY" => parsed.by_year_day = parse_signed_list(value, "BYYEARDAY", 366)?,
+            "BYWEEKNO" => parsed.by_week_no = parse_signed_list(value, "BYWEEKNO", 53)?,
+            "BYMONTH" => parsed.by_month = parse_list(value, "BYMONTH", 1, 12)?,
+            "BYSETPOS" => parsed.by_set_pos = parse_signed_list(value, "BYSETPOS", 366)?,
+            "WKST" => parsed.wkst = parse_weekday(value)?,
+            other => return Err(format!("unknown rule part {other}")),
+        }
+    }
+    parsed.freq = freq.ok_or("FREQ is required")?;
+
+    if parsed.count.is_some() && parsed.until.is_some() {
+        return Err("COUNT and UNTIL are mutually exclusive".into());
+    }
+    let freq = parsed.freq;
+    if parsed.by_day.iter().any(|by| by.nth.is_some())
+        && !matches!(freq, Frequency::Monthly | Frequency::Yearly)
+    {
+        return Err("numbered BYDAY needs FREQ=MONTHLY or FREQ=YEARLY".into());
+    }
+    if !parsed.by_week_no.is_empty() && freq != Frequency::Yearly {
+        return Err("BYWEEKNO needs FREQ=YEARLY".into());
+    }
+    if !parsed.by_year_day.is_empty()
+        && matches!(
+            freq,
+            Frequency::Daily | Frequency::Weekly | Frequency::Monthly
+        )
+    {
+        return Err("BYYEARDAY is not allowed with this FREQ".into());
+    }
+    if !parsed.by_month_day.is_empty() && freq == Frequency::Weekly {
+        return Err("BYMONTHDAY is not allowed with FREQ=WEEKLY".into());
+    }
+    Ok(parsed)
+}
+
+fn parse_frequency(value: &str) -> Result<Frequency, String> {
+    Ok(match value.to_ascii_uppercase().as_str() {
+        "SECONDLY" => Frequency::Secondly,
+        "MINUTELY" => Frequency::Minutely,
+        "HOURLY" => Frequency::Hourly,
+        "DAILY" => Frequency::Daily,
+        "WEEKLY" => Frequency::Weekly,
+        "MONTHLY" => Frequency::Monthly,
+        "YEARLY" => Frequency::Yearly,
+        _ => return Err(format!("unknown FREQ `{value}`")),
+    })
+}
+
+fn parse_list(value: &str, name: &str, min: u32, max: u32) -> Result<Vec<u32>, String> {
+    value
+        .split(',')
+        .map(|item| {
+            item.parse()
+                .ok()
+                .filter(|n| (min..=max).contains(n))
+                .ok_or_else(|| format!("invalid {name} value `{item}`"))
+        })
+        .collect()
+}
+
+fn parse_signed_list(value: &str, name: &str, max: i32) -> Result<Vec<i32>, String> {
+    value
+        .split(',')
+        .map(|item| {
+            item.parse::<i32>()
+                .ok()
+                .filter(|n| *n != 0 && n.abs() <= max)
+                .ok_or_else(|| format!("invalid {name} value `{item}`"))
+        })
+        .collect()
+}
+
+fn parse_by_day(item: &str) -> Result<ByDay, String> {
+    let split = item.len().saturating_sub(2);
+    let (nth, day) = item
+        .is_char_boundary(split)
+        .then(|| item.split_at(split))
+        .ok_or_else(|| format!("invalid BYDAY value `{item}`"))?;
+    let nth = match nth {
+        "" => None,
+        text => Some(
+            text.parse::<i32>()
+                .ok()
+                .filter(|n| *n != 0 && n.abs() <= 53)
+                .ok_or_else(|| format!("invalid BYDAY value `{item}`"))?,
+        ),
+    };
+    Ok(ByDay {
+        nth,
+        weekday: parse_weekday(day)?,
+    })
+}
+
+fn parse_weekday(text: &str) -> Result<Weekday, String> {
+    Ok(match text.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return Err(format!("unknown weekday `{text}`")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y%m%dT%H%M%S").unwrap()
+    }
+
+    fn expand(rule: &str, dtstart: &str, n: usize) -> Vec<String> {
+        RRule::parse(rule)
+            .unwrap()
+            .occurrences(local(dtstart), Tz::UTC)
+            .take(n)
+            .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn expands_common_rules() {
+        assert_eq!(
+            expand("FREQ=YEARLY", "20240229T090000", 3),
+            ["2024-02-29 09:00", "2028-02-29 09:00", "2032-02-29 09:00"]
+        );
+        assert_eq!(
+            expand("RRULE:FREQ=MONTHLY;BYDAY=2TU", "20240101T100000", 3),
+            ["2024-01-09 10:00", "2024-02-13 10:00", "2024-03-12 10:00"]
+        );
+        assert_eq!(
+            expand("FREQ=WEEKLY;INTERVAL=3", "20240103T080000", 3),
+            ["2024-01-03 08:00", "2024-01-24 08:00", "2024-02-14 08:00"]
+        );
+        assert_eq!(
+            expand(
+                "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+                "20240101T170000",
+                3
+            ),
+            ["2024-01-31 17:00", "2024-02-29 17:00", "2024-03-29 17:00"]
+        );
+        assert_eq!(
+            expand("FREQ=HOURLY;INTERVAL=5;BYDAY=SA", "20240105T230000", 3),
+            ["2024-01-06 04:00", "2024-01-06 09:00", "2024-01-06 14:00"]
+        );
+    }
+
+    #[test]
+    fn count_and_until_bound_the_set() {
+        assert_eq!(expand("FREQ=DAILY;COUNT=2", "20240101T000000", 10).len(), 2);
+        assert_eq!(
+            expand("FREQ=DAILY;UNTIL=20240103", "20240101T120000", 10),
+            ["2024-01-01 12:00", "2024-01-02 12:00", "2024-01-03 12:00"]
+        );
+        let zoned = RRule::parse("FREQ=DAILY;UNTIL=20240102T165959Z").unwrap();
+        let tokyo: Vec<_> = zoned
+            .occurrences(local("20240101T020000"), chrono_tz::Asia::Tokyo)
+            .collect();
+        assert_eq!(tokyo, [local("20240101T020000"), local("20240102T020000")]);
+        assert!(expand("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30", "20240101T000000", 1).is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for bad in [
+            "INTERVAL=2",
+            "FREQ=FORTNIGHTLY",
+            "FREQ=DAILY;COUNT=2;UNTIL=20240101",
+            "FREQ=WEEKLY;BYDAY=2MO",
+            "FREQ=MONTHLY;BYWEEKNO=3",
+            "FREQ=WEEKLY;BYMONTHDAY=1",
+            "FREQ=DAILY;BYHOUR=24",
+            "FREQ=DAILY;BYMONTHDAY=0",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;BYDAY=XX",
+            "FREQ=DAILY;FOO=1",
+        ] {
+            assert!(RRule::parse(bad).is_err(), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::fmt::Write as _;
+use std::sync::LazyLock;
+
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use chrono_tz::Tz;
+use handlebars::{
+    Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderErrorReason, Template,
+};
+use provider_core::{ProviderError, tenant_key};
+use serde_json::{Value, json};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Shared by every render: strict mode, no HTML escaping and the `date` helper.
+static REGISTRY: LazyLock<Handlebars<'static>> = LazyLock::new(|| {
+    let mut registry = Handlebars::new();
+    registry.set_strict_mode(true);
+    registry.register_escape_fn(handlebars::no_escape);
+    registry.register_helper("date", Box::new(date_helper));
+    registry
+});
+
+/// Values a payload template can reference for one fire.
+#[derive(Clone, Debug)]
+pub struct FireContext<'a> {
+    pub schedule: &'a str,
+    pub sequence: u64,
+    pub scheduled_for: DateTime<Utc>,
+    pub timezone: Tz,
+    pub tenant: &'a greentic_types::TenantCtx,
+}
+
+impl FireContext<'_> {
+    /// Template data: fire times are local to the schedule's timezone, with `today`,
+    /// `yesterday` and `tomorrow` as `YYYY-MM-DD` dates relative to the fire.
+    fn data(&self) -> Value {
+        let local = self.scheduled_for.with_timezone(&self.timezone);
+        let today = local.date_naive();
+        let day = |date: Option<NaiveDate>| date.map(|d| d.format(DATE_FORMAT).to_string());
+        json!({
+            "schedule": self.schedule,
+            "seq": self.sequence,
+            "fire_time": local.to_rfc3339(),
+            "fire_time_utc": self.scheduled_for.to_rfc3339(),
+            "timezone": self.timezone.name(),
+            "today": day(Some(today)),
+            "yesterday": day(today.checked_sub_days(Days::new(1))),
+            "tomorrow": day(today.checked_add_days(Days::new(1))),
+            "env": self.tenant.env.as_str(),
+            "tenant": self.tenant.tenant.as_str(),
+            "team": self.tenant.team.as_ref().map(|team| team.as_str()),
+            "user": self.tenant.user.as_ref().map(|user| user.as_str()),
+            "tenant_key": tenant_key(self.tenant),
+        })
+    }
+}
+
+/// Check every string in `payload` parses as a template.
+pub fn validate_payload(payload: &Value) -> Result<(), ProviderError> {
+    match payload {
+        Value::String(text) => Template::compile(text)
+            .map(|_| ())
+            .map_err(|err| ProviderError::Config(format!("payload template: {err}"))),
+        Value::Array(items) => items.iter().try_for_each(validate_payload),
+        Value::Object(fields) => fields.values().try_for_each(validate_payload),
+        _ => Ok(()),
+    }
+}
+
+/// Render every string in `payload` as a handlebars template against `fire`. Templates run
+/// in strict mode without HTML escaping, so unknown variables are errors.
+pub fn render_payload(payload: &Value, fire: &FireContext<'_>) -> Result<Value, ProviderError> {
+    render_value(&REGISTRY, payload, &fire.data())
+}
+
+fn render_value(
+    registry: &Handlebars,
+    value: &Value,
+    data: &Value,
+) -> Result<Value, ProviderError> {
+    Ok(match value {
+        Value::String(text) if text.contains("{{") => Value::String(
+            registry
+                .render_template(text, data)
+                .map_err(|err| ProviderError::Config(format!("payload template: {err}")))?,
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| render_value(registry, item, data))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, item)| Ok((key.clone(), render_value(registry, item, data)?)))
+                .collect::<Result<_, ProviderError>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+/// `{{date}}` formats the fire date; `{{date yesterday}}` a date or timestamp from the
+/// context. `format="..."` takes a strftime pattern (default `%Y-%m-%d`).
+fn date_helper(
+    h: &Helper,
+    _: &Handlebars,
+    ctx: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let fire_time = ctx
+        .data()
+        .get("fire_time")
+        .and_then(Value::as_str)
+        .and_then(|text| DateTime::parse_from_rfc3339(text).ok())
+        .ok_or(RenderErrorReason::Other("fire_time missing".into()))?;
+    let format = match h.hash_get("format") {
+        Some(format) => format
+            .value()
+            .as_str()
+            .ok_or(RenderErrorReason::InvalidParamType("format"))?,
+        None => DATE_FORMAT,
+    };
+
+    let mut rendered = String::new();
+    let written = match h.param(0) {
+        None => write!(rendered, "{}", fire_time.format(format)),
+        Some(param) => match param.value() {
+            Value::String(text) => {
+                if let Ok(at) = DateTime::parse_from_rfc3339(text) {
+                    write!(rendered, "{}", at.format(format))
+                } else if let Ok(date) = NaiveDate::parse_from_str(text, DATE_FORMAT) {
+                    write!(rendered, "{}", date.format(format))
+                } else {
+                    return Err(RenderErrorReason::Other(format!("not a date: {text}")).into());
+                }
+            }
+            _ => {
+                let name = param.relative_path().cloned().unwrap_or_default();
+                return Err(RenderErrorReason::Other(format!("unknown date {name}")).into());
+            }
+        },
+    };
+    written.map_err(|_| RenderErrorReason::Other(format!("invalid date format {format}")))?;
+    out.write(&rendered)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use greentic_types::{EnvId, TeamId, TenantCtx, TenantId};
+
+    fn render(payload: Value, scheduled_for: &str, timezone: Tz) -> Result<Value, ProviderError> {
+        let tenant = TenantCtx::new(
+            EnvId::try_from("dev").unwrap(),
+            TenantId::try_from("acme").unwrap(),
+        )
+        .with_team(Some(TeamId::try_from("ops").unwrap()));
+        let fire = FireContext {
+            schedule: "nightly",
+            sequence: 42,
+            scheduled_for: DateTime::parse_from_rfc3339(scheduled_for)
+                .unwrap()
+                .with_timezone(&Utc),
+            timezone,
+            tenant: &tenant,
+        };
+        render_payload(&payload, &fire)
+    }
+
+    #[test]
+    fn renders_dates_in_the_schedule_timezone() {
+        // 23:30 UTC on the 1st is already the 2nd in Tokyo.
+        let payload = json!({
+            "title": "report for {{date yesterday}}",
+            "window": ["{{yesterday}}", "{{today}}"],
+            "at": "{{date format=\"%H:%M %Z\"}}",
+            "stamp": "{{date fire_time_utc format=\"%d/%m %H:%M\"}}",
+        });
+        let rendered = render(payload, "2024-03-01T23:30:00Z", chrono_tz::Asia::Tokyo).unwrap();
+        assert_eq!(
+            rendered,
+            json!({
+                "title": "report for 2024-03-01",
+                "window": ["2024-03-01", "2024-03-02"],
+                "at": "08:30 +09:00",
+                "stamp": "01/03 23:30",
+            })
+        );
+
+        let utc = render(json!("{{yesterday}}"), "2024-03-01T23:30:00Z", Tz::UTC).unwrap();
+        assert_eq!(utc, json!("2024-02-29"));
+    }
+
+    #[test]
+    fn renders_schedule_and_tenant_fields() {
+        let payload = json!({
+            "id": "{{schedule}}#{{seq}}",
+            "scope": "{{env}}/{{tenant}}/{{team}}",
+            "literal": "<b>&</b>",
+            "count": 3,
+        });
+        let rendered = render(payload, "2024-03-01T00:00:00Z", Tz::UTC).unwrap();
+        assert_eq!(
+            rendered,
+            json!({
+                "id": "nightly#42",
+                "scope": "dev/acme/ops",
+                "literal": "<b>&</b>",
+                "count": 3,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_bad_templates() {
+        assert!(validate_payload(&json!({"ok": "{{seq}}"})).is_ok());
+        assert!(matches!(
+            validate_payload(&json!(["{{#if seq}}"])),
+            Err(ProviderError::Config(_))
+        ));
+        for bad in [
+            "{{unknown}}",
+            "{{date someday}}",
+            "{{date \"soon\"}}",
+            "{{date -7}}",
+        ] {
+            let err = render(json!(bad), "2024-03-01T00:00:00Z", Tz::UTC).unwrap_err();
+            assert!(matches!(err, ProviderError::Config(_)), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use chrono::{DateTime, Duration, LocalResult, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use provider_core::{DstPolicy, ProviderError};
+
+use crate::cron::CronExpr;
+
+/// Upper bound on how far a DST transition moves local clocks (Antarctica/Troll shifts 2h).
+const MAX_SHIFT: Duration = Duration::hours(3);
+
+/// Resolve an IANA timezone name against the embedded tz database; `None` means UTC.
+pub fn parse_timezone(name: Option<&str>) -> Result<Tz, ProviderError> {
+    match name {
+        None => Ok(Tz::UTC),
+        Some(name) => name
+            .parse()
+            .map_err(|_| ProviderError::Config(format!("unknown timezone {name}"))),
+    }
+}
+
+/// First fire time strictly after `after`, evaluating `cron` on wall-clock time in `tz`.
+///
+/// Local times that a DST transition skips or repeats are resolved with `policy`. Gap times
+/// fire at the instant the gap ends, so several gap times collapse into a single fire.
+pub fn next_fire_in(
+    cron: &CronExpr,
+    tz: Tz,
+    policy: DstPolicy,
+    after: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    next_fire_local(tz, policy, after, |cursor| cron.next_local_after(cursor))
+}
+
+/// Like [`next_fire_in`], for any source of wall-clock fire times: `next_local_after`
+/// returns the first local fire time strictly after its argument.
+pub(crate) fn next_fire_local(
+    tz: Tz,
+    policy: DstPolicy,
+    after: DateTime<Utc>,
+    mut next_local_after: impl FnMut(NaiveDateTime) -> Option<NaiveDateTime>,
+) -> Option<DateTime<Utc>> {
+    // Near a transition, local times before `after`'s wall clock can still fire later (the
+    // second pass through an overlap), so the search starts early and ranks by instant.
+    let local_after = after.with_timezone(&tz).naive_local();
+    let mut cursor = if stable_offset(tz, after) {
+        local_after
+    } else {
+        local_after - MAX_SHIFT
+    };
+    let mut best: Option<DateTime<Utc>> = None;
+    let mut horizon = None;
+    while let Some(local) = next_local_after(cursor) {
+        if horizon.is_some_and(|horizon| local > horizon) {
+            break;
+        }
+        if let Some(instant) = resolve_local(tz, policy, local)
+            .into_iter()
+            .filter(|instant| *instant > after)
+            .min()
+        {
+            if best.is_none() && stable_offset(tz, instant) {
+                return Some(instant);
+            }
+            best = Some(best.map_or(instant, |best| best.min(instant)));
+            horizon.get_or_insert(local + MAX_SHIFT);
+        }
+        cursor = local;
+    }
+    best
+}
+
+/// Instants a wall-clock time in `tz` fires at under `policy`, in ascending order.
+pub fn resolve_local(tz: Tz, policy: DstPolicy, local: NaiveDateTime) -> Vec<DateTime<Utc>> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(instant) => vec![instant.with_timezone(&Utc)],
+        LocalResult::Ambiguous(first, second) => match policy {
+            DstPolicy::FireTwice => vec![first.with_timezone(&Utc), second.with_timezone(&Utc)],
+            DstPolicy::Skip | DstPolicy::FireOnce => vec![first.with_timezone(&Utc)],
+        },
+        LocalResult::None => match policy {
+            DstPolicy::Skip => Vec::new(),
+            DstPolicy::FireOnce | DstPolicy::FireTwice => gap_end(tz, local).into_iter().collect(),
+        },
+    }
+}
+
+/// The transition instant ending the gap that swallows `local`.
+fn gap_end(tz: Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    let mut lo = tz
+        .from_local_datetime(&(local - MAX_SHIFT))
+        .earliest()?
+        .with_timezone(&Utc)
+        .timestamp();
+    let mut hi = lo + 2 * MAX_SHIFT.num_seconds();
+    // First instant whose wall clock has passed `local`.
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let wall = tz.timestamp_opt(mid, 0).single()?.naive_local();
+        if wall >= local {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    DateTime::from_timestamp(lo, 0)
+}
+
+/// Whether `tz` keeps one UTC offset within `MAX_SHIFT` either side of `instant`.
+fn stable_offset(tz: Tz, instant: DateTime<Utc>) -> bool {
+    let offset = |at: DateTime<Utc>| tz.offset_from_utc_datetime(&at.naive_utc()).fix();
+    offset(instant - MAX_SHIFT) == offset(instant + MAX_SHIFT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn upcoming(expr: &str, zone: &str, policy: DstPolicy, after: &str, n: usize) -> Vec<String> {
+        let cron = CronExpr::parse(expr).unwrap();
+        let tz = parse_timezone(Some(zone)).unwrap();
+        std::iter::successors(next_fire_in(&cron, tz, policy, at(after)), |prev| {
+            next_fire_in(&cron, tz, policy, *prev)
+        })
+        .take(n)
+        .map(|instant| instant.to_rfc3339())
+        .collect()
+    }
+
+    struct Case {
+        zone: &'static str,
+        expr: &'static str,
+        after: &'static str,
+        skip: &'static [&'static str],
+        fire_once: &'static [&'static str],
+        fire_twice: &'static [&'static str],
+    }
+
+    const MATRIX: &[Case] = &[
+        // New York spring forward: 2024-03-10 02:00 EST -> 03:00 EDT (07:00Z).
+        Case {
+            zone: "America/New_York",
+            expr: "30 2 * * *",
+            after: "2024-03-09T12:00:00-05:00",
+            skip: &["2024-03-11T06:30:00+00:00"],
+            fire_once: &["2024-03-10T07:00:00+00:00", "2024-03-11T06:30:00+00:00"],
+            fire_twice: &["2024-03-10T07:00:00+00:00", "2024-03-11T06:30:00+00:00"],
+        },
+        Case {
+            zone: "America/New_York",
+            expr: "*/30 1-3 10 3 *",
+            after: "2024-03-10T00:00:00-05:00",
+            skip: &[
+                "2024-03-10T06:00:00+00:00",
+                "2024-03-10T06:30:00+00:00",
+                "2024-03-10T07:00:00+00:00",
+                "2024-03-10T07:30:00+00:00",
+            ],
+            fire_once: &[
+                "2024-03-10T06:00:00+00:00",
+                "2024-03-10T06:30:00+00:00",
+                "2024-03-10T07:00:00+00:00",
+                "2024-03-10T07:30:00+00:00",
+            ],
+            fire_twice: &[
+                "2024-03-10T06:00:00+00:00",
+                "2024-03-10T06:30:00+00:00",
+                "2024-03-10T07:00:00+00:00",
+                "2024-03-10T07:30:00+00:00",
+            ],
+        },
+        // New York fall back: 2024-11-03 02:00 EDT -> 01:00 EST (06:00Z).
+        Case {
+            zone: "America/New_York",
+            expr: "30 1 * * *",
+            after: "2024-11-02T12:00:00-04:00",
+            skip: &["2024-11-03T05:30:00+00:00", "2024-11-04T06:30:00+00:00"],
+            fire_once: &["2024-11-03T05:30:00+00:00", "2024-11-04T06:30:00+00:00"],
+            fire_twice: &[
+                "2024-11-03T05:30:00+00:00",
+                "2024-11-03T06:30:00+00:00",
+                "2024-11-04T06:30:00+00:00",
+            ],
+        },
+        Case {
+            zone: "America/New_York",
+            expr: "*/30 1-2 3 11 *",
+            after: "2024-11-03T00:00:00-04:00",
+            skip: &[
+                "2024-11-03T05:00:00+00:00",
+                "2024-11-03T05:30:00+00:00",
+                "2024-11-03T07:00:00+00:00",
+                "2024-11-03T07:30:00+00:00",
+            ],
+            fire_once: &[
+                "2024-11-03T05:00:00+00:00",
+                "2024-11-03T05:30:00+00:00",
+                "2024-11-03T07:00:00+00:00",
+                "2024-11-03T07:30:00+00:00",
+            ],
+            fire_twice: &[
+                "2024-11-03T05:00:00+00:00",
+                "2024-11-03T05:30:00+00:00",
+                "2024-11-03T06:00:00+00:00",
+                "2024-11-03T06:30:00+00:00",
+                "2024-11-03T07:00:00+00:00",
+                "2024-11-03T07:30:00+00:00",
+            ],
+        },
+        // Berlin: 2024-03-31 02:00 CET -> 03:00 CEST and 2024-10-27 03:00 CEST -> 02:00 CET.
+        Case {
+            zone: "Europe/Berlin",
+            expr: "15 2 * 3,10 SUN",
+            after: "2024-03-30T00:00:00+01:00",
+            skip: &["2024-10-06T00:15:00+00:00"],
+            fire_once: &["2024-03-31T01:00:00+00:00", "2024-10-06T00:15:00+00:00"],
+            fire_twice: &["2024-03-31T01:00:00+00:00", "2024-10-06T00:15:00+00:00"],
+        },
+        Case {
+            zone: "Europe/Berlin",
+            expr: "15 2 27 10 *",
+            after: "2024-10-26T00:00:00+02:00",
+            skip: &["2024-10-27T00:15:00+00:00", "2025-10-27T01:15:00+00:00"],
+            fire_once: &["2024-10-27T00:15:00+00:00", "2025-10-27T01:15:00+00:00"],
+            fire_twice: &[
+                "2024-10-27T00:15:00+00:00",
+                "2024-10-27T01:15:00+00:00",
+                "2025-10-27T01:15:00+00:00",
+            ],
+        },
+        // Lord Howe shifts by 30 minutes: 2024-10-06 02:00 -> 02:30 and 2024-04-07 02:00 -> 01:30.
+        Case {
+            zone: "Australia/Lord_Howe",
+            expr: "10 2 6 10 *",
+            after: "2024-10-01T00:00:00Z",
+            skip: &["2025-10-05T15:10:00+00:00"],
+            fire_once: &["2024-10-05T15:30:00+00:00", "2025-10-05T15:10:00+00:00"],
+            fire_twice: &["2024-10-05T15:30:00+00:00", "2025-10-05T15:10:00+00:00"],
+        },
+        Case {
+            zone: "Australia/Lord_Howe",
+            expr: "45 1 7 4 *",
+            after: "2024-04-01T00:00:00Z",
+            skip: &["2024-04-06T14:45:00+00:00", "2025-04-06T15:15:00+00:00"],
+            fire_once: &["2024-04-06T14:45:00+00:00", "2025-04-06T15:15:00+00:00"],
+            fire_twice: &[
+                "2024-04-06T14:45:00+00:00",
+                "2024-04-06T15:15:00+00:00",
+                "2025-04-06T15:15:00+00:00",
+            ],
+        },
+    ];
+
+    #[test]
+    fn dst_transition_matrix() {
+        for case in MATRIX {
+            for (policy, expected) in [
+                (DstPolicy::Skip, case.skip),
+                (DstPolicy::FireOnce, case.fire_once),
+                (DstPolicy::FireTwice, case.fire_twice),
+            ] {
+                assert_eq!(
+                    upcoming(case.expr, case.zone, policy, case.after, expected.len()),
+                    expected,
+                    "{} `{}` {policy:?}",
+                    case.zone,
+                    case.expr
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn resuming_inside_an_overlap_finds_the_repeat() {
+        let cron = CronExpr::parse("*/15 1 3 11 *").unwrap();
+        let tz = parse_timezone(Some("America/New_York")).unwrap();
+        // 01:30 EDT, between the first and second pass through 01:xx.
+        let after = at("2024-11-03T05:30:00Z");
+        assert_eq!(
+            next_fire_in(&cron, tz, DstPolicy::FireTwice, after).unwrap(),
+            at("2024-11-03T05:45:00Z")
+        );
+        assert_eq!(
+            next_fire_in(&cron, tz, DstPolicy::FireTwice, at("2024-11-03T05:45:00Z")).unwrap(),
+            at("2024-11-03T06:00:00Z")
+        );
+        assert_eq!(
+            next_fire_in(&cron, tz, DstPolicy::FireOnce, at("2024-11-03T05:45:00Z")).unwrap(),
+            at("2025-11-03T06:00:00Z")
+        );
+    }
+
+    #[test]
+    fn utc_matches_plain_cron() {
+        let cron = CronExpr::parse("0 9 * * MON-FRI").unwrap();
+        let after = at("2024-03-08T10:00:00Z");
+        assert_eq!(
+            next_fire_in(&cron, Tz::UTC, DstPolicy::default(), after),
+            cron.next_fire_after(after)
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_timezones() {
+        assert_eq!(parse_timezone(None).unwrap(), Tz::UTC);
+        assert!(matches!(
+            parse_timezone(Some("Mars/Olympus_Mons")),
+            Err(ProviderError::Config(_))
+        ));
+    }
+}
@@ -0,0 +1,347 @@
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use provider_core::{DstPolicy, ProviderError, Schedule, ScheduleKind};
+
+use crate::cron::CronExpr;
+use crate::jitter;
+use crate::rrule::RRule;
+use crate::timezone::{next_fire_in, next_fire_local, parse_timezone};
+use std::collections::BTreeSet;
+
+/// A schedule's trigger, validated and ready for next-fire computation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Trigger {
+    Cron {
+        cron: CronExpr,
+        timezone: Tz,
+        dst_policy: DstPolicy,
+    },
+    Interval {
+        every: Duration,
+        anchor: DateTime<Utc>,
+    },
+    /// One-shot triggers; delays are resolved against their anchor.
+    At(DateTime<Utc>),
+    Rrule {
+        rule: RRule,
+        dtstart: NaiveDateTime,
+        exdates: BTreeSet<NaiveDateTime>,
+        timezone: Tz,
+        dst_policy: DstPolicy,
+    },
+}
+
+impl Trigger {
+    /// Validate `schedule`, resolving `delay` schedules relative to their `anchor`, or to
+    /// `loaded_at` when it is unset.
+    pub fn compile(schedule: &Schedule, loaded_at: DateTime<Utc>) -> Result<Self, ProviderError> {
+        let timezone = parse_timezone(schedule.timezone.as_deref())?;
+        if let Some(jitter) = &schedule.jitter
+            && jitter.window_secs > jitter::MAX_WINDOW_SECS
+        {
+            return Err(ProviderError::Config(format!(
+                "jitter window of {}s exceeds one day",
+                jitter.window_secs
+            )));
+        }
+        Ok(match &schedule.kind {
+            ScheduleKind::Cron { cron } => Trigger::Cron {
+                cron: CronExpr::parse(cron)?,
+                timezone,
+                dst_policy: schedule.dst_policy,
+            },
+            ScheduleKind::Interval { every_secs, anchor } => {
+                if *every_secs == 0 {
+                    return Err(ProviderError::Config("interval must be positive".into()));
+                }
+                Trigger::Interval {
+                    every: seconds(*every_secs)?,
+                    anchor: anchor.unwrap_or(DateTime::UNIX_EPOCH),
+                }
+            }
+            ScheduleKind::Once { at } => Trigger::At(*at),
+            ScheduleKind::Delay { delay_secs, anchor } => anchor
+                .unwrap_or(loaded_at)
+                .checked_add_signed(seconds(*delay_secs)?)
+                .map(Trigger::At)
+                .ok_or_else(|| ProviderError::Config("delay out of range".into()))?,
+            ScheduleKind::Rrule {
+                rrule,
+                dtstart,
+                exdate,
+            } => {
+                let rule = RRule::parse(rrule)?;
+                let exdates: BTreeSet<_> = exdate.iter().copied().collect();
+                if !rule
+                    .occurrences(*dtstart, timezone)
+                    .any(|occurrence| !exdates.contains(&occurrence))
+                {
+                    return Err(ProviderError::Config(format!(
+                        "invalid RRULE `{rrule}`: never fires"
+                    )));
+                }
+                Trigger::Rrule {
+                    rule,
+                    dtstart: *dtstart,
+                    exdates,
+                    timezone,
+                    dst_policy: schedule.dst_policy,
+                }
+            }
+        })
+    }
+
+    /// First fire time strictly after `after`, or `None` once a one-shot has passed.
+    pub fn next_fire_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Trigger::Cron {
+                cron,
+                timezone,
+                dst_policy,
+            } => next_fire_in(cron, *timezone, *dst_policy, after),
+            Trigger::Interval { every, anchor } => {
+                if after < *anchor {
+                    return Some(*anchor);
+                }
+                let periods = (after - *anchor).num_seconds() / every.num_seconds();
+                let elapsed = Duration::try_seconds(every.num_seconds().checked_mul(periods + 1)?)?;
+                anchor.checked_add_signed(elapsed)
+            }
+            Trigger::At(at) => (*at > after).then_some(*at),
+            Trigger::Rrule {
+                rule,
+                dtstart,
+                exdates,
+                timezone,
+                dst_policy,
+            } => next_fire_local(*timezone, *dst_policy, after, |mut cursor| {
+                loop {
+                    let local = rule.next_local_after(*dtstart, *timezone, cursor)?;
+                    if !exdates.contains(&local) {
+                        return Some(local);
+                    }
+                    cursor = local;
+                }
+            }),
+        }
+    }
+
+    /// Latest fire at or before `at` and after the Unix epoch. Searches back from `at`
+    /// over a doubling window, so frequent triggers don't replay their whole history.
+    pub fn latest_at_or_before(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut window = Duration::minutes(1);
+        loop {
+            let start = at
+                .checked_sub_signed(window)
+                .filter(|start| *start > DateTime::UNIX_EPOCH)
+                .unwrap_or(DateTime::UNIX_EPOCH);
+            let mut latest = None;
+            let mut cursor = start;
+            while let Some(next) = self.next_fire_after(cursor).filter(|next| *next <= at) {
+                latest = Some(next);
+                cursor = next;
+            }
+            if latest.is_some() || start == DateTime::UNIX_EPOCH {
+                return latest;
+            }
+            window = window.checked_add(&window).unwrap_or(Duration::MAX);
+        }
+    }
+}
+
+fn seconds(secs: u64) -> Result<Duration, ProviderError> {
+    i64::try_from(secs)
+        .ok()
+        .and_then(Duration::try_seconds)
+        .ok_or_else(|| ProviderError::Config(format!("{secs}s is out of range")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn compile(kind: ScheduleKind) -> Result<Trigger, ProviderError> {
+        let schedule = Schedule {
+            name: "test".into(),
+            kind,
+            topic: "timer.test".into(),
+            payload: json!({}),
+            payload_template: false,
+            timezone: None,
+            dst_policy: DstPolicy::default(),
+            catch_up: Default::default(),
+            jitter: None,
+            calendar: None,
+        };
+        Trigger::compile(&schedule, at("2024-05-01T12:00:00Z"))
+    }
+
+    #[test]
+    fn interval_aligns_to_anchor() {
+        let trigger = compile(ScheduleKind::Interval {
+            every_secs: 900,
+            anchor: Some(at("2024-05-01T10:05:00Z")),
+        })
+        .unwrap();
+        let next = |after| trigger.next_fire_after(at(after)).unwrap().to_rfc3339();
+        assert_eq!(next("2024-05-01T00:00:00Z"), "2024-05-01T10:05:00+00:00");
+        assert_eq!(next("2024-05-01T10:05:00Z"), "2024-05-01T10:20:00+00:00");
+        assert_eq!(next("2024-05-01T12:49:59.5Z"), "2024-05-01T12:50:00+00:00");
+
+        let epoch = compile(ScheduleKind::Interval {
+            every_secs: 3600,
+            anchor: None,
+        })
+        .unwrap();
+        assert_eq!(
+            epoch.next_fire_after(at("2024-05-01T10:30:00Z")),
+            Some(at("2024-05-01T11:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn one_shots_fire_once() {
+        let once = compile(ScheduleKind::Once {
+            at: at("2024-06-01T00:00:00Z"),
+        })
+        .unwrap();
+        assert_eq!(
+            once.next_fire_after(at("2024-05-01T00:00:00Z")),
+            Some(at("2024-06-01T00:00:00Z"))
+        );
+        assert_eq!(once.next_fire_after(at("2024-06-01T00:00:00Z")), None);
+
+        let delay = compile(ScheduleKind::Delay {
+            delay_secs: 90,
+            anchor: None,
+        })
+        .unwrap();
+        assert_eq!(delay, Trigger::At(at("2024-05-01T12:01:30Z")));
+        let anchored = compile(ScheduleKind::Delay {
+            delay_secs: 90,
+            anchor: Some(at("2024-04-01T00:00:00Z")),
+        })
+        .unwrap();
+        assert_eq!(anchored, Trigger::At(at("2024-04-01T00:01:30Z")));
+    }
+
+    #[test]
+    fn finds_the_latest_fire_at_or_before() {
+        let hourly = compile(ScheduleKind::Cron {
+            cron: "0 * * * *".into(),
+        })
+        .unwrap();
+        assert_eq!(
+            hourly.latest_at_or_before(at("2024-05-01T10:59:59Z")),
+            Some(at("2024-05-01T10:00:00Z"))
+        );
+        assert_eq!(
+            hourly.latest_at_or_before(at("2024-05-01T11:00:00Z")),
+            Some(at("2024-05-01T11:00:00Z"))
+        );
+
+        let once = compile(ScheduleKind::Once {
+            at: at("2020-01-01T00:00:00Z"),
+        })
+        .unwrap();
+        assert_eq!(
+            once.latest_at_or_before(at("2024-05-01T00:00:00Z")),
+            Some(at("2020-01-01T00:00:00Z"))
+        );
+        assert_eq!(once.latest_at_or_before(at("2019-12-31T23:59:59Z")), None);
+    }
+
+    #[test]
+    fn rrules_honour_exdates_and_dst() {
+        let local = |text| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M").unwrap();
+        let schedule = Schedule {
+            name: "test".into(),
+            kind: ScheduleKind::Rrule {
+                rrule: "FREQ=WEEKLY;BYDAY=SU;COUNT=4".into(),
+                dtstart: local("2024-03-03T02:30"),
+                exdate: vec![local("2024-03-17T02:30")],
+            },
+            topic: "timer.test".into(),
+            payload: json!({}),
+            payload_template: false,
+            timezone: Some("America/New_York".into()),
+            dst_policy: DstPolicy::Skip,
+            catch_up: Default::default(),
+            jitter: None,
+            calendar: None,
+        };
+        let trigger = Trigger::compile(&schedule, at("2024-01-01T00:00:00Z")).unwrap();
+        let fires: Vec<_> = std::iter::successors(
+            trigger.next_fire_after(at("2024-03-01T00:00:00Z")),
+            |prev| trigger.next_fire_after(*prev),
+        )
+        .map(|fire| fire.to_rfc3339())
+        .collect();
+        // 10 March 02:30 falls in the spring-forward gap and 17 March is excluded.
+        assert_eq!(
+            fires,
+            ["2024-03-03T07:30:00+00:00", "2024-03-24T06:30:00+00:00"]
+        );
+
+        let never = Schedule {
+            kind: ScheduleKind::Rrule {
+                rrule: "FREQ=DAILY;COUNT=1".into(),
+                dtstart: local("2024-03-03T02:30"),
+                exdate: vec![local("2024-03-03T02:30")],
+            },
+            ..schedule
+        };
+        let err = Trigger::compile(&never, at("2024-01-01T00:00:00Z")).unwrap_err();
+        assert!(matches!(err, ProviderError::Config(msg) if msg.contains("never fires")));
+    }
+
+    #[test]
+    fn rejects_invalid_triggers() {
+        let zero = compile(ScheduleKind::Interval {
+            every_secs: 0,
+            anchor: None,
+        });
+        assert!(matches!(zero, Err(ProviderError::Config(_))));
+        let huge = compile(ScheduleKind::Delay {
+            delay_secs: u64::MAX,
+            anchor: None,
+        });
+        assert!(matches!(huge, Err(ProviderError::Config(_))));
+
+        let jittered = Schedule {
+            name: "test".into(),
+            kind: ScheduleKind::Cron {
+                cron: "@daily".into(),
+            },
+            topic: "timer.test".into(),
+            payload: json!({}),
+            payload_template: false,
+            timezone: None,
+            dst_policy: DstPolicy::default(),
+            catch_up: Default::default(),
+            jitter: Some(provider_core::Jitter {
+                window_secs: u64::MAX,
+                mode: Default::default(),
+            }),
+            calendar: None,
+        };
+        assert!(Trigger::compile(&jittered, at("2024-05-01T12:00:00Z")).is_err());
+        let with_window = |window_secs| Schedule {
+            jitter: Some(provider_core::Jitter {
+                window_secs,
+                mode: Default::default(),
+            }),
+            ..jittered.clone()
+        };
+        assert!(Trigger::compile(&with_window(86_401), at("2024-05-01T12:00:00Z")).is_err());
+        assert!(Trigger::compile(&with_window(86_400), at("2024-05-01T12:00:00Z")).is_ok());
+        assert!(Trigger::compile(&with_window(600), at("2024-05-01T12:00:00Z")).is_ok());
+    }
+}
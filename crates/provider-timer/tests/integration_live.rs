@@ -1,4 +1,4 @@
-use provider_core::{Schedule, SchedulerConfig};
+use provider_core::{Schedule, ScheduleKind, SchedulerConfig};
 use provider_timer::TimerSource;
 use std::env;
 use std::error::Error;
@@ -24,11 +24,19 @@ fn live_timer_smoke() -> Result<(), Box<dyn Error>> {
     let source = TimerSource::new(SchedulerConfig {
         schedules: vec![Schedule {
             name: "live".into(),
-            cron: "*/5 * * * *".into(),
+            kind: ScheduleKind::Cron {
+                cron: "*/5 * * * *".into(),
+            },
             topic: "timer.live".into(),
             payload: serde_json::json!({"kind": "live"}),
+            payload_template: false,
+            timezone: None,
+            dst_policy: Default::default(),
+            catch_up: Default::default(),
+            jitter: None,
+            calendar: None,
         }],
-    });
+    })?;
 
     let event = source.fire(sample_tenant(), "live")?;
     assert_eq!(event.topic, "timer.live");
@@ -0,0 +1,512 @@
+//! The recurrence examples of RFC 5545 section 3.8.5.3, evaluated through
+//! `TimerSource::next_fire_after` with `DTSTART;TZID=America/New_York`.
+
+use chrono::{NaiveDateTime, TimeZone};
+use chrono_tz::America::New_York;
+use provider_core::{Schedule, ScheduleKind, SchedulerConfig};
+use provider_timer::TimerSource;
+
+fn local(text: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(text, "%Y%m%dT%H%M%S").unwrap()
+}
+
+/// Up to `n` fires as `YYYYMMDDTHHMM` New York wall-clock times.
+fn fires(rrule: &str, dtstart: &str, exdate: &[&str], n: usize) -> Vec<String> {
+    let source = TimerSource::new(SchedulerConfig {
+        schedules: vec![Schedule {
+            name: "rfc".into(),
+            kind: ScheduleKind::Rrule {
+                rrule: rrule.into(),
+                dtstart: local(dtstart),
+                exdate: exdate.iter().map(|text| local(text)).collect(),
+            },
+            topic: "timer.rfc".into(),
+            payload: serde_json::json!({}),
+            payload_template: false,
+            timezone: Some("America/New_York".into()),
+            dst_policy: Default::default(),
+            catch_up: Default::default(),
+            jitter: None,
+            calendar: None,
+        }],
+    })
+    .unwrap();
+    let start = New_York
+        .from_local_datetime(&local(dtstart))
+        .single()
+        .unwrap()
+        .to_utc();
+    std::iter::successors(
+        source
+            .next_fire_after("rfc", start - chrono::Duration::seconds(1))
+            .unwrap(),
+        |prev| source.next_fire_after("rfc", *prev).unwrap(),
+    )
+    .take(n)
+    .map(|at| {
+        at.with_timezone(&New_York)
+            .format("%Y%m%dT%H%M")
+            .to_string()
+    })
+    .collect()
+}
+
+fn at_nine(days: &[&str]) -> Vec<String> {
+    days.iter().map(|day| format!("{day}T0900")).collect()
+}
+
+#[test]
+fn daily_rules() {
+    assert_eq!(
+        fires("FREQ=DAILY;COUNT=10", "19970902T090000", &[], 100),
+        at_nine(&[
+            "19970902", "19970903", "19970904", "19970905", "19970906", "19970907", "19970908",
+            "19970909", "19970910", "19970911",
+        ])
+    );
+
+    let until = fires(
+        "FREQ=DAILY;UNTIL=19971224T000000Z",
+        "19970902T090000",
+        &[],
+        200,
+    );
+    assert_eq!(until.len(), 113);
+    assert_eq!(until.first().unwrap(), "19970902T0900");
+    assert_eq!(until.last().unwrap(), "19971223T0900");
+
+    assert_eq!(
+        fires("FREQ=DAILY;INTERVAL=2", "19970902T090000", &[], 17),
+        at_nine(&[
+            "19970902", "19970904", "19970906", "19970908", "19970910", "19970912", "19970914",
+            "19970916", "19970918", "19970920", "19970922", "19970924", "19970926", "19970928",
+            "19970930", "19971002", "19971004",
+        ])
+    );
+
+    assert_eq!(
+        fires(
+            "FREQ=DAILY;INTERVAL=10;COUNT=5",
+            "19970902T090000",
+            &[],
+            100
+        ),
+        at_nine(&["19970902", "19970912", "19970922", "19971002", "19971012"])
+    );
+
+    // Every day in January, for 3 years, written both ways.
+    for rule in [
+        "FREQ=YEARLY;UNTIL=20000131T140000Z;BYMONTH=1;BYDAY=SU,MO,TU,WE,TH,FR,SA",
+        "FREQ=DAILY;UNTIL=20000131T140000Z;BYMONTH=1",
+    ] {
+        let january = fires(rule, "19980101T090000", &[], 200);
+        assert_eq!(january.len(), 93, "{rule}");
+        assert_eq!(january[30], "19980131T0900");
+        assert_eq!(january[31], "19990101T0900");
+        assert_eq!(january.last().unwrap(), "20000131T0900");
+    }
+}
+
+#[test]
+fn weekly_rules() {
+    assert_eq!(
+        fires("FREQ=WEEKLY;COUNT=10", "19970902T090000", &[], 100),
+        at_nine(&[
+            "19970902", "19970909", "19970916", "19970923", "19970930", "19971007", "19971014",
+            "19971021", "19971028", "19971104",
+        ])
+    );
+
+    let until = fires(
+        "FREQ=WEEKLY;UNTIL=19971224T000000Z",
+        "19970902T090000",
+        &[],
+        100,
+    );
+    assert_eq!(until.len(), 17);
+    assert_eq!(until.last().unwrap(), "19971223T0900");
+
+    assert_eq!(
+        fires("FREQ=WEEKLY;INTERVAL=2;WKST=SU", "19970902T090000", &[], 10),
+        at_nine(&[
+            "19970902", "19970916", "19970930", "19971014", "19971028", "19971111", "19971125",
+            "19971209", "19971223", "19980106",
+        ])
+    );
+
+    let tue_thu = at_nine(&[
+        "19970902", "19970904", "19970909", "19970911", "19970916", "19970918", "19970923",
+        "19970925", "19970930", "19971002",
+    ]);
+    assert_eq!(
+        fires(
+            "FREQ=WEEKLY;UNTIL=19971007T000000Z;WKST=SU;BYDAY=TU,TH",
+            "19970902T090000",
+            &[],
+            100
+        ),
+        tue_thu
+    );
+    assert_eq!(
+        fires(
+            "FREQ=WEEKLY;COUNT=10;WKST=SU;BYDAY=TU,TH",
+            "19970902T090000",
+            &[],
+            100
+        ),
+        tue_thu
+    );
+
+    assert_eq!(
+        fires(
+            "FREQ=WEEKLY;INTERVAL=2;UNTIL=19971224T000000Z;WKST=SU;BYDAY=MO,WE,FR",
+            "19970901T090000",
+            &[],
+            100
+        ),
+        at_nine(&[
+            "19970901", "19970903", "19970905", "19970915", "19970917", "19970919", "19970929",
+            "19971001", "19971003", "19971013", "19971015", "19971017", "19971027", "19971029",
+            "19971031", "19971110", "19971112", "19971114", "19971124", "19971126", "19971128",
+            "19971208", "19971210", "19971212", "19971222",
+        ])
+    );
+
+    assert_eq!(
+        fires(
+            "FREQ=WEEKLY;INTERVAL=2;COUNT=8;WKST=SU;BYDAY=TU,TH",
+            "19970902T090000",
+            &[],
+            100
+        ),
+        at_nine(&[
+            "19970902", "19970904", "19970916", "19970918", "19970930", "19971002", "19971014",
+            "19971016",
+        ])
+    );
+}
+
+#[test]
+fn monthly_rules() {
+    assert_eq!(
+        fires(
+            "FREQ=MONTHLY;COUNT=10;BYDAY=1FR",
+            "19970905T090000",
+            &[],
+            100
+        ),
+        at_nine(&[
+            "19970905", "19971003", "19971107", "19971205", "19980102", "19980206", "19980306",
+            "19980403", "19980501", "19980605",
+        ])
+    );
+    assert_eq!(
+        fires(
+            "FREQ=MONTHLY;UNTIL=19971224T000000Z;BYDAY=1FR",
+            "19970905T090000",
+            &[],
+            100
+        ),
+        at_nine(&["19970905", "19971003", "19971107", "19971205"])
+    );
+    assert_eq!(
+        fires(
+            "FREQ=MONTHLY;INTERVAL=2;COUNT=10;BYDAY=1SU,-1SU",
+            "19970907T090000",
+            &[],
+            100
+        ),
+        at_nine(&[
+            "19970907", "19970928", "19971102", "19971130", "19980104", "19980125", "19980301",
+            "19980329", "19980503", "19980531",
+        ])
+    );
+    assert_eq!(
+        fires(
+            "FREQ=MONTHLY;COUNT=6;BYDAY=-2MO",
+            "19970922T090000",
+            &[],
+            100
+        ),
+        at_nine(&[
+            "19970922", "19971020", "19971117", "19971222", "19980119", "19980216",
+        ])
+    );
+    assert_eq!(
+        fires("FREQ=MONTHLY;BYMONTHDAY=-3", "19970928T090000", &[], 6),
+        at_nine(&[
+            "19970928", "19971029", "19971128", "19971229", "19980129", "19980226",
+        ])
+    );
+    assert_eq!(
+        fires(
+            "FREQ=MONTHLY;COUNT=10;BYMONTHDAY=2,15",
+            "19970902T090000",
+            &[],
+            100
+        ),
+        at_nine(&[
+            "19970902", "19970915", "19971002", "19971015", "19971102", "19971115", "19971202",
+            "19971215", "19980102", "19980115",
+        ])
+    );
+    assert_eq!(
+        fires(
+            "FREQ=MONTHLY;COUNT=10;BYMONTHDAY=1,-1",
+            "19970930T090000",
+            &[],
+            100
+        ),
+        at_nine(&[
+            "19970930", "19971001", "19971031", "19971101", "19971130", "19971201", "19971231",
+            "19980101", "19980131", "19980201",
+        ])
+    );
+    assert_eq!(
+        fires(
+            "FREQ=MONTHLY;INTERVAL=18;COUNT=10;BYMONTHDAY=10,11,12,13,14,15",
+            "19970910T090000",
+            &[],
+            100
+        ),
+        at_nine(&[
+            "19970910", "19970911", "19970912", "19970913", "19970914", "19970915", "19990310",
+            "19990311", "19990312", "19990313",
+        ])
+    );
+    assert_eq!(
+        fires(
+            "FREQ=MONTHLY;INTERVAL=2;BYDAY=TU",
+            "19970902T090000",
+            &[],
+            18
+        ),
+        at_nine(&[
+            "19970902", "19970909", "19970916", "19970923", "19970930", "19971104", "19971111",
+            "19971118", "19971125", "19980106", "19980113", "19980120", "19980127", "19980303",
+            "19980310", "19980317", "19980324", "19980331",
+        ])
+    );
+}
+
+#[test]
+fn yearly_rules() {
+    assert_eq!(
+        fires(
+            "FREQ=YEARLY;COUNT=10;BYMONTH=6,7",
+            "19970610T090000",
+            &[],
+            100
+        ),
+        at_nine(&[
+            "19970610", "19970710", "19980610", "19980710", "19990610", "19990710", "20000610",
+            "20000710", "20010610", "20010710",
+        ])
+    );
+    assert_eq!(
+        fires(
+            "FREQ=YEARLY;INTERVAL=2;COUNT=10;BYMONTH=1,2,3",
+            "19970310T090000",
+            &[],
+            100
+        ),
+        at_nine(&[
+            "19970310", "19990110", "19990210", "19990310", "20010110", "20010210", "20010310",
+            "20030110", "20030210", "20030310",
+        ])
+    );
+    assert_eq!(
+        fires(
+            "FREQ=YEARLY;INTERVAL=3;COUNT=10;BYYEARDAY=1,100,200",
+            "19970101T090000",
+            &[],
+            100
+        ),
+        at_nine(&[
+            "19970101", "19970410", "19970719", "20000101", "20000409", "20000718", "20030101",
+            "20030410", "20030719", "20060101",
+        ])
+    );
+    assert_eq!(
+        fires("FREQ=YEARLY;BYDAY=20MO", "19970519T090000", &[], 3),
+        at_nine(&["19970519", "19980518", "19990517"])
+    );
+    assert_eq!(
+        fires(
+            "FREQ=YEARLY;BYWEEKNO=20;BYDAY=MO",
+            "19970512T090000",
+            &[],
+            3
+        ),
+        at_nine(&["19970512", "19980511", "19990517"])
+    );
+    assert_eq!(
+        fires("FREQ=YEARLY;BYMONTH=3;BYDAY=TH", "19970313T090000", &[], 11),
+        at_nine(&[
+            "19970313", "19970320", "19970327", "19980305", "19980312", "19980319", "19980326",
+            "19990304", "19990311", "19990318", "19990325",
+        ])
+    );
+    assert_eq!(
+        fires(
+            "FREQ=YEARLY;BYDAY=TH;BYMONTH=6,7,8",
+            "19970605T090000",
+            &[],
+            15
+        ),
+        at_nine(&[
+            "19970605", "19970612", "19970619", "19970626", "19970703", "19970710", "19970717",
+            "19970724", "19970731", "19970807", "19970814", "19970821", "19970828", "19980604",
+            "19980611",
+        ])
+    );
+}
+
+#[test]
+fn combined_rules() {
+    // Every Friday the 13th; DTSTART itself is excluded.
+    assert_eq!(
+        fires(
+            "FREQ=MONTHLY;BYDAY=FR;BYMONTHDAY=13",
+            "19970902T090000",
+            &["19970902T090000"],
+            5
+        ),
+        at_nine(&["19980213", "19980313", "19981113", "19990813", "20001013",])
+    );
+    // The first Saturday that follows the first Sunday of the month.
+    assert_eq!(
+        fires(
+            "FREQ=MONTHLY;BYDAY=SA;BYMONTHDAY=7,8,9,10,11,12,13",
+            "19970913T090000",
+            &[],
+            10
+        ),
+        at_nine(&[
+            "19970913", "19971011", "19971108", "19971213", "19980110", "19980207", "19980307",
+            "19980411", "19980509", "19980613",
+        ])
+    );
+    // US presidential election day.
+    assert_eq!(
+        fires(
+            "FREQ=YEARLY;INTERVAL=4;BYMONTH=11;BYDAY=TU;BYMONTHDAY=2,3,4,5,6,7,8",
+            "19961105T090000",
+            &[],
+            3
+        ),
+        at_nine(&["19961105", "20001107", "20041102"])
+    );
+    assert_eq!(
+        fires(
+            "FREQ=MONTHLY;COUNT=3;BYDAY=TU,WE,TH;BYSETPOS=3",
+            "19970904T090000",
+            &[],
+            100
+        ),
+        at_nine(&["19970904", "19971007", "19971106"])
+    );
+    assert_eq!(
+        fires(
+            "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-2",
+            "19970929T090000",
+            &[],
+            7
+        ),
+        at_nine(&[
+            "19970929", "19971030", "19971127", "19971230", "19980129", "19980226", "19980330",
+        ])
+    );
+}
+
+#[test]
+fn sub_daily_rules() {
+    // The RFC writes UNTIL as 17:00Z but lists fires up to 15:00 local; a floating UNTIL
+    // matches the listed set.
+    assert_eq!(
+        fires(
+            "FREQ=HOURLY;INTERVAL=3;UNTIL=19970902T170000",
+            "19970902T090000",
+            &[],
+            100
+        ),
+        ["19970902T0900", "19970902T1200", "19970902T1500"]
+    );
+    assert_eq!(
+        fires(
+            "FREQ=MINUTELY;INTERVAL=15;COUNT=6",
+            "19970902T090000",
+            &[],
+            100
+        ),
+        [
+            "19970902T0900",
+            "19970902T0915",
+            "19970902T0930",
+            "19970902T0945",
+            "19970902T1000",
+            "19970902T1015",
+        ]
+    );
+    assert_eq!(
+        fires(
+            "FREQ=MINUTELY;INTERVAL=90;COUNT=4",
+            "19970902T090000",
+            &[],
+            100
+        ),
+        [
+            "19970902T0900",
+            "19970902T1030",
+            "19970902T1200",
+            "19970902T1330",
+        ]
+    );
+
+    let expected: Vec<String> = ["19970902", "19970903"]
+        .iter()
+        .flat_map(|day| {
+            (9..=16).flat_map(move |hour| {
+                [0, 20, 40].map(|minute| format!("{day}T{hour:02}{minute:02}"))
+            })
+        })
+        .collect();
+    for rule in [
+        "FREQ=DAILY;BYHOUR=9,10,11,12,13,14,15,16;BYMINUTE=0,20,40",
+        "FREQ=MINUTELY;INTERVAL=20;BYHOUR=9,10,11,12,13,14,15,16",
+    ] {
+        assert_eq!(fires(rule, "19970902T090000", &[], 48), expected, "{rule}");
+    }
+}
+
+#[test]
+fn week_start_and_invalid_dates() {
+    assert_eq!(
+        fires(
+            "FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=MO",
+            "19970805T090000",
+            &[],
+            100
+        ),
+        at_nine(&["19970805", "19970810", "19970819", "19970824"])
+    );
+    assert_eq!(
+        fires(
+            "FREQ=WEEKLY;INTERVAL=2;COUNT=4;BYDAY=TU,SU;WKST=SU",
+            "19970805T090000",
+            &[],
+            100
+        ),
+        at_nine(&["19970805", "19970817", "19970819", "19970831"])
+    );
+    // February 30th does not exist and is skipped.
+    assert_eq!(
+        fires(
+            "FREQ=MONTHLY;BYMONTHDAY=15,30;COUNT=5",
+            "20070115T090000",
+            &[],
+            100
+        ),
+        at_nine(&["20070115", "20070130", "20070215", "20070315", "20070330"])
+    );
+}
@@ -0,0 +1,139 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use provider_core::ProviderError;
+use serde_json::{Map, Value, json};
+
+/// Decode raw request bytes into an event payload according to `content_type`.
+///
+/// - JSON (`application/json`, `*+json`) is parsed as-is.
+/// - `application/x-www-form-urlencoded` becomes an object; repeated keys collect into arrays.
+/// - Text (`text/*`, `*xml`) becomes a string.
+/// - Anything else, or text that is not UTF-8, is wrapped in a base64 envelope
+///   `{"encoding": "base64", "content_type": ..., "data": ...}`.
+///
+/// Without a content type the body is treated as JSON, then text, then binary.
+pub fn decode_body(content_type: Option<&str>, raw: &[u8]) -> Result<Value, ProviderError> {
+    let media_type = content_type.map(media_type);
+    match media_type.as_deref() {
+        Some(mt) if is_json(mt) => serde_json::from_slice(raw)
+            .map_err(|err| ProviderError::Other(format!("invalid JSON body: {err}"))),
+        Some("application/x-www-form-urlencoded") => Ok(decode_form(raw)),
+        Some(mt) if is_text(mt) => Ok(text_or_binary(content_type, raw)),
+        Some(_) => Ok(binary_envelope(content_type, raw)),
+        None => Ok(serde_json::from_slice(raw).unwrap_or_else(|_| text_or_binary(None, raw))),
+    }
+}
+
+fn media_type(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+fn is_json(media_type: &str) -> bool {
+    media_type == "application/json" || media_type.ends_with("+json")
+}
+
+fn is_text(media_type: &str) -> bool {
+    media_type.starts_with("text/") || media_type.ends_with("/xml") || media_type.ends_with("+xml")
+}
+
+fn decode_form(raw: &[u8]) -> Value {
+    let mut fields = Map::new();
+    for (key, value) in form_urlencoded::parse(raw) {
+        let value = Value::String(value.into_owned());
+        match fields.get_mut(key.as_ref()) {
+            Some(Value::Array(items)) => items.push(value),
+            Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+            None => {
+                fields.insert(key.into_owned(), value);
+            }
+        }
+    }
+    Value::Object(fields)
+}
+
+fn text_or_binary(content_type: Option<&str>, raw: &[u8]) -> Value {
+    match std::str::from_utf8(raw) {
+        Ok(text) => Value::String(text.to_string()),
+        Err(_) => binary_envelope(content_type, raw),
+    }
+}
+
+fn binary_envelope(content_type: Option<&str>, raw: &[u8]) -> Value {
+    json!({
+        "encoding": "base64",
+        "content_type": content_type,
+        "data": STANDARD.encode(raw),
+    })
+}
+
+/// Serde adapter carrying optional raw bytes as a base64 string.
+pub(crate) mod base64_bytes {
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &Option<Vec<u8>>, ser: S) -> Result<S::Ok, S::Error> {
+        match bytes {
+            Some(bytes) => ser.serialize_some(&STANDARD.encode(bytes)),
+            None => ser.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<Option<Vec<u8>>, D::Error> {
+        Option::<String>::deserialize(de)?
+            .map(|encoded| STANDARD.decode(encoded).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_json_with_charset() {
+        let value = decode_body(Some("application/json; charset=utf-8"), br#"{"a":1}"#).unwrap();
+        assert_eq!(value, json!({"a": 1}));
+        assert!(decode_body(Some("application/json"), b"not json").is_err());
+    }
+
+    #[test]
+    fn decodes_form_with_repeated_keys() {
+        let raw = b"From=%2B15551234567&Body=Hello+there&MediaUrl=a&MediaUrl=b";
+        let value = decode_body(Some("application/x-www-form-urlencoded"), raw).unwrap();
+        assert_eq!(
+            value,
+            json!({"From": "+15551234567", "Body": "Hello there", "MediaUrl": ["a", "b"]})
+        );
+    }
+
+    #[test]
+    fn decodes_text_and_xml_as_strings() {
+        let value = decode_body(Some("text/plain"), b"ping").unwrap();
+        assert_eq!(value, json!("ping"));
+        let value = decode_body(Some("application/xml"), b"<a/>").unwrap();
+        assert_eq!(value, json!("<a/>"));
+    }
+
+    #[test]
+    fn wraps_binary_in_base64_envelope() {
+        let value = decode_body(Some("application/octet-stream"), &[0, 159, 146, 150]).unwrap();
+        assert_eq!(
+            value,
+            json!({"encoding": "base64", "content_type": "application/octet-stream", "data": "AJ+Slg=="})
+        );
+        let value = decode_body(Some("text/plain"), &[0xff, 0xfe]).unwrap();
+        assert_eq!(value.get("encoding"), Some(&json!("base64")));
+    }
+
+    #[test]
+    fn sniffs_missing_content_type() {
+        assert_eq!(decode_body(None, b"[1,2]").unwrap(), json!([1, 2]));
+        assert_eq!(decode_body(None, b"hello").unwrap(), json!("hello"));
+    }
+}
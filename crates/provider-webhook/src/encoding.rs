@@ -0,0 +1,242 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use greentic_types::{EventEnvelope, EventId, TenantCtx};
+use provider_core::ProviderError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use crate::signature::header_value;
+
+/// CloudEvents spec version emitted and accepted.
+pub const CLOUDEVENTS_SPEC_VERSION: &str = "1.0";
+
+/// Extension attributes carrying envelope fields CloudEvents has no slot for.
+const EXT_TOPIC: &str = "greentictopic";
+const EXT_TENANT: &str = "greentictenant";
+const EXT_CORRELATION: &str = "greenticcorrelation";
+const EXT_METADATA: &str = "greenticmetadata";
+
+/// Body encoding for outbound webhook requests.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboundEncoding {
+    /// `event.payload` only, as JSON.
+    #[default]
+    Payload,
+    /// CloudEvents HTTP structured mode (`application/cloudevents+json`).
+    CloudeventsStructured,
+    /// CloudEvents HTTP binary mode: attributes in `ce-*` headers, payload as the body.
+    CloudeventsBinary,
+    /// The full `EventEnvelope` as JSON.
+    Envelope,
+}
+
+/// Headers and body produced for an event under an [`OutboundEncoding`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodedEvent {
+    pub headers: BTreeMap<String, String>,
+    pub body: Value,
+}
+
+/// Encode `event` for delivery. The `content-type` header is always set.
+pub fn encode_event(
+    encoding: OutboundEncoding,
+    event: &EventEnvelope,
+) -> Result<EncodedEvent, ProviderError> {
+    let mut headers = BTreeMap::new();
+    let body = match encoding {
+        OutboundEncoding::Payload => {
+            headers.insert("content-type".into(), "application/json".into());
+            event.payload.clone()
+        }
+        OutboundEncoding::Envelope => {
+            headers.insert("content-type".into(), "application/json".into());
+            serde_json::to_value(event).map_err(encode_error)?
+        }
+        OutboundEncoding::CloudeventsStructured => {
+            headers.insert("content-type".into(), "application/cloudevents+json".into());
+            let mut body = Map::new();
+            for (name, value) in cloudevent_attributes(event)? {
+                body.insert(name.into(), Value::String(value));
+            }
+            body.insert("datacontenttype".into(), "application/json".into());
+            body.insert("data".into(), event.payload.clone());
+            Value::Object(body)
+        }
+        OutboundEncoding::CloudeventsBinary => {
+            headers.insert("content-type".into(), "application/json".into());
+            for (name, value) in cloudevent_attributes(event)? {
+                headers.insert(format!("ce-{name}"), percent_encode(&value));
+            }
+            event.payload.clone()
+        }
+    };
+    Ok(EncodedEvent { headers, body })
+}
+
+/// Rebuild an `EventEnvelope` from a CloudEvents HTTP request in binary (`ce-specversion`
+/// header present) or structured mode. Envelope fields without a CloudEvents attribute come
+/// from the `greentic*` extensions; `greentictenant` is required, the others are optional.
+pub fn decode_cloudevent(
+    headers: &BTreeMap<String, String>,
+    body: &Value,
+) -> Result<EventEnvelope, ProviderError> {
+    let (attributes, data) = if header_value(headers, "ce-specversion").is_some() {
+        let attributes = headers
+            .iter()
+            .filter_map(|(name, value)| {
+                let name = name.to_ascii_lowercase();
+                let attr = name.strip_prefix("ce-")?.to_string();
+                Some(percent_decode(value).map(|value| (attr, value)))
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+        (attributes, body.clone())
+    } else {
+        let object = body
+            .as_object()
+            .ok_or_else(|| decode_error("structured CloudEvent must be a JSON object"))?;
+        let attributes = object
+            .iter()
+            .filter(|(name, _)| name.as_str() != "data")
+            .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+            .collect::<BTreeMap<_, _>>();
+        (
+            attributes,
+            object.get("data").cloned().unwrap_or(Value::Null),
+        )
+    };
+
+    let required = |name: &str| {
+        attributes
+            .get(name)
+            .cloned()
+            .ok_or_else(|| decode_error(&format!("missing CloudEvents attribute {name}")))
+    };
+    let spec_version = required("specversion")?;
+    if spec_version != CLOUDEVENTS_SPEC_VERSION {
+        return Err(decode_error(&format!(
+            "unsupported CloudEvents specversion {spec_version}"
+        )));
+    }
+    let id = EventId::new(required("id")?)
+        .map_err(|err| decode_error(&format!("invalid event id: {err}")))?;
+    let time = match attributes.get("time") {
+        Some(time) => DateTime::parse_from_rfc3339(time)
+            .map_err(|err| decode_error(&format!("invalid time: {err}")))?
+            .with_timezone(&Utc),
+        None => Utc::now(),
+    };
+    let tenant: TenantCtx = serde_json::from_str(&required(EXT_TENANT)?)
+        .map_err(|err| decode_error(&format!("invalid {EXT_TENANT}: {err}")))?;
+    let metadata = match attributes.get(EXT_METADATA) {
+        Some(raw) => serde_json::from_str(raw)
+            .map_err(|err| decode_error(&format!("invalid {EXT_METADATA}: {err}")))?,
+        None => BTreeMap::new(),
+    };
+
+    Ok(EventEnvelope {
+        id,
+        topic: attributes.get(EXT_TOPIC).cloned().unwrap_or_default(),
+        r#type: required("type")?,
+        source: required("source")?,
+        tenant,
+        subject: attributes.get("subject").cloned(),
+        time,
+        correlation_id: attributes.get(EXT_CORRELATION).cloned(),
+        payload: data,
+        metadata,
+    })
+}
+
+/// Context attributes (name, value) for `event`, excluding `datacontenttype`.
+fn cloudevent_attributes(
+    event: &EventEnvelope,
+) -> Result<Vec<(&'static str, String)>, ProviderError> {
+    let mut attributes = vec![
+        ("specversion", CLOUDEVENTS_SPEC_VERSION.to_string()),
+        ("id", event.id.to_string()),
+        ("source", event.source.clone()),
+        ("type", event.r#type.clone()),
+        (
+            "time",
+            event.time.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+        ),
+        (EXT_TOPIC, event.topic.clone()),
+        (
+            EXT_TENANT,
+            serde_json::to_string(&event.tenant).map_err(encode_error)?,
+        ),
+    ];
+    if let Some(subject) = &event.subject {
+        attributes.push(("subject", subject.clone()));
+    }
+    if let Some(correlation) = &event.correlation_id {
+        attributes.push((EXT_CORRELATION, correlation.clone()));
+    }
+    if !event.metadata.is_empty() {
+        attributes.push((
+            EXT_METADATA,
+            serde_json::to_string(&event.metadata).map_err(encode_error)?,
+        ));
+    }
+    Ok(attributes)
+}
+
+/// Percent-encode per the CloudEvents HTTP binding: bytes outside printable ASCII, space,
+/// `"` and `%`.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'"' | b'%' => encoded.push_str(&format!("%{byte:02X}")),
+            0x21..=0x7e => encoded.push(char::from(byte)),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+fn percent_decode(value: &str) -> Result<String, ProviderError> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' {
+            let hex = value
+                .get(idx + 1..idx + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| decode_error(&format!("invalid percent-encoding in {value}")))?;
+            decoded.push(hex);
+            idx += 3;
+        } else {
+            decoded.push(bytes[idx]);
+            idx += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|err| decode_error(&format!("header is not UTF-8: {err}")))
+}
+
+fn encode_error(err: serde_json::Error) -> ProviderError {
+    ProviderError::Other(format!("failed to encode event: {err}"))
+}
+
+fn decode_error(message: &str) -> ProviderError {
+    ProviderError::Other(format!("invalid CloudEvent: {message}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_encoding_roundtrips() {
+        let raw = r#"{"tenant":"acme"} 100% ünïcode"#;
+        let encoded = percent_encode(raw);
+        assert_eq!(
+            encoded,
+            "{%22tenant%22:%22acme%22}%20100%25%20%C3%BCn%C3%AFcode"
+        );
+        assert_eq!(percent_decode(&encoded).unwrap(), raw);
+        assert!(percent_decode("%zz").is_err());
+    }
+}
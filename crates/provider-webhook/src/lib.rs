@@ -1,38 +1,110 @@
-use provider_core::secrets::{SecretProvider, resolve_secret};
+pub mod body;
+pub mod encoding;
+pub mod routing;
+pub mod signature;
+pub mod topic;
+
+use chrono::Utc;
+use provider_core::secrets::{SecretProvider, SecretResolution, resolve_secret};
 use provider_core::{
-    HttpEndpointConfig, ProviderError, WebhookRoute, new_event, set_idempotency_key,
+    DedupStore, HttpEndpointConfig, ProviderError, WebhookRoute, new_event, set_idempotency_key,
+    tenant_key,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use std::collections::BTreeMap;
 
+pub use body::decode_body;
+pub use encoding::{EncodedEvent, OutboundEncoding, decode_cloudevent, encode_event};
+pub use routing::{RouteMatch, match_path, select_route};
+pub use signature::{sign_hmac, sign_standard_webhooks, verify_signature};
+pub use topic::{derive_event_type, sanitize_event_type};
+
 /// Result of handling a webhook request, including secrets metadata events.
 pub struct WebhookResult {
     pub event: greentic_types::EventEnvelope,
     pub secret_events: Vec<greentic_types::EventEnvelope>,
 }
 
+/// Outcome of [`WebhookSource::handle_request_deduped`].
+pub enum WebhookOutcome {
+    /// First delivery within the dedup window; forward the event.
+    Accepted(Box<WebhookResult>),
+    /// Replayed delivery; drop it but still forward the secret events.
+    Duplicate {
+        dedup_key: String,
+        secret_events: Vec<greentic_types::EventEnvelope>,
+    },
+}
+
 /// Minimal representation of an inbound HTTP request supplied by the host/runner.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct InboundHttpRequest {
     pub method: String,
     pub path: String,
     pub headers: BTreeMap<String, String>,
+    /// Pre-parsed JSON body; ignored when `raw_body` is supplied.
+    #[serde(default)]
     pub body: Value,
+    /// Body bytes exactly as received (base64 on the wire). Preferred over `body` because it
+    /// covers non-JSON payloads and keeps signatures verifiable.
+    #[serde(
+        default,
+        with = "body::base64_bytes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub raw_body: Option<Vec<u8>>,
     pub correlation_id: Option<String>,
     /// Host-provided flag indicating whether the request signature was already validated.
+    /// Ignored for routes that configure an in-component `signature` scheme.
     #[serde(default)]
     pub signature_validated: bool,
 }
 
+impl InboundHttpRequest {
+    /// Bytes covered by signatures: `raw_body` when present, else the compact JSON `body`.
+    pub fn body_bytes(&self) -> Result<Vec<u8>, ProviderError> {
+        match &self.raw_body {
+            Some(raw) => Ok(raw.clone()),
+            None => serde_json::to_vec(&self.body)
+                .map_err(|err| ProviderError::Other(format!("failed to encode body: {err}"))),
+        }
+    }
+
+    /// Event payload: `raw_body` decoded by content type when present, else `body`.
+    pub fn payload(&self) -> Result<Value, ProviderError> {
+        match &self.raw_body {
+            Some(raw) => decode_body(signature::header_value(&self.headers, "content-type"), raw),
+            None => Ok(self.body.clone()),
+        }
+    }
+}
+
 /// Helper to handle inbound webhook requests and map them into EventEnvelope instances.
 pub struct WebhookSource {
     config: HttpEndpointConfig,
 }
 
 impl WebhookSource {
-    pub fn new(config: HttpEndpointConfig) -> Self {
-        Self { config }
+    /// Rejects routes that set both a `signature` and `replay.key_header`: signed routes
+    /// dedup on the verified body, so the header would be silently ignored.
+    pub fn new(config: HttpEndpointConfig) -> Result<Self, ProviderError> {
+        for route in &config.routes {
+            if route.signature.is_some()
+                && let Some(header) = route
+                    .replay
+                    .as_ref()
+                    .and_then(|replay| replay.key_header.as_deref())
+            {
+                return Err(ProviderError::Config(format!(
+                    "route {}: replay.key_header `{header}` cannot be used with a signature; \
+                     signed routes dedup on the SHA-256 of the verified body",
+                    route.path
+                )));
+            }
+        }
+        Ok(Self { config })
     }
 
     /// Resolve secrets via the Greentic secrets-store inside the component (wasm32).
@@ -51,14 +123,45 @@ impl WebhookSource {
         request: InboundHttpRequest,
         secrets: &dyn SecretProvider,
     ) -> Result<WebhookResult, ProviderError> {
-        let route = self
-            .match_route(&request.path)
-            .ok_or_else(|| ProviderError::Config(format!("no route for path {}", request.path)))?;
+        let matched = self.match_route(&request.method, &request.path)?;
+        self.handle_matched(matched, tenant, request, secrets)
+    }
+
+    fn handle_matched(
+        &self,
+        RouteMatch { route, params }: RouteMatch<'_>,
+        tenant: greentic_types::TenantCtx,
+        request: InboundHttpRequest,
+        secrets: &dyn SecretProvider,
+    ) -> Result<WebhookResult, ProviderError> {
+        let topic_prefix = routing::render_topic_prefix(&route.topic_prefix, &params)?;
+
+        let secret = resolve_route_secret(route, secrets, tenant.clone())?;
+        let signature_valid = match &route.signature {
+            Some(scheme) => {
+                let key = secret.value.as_deref().ok_or_else(|| {
+                    ProviderError::Signature(format!(
+                        "signing secret for route {} is not available",
+                        route.path
+                    ))
+                })?;
+                let body = request.body_bytes()?;
+                let signed_at = verify_signature(scheme, key, &request.headers, &body, Utc::now())?;
+                if let (Some(replay), Some(signed_at)) = (&route.replay, signed_at) {
+                    signature::check_skew(signed_at, Utc::now(), replay.max_skew_secs)?;
+                }
+                true
+            }
+            None => request.signature_validated || route.secret_ref.is_none(),
+        };
 
         let mut metadata = self.request_metadata(&request);
-        let signature_valid = request.signature_validated || route.secret_ref.is_none();
         metadata.insert("signature_valid".into(), signature_valid.to_string());
-        metadata.insert("topic_prefix".into(), route.topic_prefix.clone());
+        metadata.insert("topic_prefix".into(), topic_prefix.clone());
+        metadata.insert("route".into(), route.path.clone());
+        for (name, value) in params.iter() {
+            metadata.insert(format!("path_param:{name}"), value.clone());
+        }
 
         if let Some(key) = request
             .headers
@@ -68,10 +171,11 @@ impl WebhookSource {
             set_idempotency_key(&mut metadata, key.clone());
         }
 
-        let event_type = detect_event_type(&request.body).unwrap_or_else(|| "received".to_string());
-        let topic = format!("{}.{}", route.topic_prefix, event_type);
-
-        let secret_events = resolve_webhook_secret(route, secrets, tenant.clone())?;
+        let payload = request.payload()?;
+        let rule = route.topic_rule.clone().unwrap_or_default();
+        let event_type = derive_event_type(&rule, &payload, &request.headers);
+        metadata.insert("event_type".into(), event_type.clone());
+        let topic = format!("{}.{}", topic_prefix, event_type);
 
         Ok(WebhookResult {
             event: new_event(
@@ -81,19 +185,49 @@ impl WebhookSource {
                 tenant,
                 Some(request.path.clone()),
                 request.correlation_id.clone(),
-                request.body.clone(),
+                payload,
                 metadata,
             ),
-            secret_events,
+            secret_events: secret.events,
         })
     }
 
-    fn match_route(&self, path: &str) -> Option<&WebhookRoute> {
+    /// Like [`Self::handle_request`], but drops deliveries already recorded in `dedup` for
+    /// routes that configure `replay`. Routes without replay protection are always accepted.
+    pub fn handle_request_deduped(
+        &self,
+        tenant: greentic_types::TenantCtx,
+        request: InboundHttpRequest,
+        secrets: &dyn SecretProvider,
+        dedup: &dyn DedupStore,
+    ) -> Result<WebhookOutcome, ProviderError> {
+        let matched = self.match_route(&request.method, &request.path)?;
+        let route = matched.route;
+        let Some(replay) = &route.replay else {
+            let result = self.handle_matched(matched, tenant, request, secrets)?;
+            return Ok(WebhookOutcome::Accepted(Box::new(result)));
+        };
+
+        let dedup_key = format!(
+            "webhook/{}/{}/{}",
+            tenant_key(&tenant),
+            route.path.trim_matches('/'),
+            delivery_id(route, &request, replay.key_header.as_deref())?
+        );
+        let mut result = self.handle_matched(matched, tenant, request, secrets)?;
+        if !dedup.check_and_record(&dedup_key, Utc::now(), replay.ttl_secs)? {
+            return Ok(WebhookOutcome::Duplicate {
+                dedup_key,
+                secret_events: result.secret_events,
+            });
+        }
+        result.event.metadata.insert("dedup_key".into(), dedup_key);
+        Ok(WebhookOutcome::Accepted(Box::new(result)))
+    }
+
+    fn match_route(&self, method: &str, path: &str) -> Result<RouteMatch<'_>, ProviderError> {
         let normalized = strip_base(&self.config.base_path, path);
-        self.config
-            .routes
-            .iter()
-            .find(|route| normalized == route.path)
+        select_route(&self.config.routes, method, &normalized)
     }
 
     fn request_metadata(&self, request: &InboundHttpRequest) -> BTreeMap<String, String> {
@@ -116,18 +250,55 @@ pub fn resolve_webhook_secret(
     secrets: &dyn SecretProvider,
     tenant: greentic_types::TenantCtx,
 ) -> Result<Vec<greentic_types::EventEnvelope>, ProviderError> {
-    if let Some(key) = route.secret_ref.as_ref() {
-        let resolution = resolve_secret(
+    Ok(resolve_route_secret(route, secrets, tenant)?.events)
+}
+
+/// Delivery id used for dedup: the configured header, then `Idempotency-Key`, then a
+/// SHA-256 digest of the body bytes. Routes that verify a `signature` always use the
+/// digest: the vendor schemes do not sign those headers, so a replayed request could
+/// otherwise slip past dedup with a fresh key ([`WebhookSource::new`] rejects a
+/// `key_header` on such routes).
+fn delivery_id(
+    route: &WebhookRoute,
+    request: &InboundHttpRequest,
+    key_header: Option<&str>,
+) -> Result<String, ProviderError> {
+    let header = key_header
+        .and_then(|name| signature::header_value(&request.headers, name))
+        .or_else(|| signature::header_value(&request.headers, "idempotency-key"))
+        .map(str::trim)
+        .filter(|value| !value.is_empty() && route.signature.is_none());
+    match header {
+        Some(value) => Ok(value.to_string()),
+        None => Ok(format!(
+            "sha256:{}",
+            hex::encode(Sha256::digest(request.body_bytes()?))
+        )),
+    }
+}
+
+fn resolve_route_secret(
+    route: &WebhookRoute,
+    secrets: &dyn SecretProvider,
+    tenant: greentic_types::TenantCtx,
+) -> Result<SecretResolution, ProviderError> {
+    match route.secret_ref.as_ref() {
+        Some(key) => resolve_secret(
             secrets,
             key,
             "tenant",
             tenant,
             "webhook-gateway",
             "webhook signing secret",
-        )?;
-        Ok(resolution.events)
-    } else {
-        Ok(Vec::new())
+        ),
+        None if route.signature.is_some() => Err(ProviderError::Config(format!(
+            "route {} configures a signature scheme without secret_ref",
+            route.path
+        ))),
+        None => Ok(SecretResolution {
+            value: None,
+            events: Vec::new(),
+        }),
     }
 }
 
@@ -137,6 +308,26 @@ pub struct OutboundWebhookConfig {
     pub url: String,
     #[serde(default)]
     pub headers: BTreeMap<String, String>,
+    /// Secret holding the signing key; requests are sent unsigned when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_ref: Option<String>,
+    /// Signature format applied when `secret_ref` is set.
+    #[serde(default)]
+    pub signing: OutboundSigning,
+    /// Body encoding; defaults to the bare event payload.
+    #[serde(default)]
+    pub encoding: OutboundEncoding,
+}
+
+/// How outbound requests are signed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "scheme", rename_all = "snake_case")]
+pub enum OutboundSigning {
+    /// Standard Webhooks: `webhook-id`, `webhook-timestamp` and `webhook-signature` headers.
+    #[default]
+    StandardWebhooks,
+    /// Single header carrying an HMAC of the body.
+    Hmac(provider_core::HmacSignature),
 }
 
 /// Representation of an HTTP request to be executed by the host.
@@ -146,27 +337,99 @@ pub struct OutgoingWebhookRequest {
     pub url: String,
     pub headers: BTreeMap<String, String>,
     pub body: Value,
+    /// `body` serialized exactly as signed. Hosts must send these bytes verbatim rather than
+    /// re-serializing `body`, which may not reproduce them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_body: Option<String>,
+}
+
+/// Outbound request plus secrets metadata events.
+pub struct OutgoingWebhook {
+    pub request: OutgoingWebhookRequest,
+    pub secret_events: Vec<greentic_types::EventEnvelope>,
 }
 
 /// Build an outbound HTTP request for the sink component.
+///
+/// The body and content type follow `config.encoding`. When `secret_ref` is configured the
+/// signing key is resolved through `secrets` and the signature covers `raw_body`, the compact
+/// JSON serialization of `body`, which hosts must send verbatim.
 pub fn build_outgoing_request(
     config: &OutboundWebhookConfig,
     event: &greentic_types::EventEnvelope,
-) -> Result<OutgoingWebhookRequest, ProviderError> {
+    secrets: &dyn SecretProvider,
+) -> Result<OutgoingWebhook, ProviderError> {
+    let encoded = encode_event(config.encoding, event)?;
     let mut headers = config.headers.clone();
-    headers.insert("content-type".into(), "application/json".into());
+    headers.extend(encoded.headers);
     if let Some(correlation) = &event.correlation_id {
         headers.insert("x-correlation-id".into(), correlation.clone());
     }
+    let body = encoded.body;
+    let raw_body = serde_json::to_string(&body)
+        .map_err(|err| ProviderError::Other(format!("failed to encode body: {err}")))?;
+
+    let mut secret_events = Vec::new();
+    if let Some(key) = &config.secret_ref {
+        let secret = resolve_secret(
+            secrets,
+            key,
+            "tenant",
+            event.tenant.clone(),
+            "webhook-gateway",
+            "webhook signing secret",
+        )?;
+        secret_events = secret.events;
+        let secret = secret.value.ok_or_else(|| {
+            ProviderError::Config(format!("signing secret {key} is not available"))
+        })?;
+        sign_outgoing(
+            &config.signing,
+            &secret,
+            event,
+            raw_body.as_bytes(),
+            &mut headers,
+        )?;
+    }
 
-    Ok(OutgoingWebhookRequest {
-        method: "POST".into(),
-        url: config.url.clone(),
-        headers,
-        body: event.payload.clone(),
+    Ok(OutgoingWebhook {
+        request: OutgoingWebhookRequest {
+            method: "POST".into(),
+            url: config.url.clone(),
+            headers,
+            body,
+            raw_body: Some(raw_body),
+        },
+        secret_events,
     })
 }
 
+fn sign_outgoing(
+    signing: &OutboundSigning,
+    secret: &[u8],
+    event: &greentic_types::EventEnvelope,
+    body: &[u8],
+    headers: &mut BTreeMap<String, String>,
+) -> Result<(), ProviderError> {
+    match signing {
+        OutboundSigning::StandardWebhooks => {
+            let msg_id = event.id.to_string();
+            let timestamp = Utc::now().timestamp();
+            let signature = signature::sign_standard_webhooks(secret, &msg_id, timestamp, body)?;
+            headers.insert("webhook-id".into(), msg_id);
+            headers.insert("webhook-timestamp".into(), timestamp.to_string());
+            headers.insert("webhook-signature".into(), signature);
+        }
+        OutboundSigning::Hmac(cfg) => {
+            headers.insert(
+                cfg.header.to_ascii_lowercase(),
+                signature::sign_hmac(cfg, secret, body),
+            );
+        }
+    }
+    Ok(())
+}
+
 fn strip_base(base: &str, path: &str) -> String {
     let trimmed_base = base.trim_end_matches('/');
     let mut trimmed_path = path.trim_start_matches(trimmed_base).to_string();
@@ -176,21 +439,14 @@ fn strip_base(base: &str, path: &str) -> String {
     trimmed_path
 }
 
-fn detect_event_type(body: &Value) -> Option<String> {
-    match body {
-        Value::Object(map) => map
-            .get("type")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string()),
-        _ => None,
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use provider_core::secrets::StaticSecretProvider;
-    use provider_core::{HttpEndpointConfig, WebhookRoute};
+    use provider_core::{
+        DigestEncoding, EventTypeSource, HmacAlgorithm, HmacSignature, HttpEndpointConfig,
+        InMemoryDedupStore, ReplayProtection, SignatureScheme, TopicRule, WebhookRoute,
+    };
     use serde_json::json;
 
     fn sample_tenant() -> greentic_types::TenantCtx {
@@ -210,24 +466,65 @@ mod tests {
                     path: "/stripe".into(),
                     secret_ref: Some("WEBHOOK_SIGNING_SECRET".into()),
                     topic_prefix: "webhook.stripe".into(),
+                    ..Default::default()
                 },
                 WebhookRoute {
                     path: "/github".into(),
                     secret_ref: None,
                     topic_prefix: "webhook.github".into(),
+                    ..Default::default()
+                },
+                WebhookRoute {
+                    path: "/tenants/{tenant}/{team}/{handler}".into(),
+                    secret_ref: None,
+                    topic_prefix: "webhook.{handler}".into(),
+                    methods: vec!["POST".into()],
+                    ..Default::default()
+                },
+                WebhookRoute {
+                    path: "/signed".into(),
+                    secret_ref: Some("WEBHOOK_SIGNING_SECRET".into()),
+                    topic_prefix: "webhook.signed".into(),
+                    signature: Some(SignatureScheme::Hmac(HmacSignature {
+                        header: "X-Signature".into(),
+                        algorithm: HmacAlgorithm::Sha256,
+                        encoding: DigestEncoding::Hex,
+                        prefix: Some("sha256=".into()),
+                    })),
+                    ..Default::default()
                 },
             ],
         }
     }
 
+    fn signed_request(signature: &str) -> InboundHttpRequest {
+        InboundHttpRequest {
+            method: "POST".into(),
+            path: "/webhook/signed".into(),
+            headers: BTreeMap::from([("x-signature".into(), signature.into())]),
+            body: json!({"type": "payment_succeeded", "id": "evt_1"}),
+            raw_body: None,
+            correlation_id: None,
+            signature_validated: false,
+        }
+    }
+
+    fn signing_secrets() -> StaticSecretProvider {
+        StaticSecretProvider::new(BTreeMap::from([(
+            "WEBHOOK_SIGNING_SECRET".into(),
+            b"sig".to_vec(),
+        )]))
+    }
+
     #[test]
     fn maps_inbound_request_to_event() {
-        let source = WebhookSource::new(sample_config());
+        let source = WebhookSource::new(sample_config()).expect("valid config");
         let req = InboundHttpRequest {
             method: "POST".into(),
             path: "/webhook/stripe".into(),
             headers: BTreeMap::from([("idempotency-key".into(), "idem-1".into())]),
             body: json!({"type": "payment_succeeded", "id": "evt_1"}),
+            raw_body: None,
             correlation_id: Some("req-123".into()),
             signature_validated: true,
         };
@@ -255,11 +552,175 @@ mod tests {
         assert_eq!(result.secret_events[0].topic, "greentic.secrets.put");
     }
 
+    #[test]
+    fn topic_rule_reads_vendor_headers() {
+        let header_rule = |header: &str| TopicRule {
+            sources: vec![EventTypeSource::Header(header.into())],
+            fallback: "unknown".into(),
+        };
+        let source = WebhookSource::new(HttpEndpointConfig {
+            base_path: "/webhook".into(),
+            routes: vec![
+                WebhookRoute {
+                    path: "/github".into(),
+                    topic_prefix: "webhook.github".into(),
+                    topic_rule: Some(header_rule("X-GitHub-Event")),
+                    ..Default::default()
+                },
+                WebhookRoute {
+                    path: "/shopify".into(),
+                    topic_prefix: "webhook.shopify".into(),
+                    topic_rule: Some(header_rule("X-Shopify-Topic")),
+                    ..Default::default()
+                },
+            ],
+        })
+        .expect("valid config");
+        let secrets = StaticSecretProvider::empty();
+
+        let mut req = signed_request("unused");
+        req.path = "/webhook/github".into();
+        req.headers = BTreeMap::from([("X-GitHub-Event".into(), "pull_request".into())]);
+        let result = source
+            .handle_request(sample_tenant(), req.clone(), &secrets)
+            .expect("github event");
+        assert_eq!(result.event.topic, "webhook.github.pull_request");
+        assert_eq!(
+            result.event.metadata.get("event_type"),
+            Some(&"pull_request".into())
+        );
+
+        req.path = "/webhook/shopify".into();
+        req.headers = BTreeMap::from([("x-shopify-topic".into(), "orders/create".into())]);
+        let result = source
+            .handle_request(sample_tenant(), req.clone(), &secrets)
+            .expect("shopify event");
+        assert_eq!(result.event.topic, "webhook.shopify.orders.create");
+
+        req.headers.clear();
+        let result = source
+            .handle_request(sample_tenant(), req, &secrets)
+            .expect("fallback event");
+        assert_eq!(result.event.topic, "webhook.shopify.unknown");
+    }
+
+    #[test]
+    fn templated_route_exposes_params_and_topic() {
+        let source = WebhookSource::new(sample_config()).expect("valid config");
+        let mut req = signed_request("unused");
+        req.path = "/webhook/tenants/acme/core/shopify".into();
+        let result = source
+            .handle_request(sample_tenant(), req.clone(), &StaticSecretProvider::empty())
+            .expect("templated event");
+        assert_eq!(result.event.topic, "webhook.shopify.payment_succeeded");
+        let meta = &result.event.metadata;
+        assert_eq!(meta.get("path_param:tenant"), Some(&"acme".into()));
+        assert_eq!(meta.get("path_param:team"), Some(&"core".into()));
+        assert_eq!(meta.get("path_param:handler"), Some(&"shopify".into()));
+        assert_eq!(
+            meta.get("route"),
+            Some(&"/tenants/{tenant}/{team}/{handler}".into())
+        );
+
+        req.method = "GET".into();
+        let err = source
+            .handle_request(sample_tenant(), req, &StaticSecretProvider::empty())
+            .err()
+            .expect("method filtered");
+        assert!(matches!(err, ProviderError::Config(_)));
+    }
+
+    #[test]
+    fn verifies_hmac_signature_in_component() {
+        let source = WebhookSource::new(sample_config()).expect("valid config");
+        // HMAC-SHA256("sig", compact JSON body).
+        let req = signed_request(
+            "sha256=7fcd5829629ff53468c47758b6a47ca16c22f46a31b36c30d5fce05fc1d4955a",
+        );
+        let result = source
+            .handle_request(sample_tenant(), req, &signing_secrets())
+            .expect("verified event");
+        assert_eq!(result.event.topic, "webhook.signed.payment_succeeded");
+        assert_eq!(
+            result.event.metadata.get("signature_valid"),
+            Some(&"true".into())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_signature_even_if_host_flag_set() {
+        let source = WebhookSource::new(sample_config()).expect("valid config");
+        let mut req = signed_request(&format!("sha256={}", "00".repeat(32)));
+        req.signature_validated = true;
+        let err = source
+            .handle_request(sample_tenant(), req, &signing_secrets())
+            .err()
+            .expect("signature mismatch");
+        assert!(matches!(err, ProviderError::Signature(_)));
+    }
+
+    #[test]
+    fn missing_secret_fails_signature_check() {
+        let source = WebhookSource::new(sample_config()).expect("valid config");
+        let req = signed_request(
+            "sha256=7fcd5829629ff53468c47758b6a47ca16c22f46a31b36c30d5fce05fc1d4955a",
+        );
+        let err = source
+            .handle_request(sample_tenant(), req, &StaticSecretProvider::empty())
+            .err()
+            .expect("secret missing");
+        assert!(matches!(err, ProviderError::Signature(_)));
+    }
+
+    #[test]
+    fn verifies_and_decodes_raw_form_body() {
+        let source = WebhookSource::new(sample_config()).expect("valid config");
+        let raw = b"type=message.received&Body=Hello+there".to_vec();
+        // HMAC-SHA256("sig", raw form body).
+        let mut req = signed_request(
+            "sha256=65917e75a9fd0012ba9c5085b7fef906ceacd4f9ddf4ec177392da1cdc32a8a8",
+        );
+        req.headers.insert(
+            "Content-Type".into(),
+            "application/x-www-form-urlencoded".into(),
+        );
+        req.body = Value::Null;
+        req.raw_body = Some(raw);
+
+        let result = source
+            .handle_request(sample_tenant(), req, &signing_secrets())
+            .expect("verified form event");
+        assert_eq!(result.event.topic, "webhook.signed.message.received");
+        assert_eq!(
+            result.event.payload,
+            json!({"type": "message.received", "Body": "Hello there"})
+        );
+    }
+
+    #[test]
+    fn raw_body_roundtrips_as_base64() {
+        let req: InboundHttpRequest = serde_json::from_value(json!({
+            "method": "POST",
+            "path": "/webhook/github",
+            "headers": {"content-type": "text/plain"},
+            "raw_body": "cGluZw==",
+            "correlation_id": null
+        }))
+        .expect("request");
+        assert_eq!(req.raw_body.as_deref(), Some(&b"ping"[..]));
+        assert_eq!(req.payload().expect("payload"), json!("ping"));
+        let value = serde_json::to_value(&req).expect("serialize");
+        assert_eq!(value.get("raw_body"), Some(&json!("cGluZw==")));
+    }
+
     #[test]
     fn builds_outgoing_request() {
         let cfg = OutboundWebhookConfig {
             url: "https://example.test/endpoint".into(),
             headers: BTreeMap::from([("x-custom".into(), "value".into())]),
+            secret_ref: None,
+            signing: OutboundSigning::default(),
+            encoding: OutboundEncoding::default(),
         };
         let event = new_event(
             "webhook.outgoing",
@@ -272,7 +733,11 @@ mod tests {
             BTreeMap::new(),
         );
 
-        let outgoing = build_outgoing_request(&cfg, &event).expect("build");
+        let outgoing =
+            build_outgoing_request(&cfg, &event, &StaticSecretProvider::empty()).expect("build");
+        assert!(outgoing.secret_events.is_empty());
+        let outgoing = outgoing.request;
+        assert!(!outgoing.headers.contains_key("webhook-signature"));
         assert_eq!(outgoing.url, cfg.url);
         assert_eq!(outgoing.method, "POST");
         assert_eq!(outgoing.body, json!({"hello": "world"}));
@@ -282,12 +747,86 @@ mod tests {
         );
     }
 
+    fn outgoing_event() -> greentic_types::EventEnvelope {
+        new_event(
+            "webhook.outgoing",
+            "com.greentic.webhook.generic.v1",
+            "webhook-gateway",
+            sample_tenant(),
+            None,
+            None,
+            json!({"hello": "world"}),
+            BTreeMap::new(),
+        )
+    }
+
+    #[test]
+    fn signs_outgoing_request_with_standard_webhooks() {
+        let cfg: OutboundWebhookConfig = serde_json::from_value(json!({
+            "url": "https://example.test/endpoint",
+            "secret_ref": "WEBHOOK_SIGNING_SECRET"
+        }))
+        .expect("config");
+        assert_eq!(cfg.signing, OutboundSigning::StandardWebhooks);
+        let event = outgoing_event();
+
+        let outgoing = build_outgoing_request(&cfg, &event, &signing_secrets()).expect("build");
+        assert_eq!(outgoing.secret_events.len(), 1);
+        assert_eq!(outgoing.secret_events[0].topic, "greentic.secrets.put");
+
+        let headers = &outgoing.request.headers;
+        assert_eq!(headers.get("webhook-id"), Some(&event.id.to_string()));
+        let timestamp: i64 = headers["webhook-timestamp"].parse().expect("timestamp");
+        let body = outgoing.request.raw_body.as_deref().expect("raw body");
+        assert_eq!(body, r#"{"hello":"world"}"#);
+        let expected =
+            sign_standard_webhooks(b"sig", event.id.as_str(), timestamp, body.as_bytes())
+                .expect("sign");
+        assert_eq!(headers.get("webhook-signature"), Some(&expected));
+    }
+
+    #[test]
+    fn signs_outgoing_request_with_hmac_header() {
+        let scheme = HmacSignature {
+            header: "X-Signature".into(),
+            algorithm: HmacAlgorithm::Sha256,
+            encoding: DigestEncoding::Hex,
+            prefix: Some("sha256=".into()),
+        };
+        let cfg = OutboundWebhookConfig {
+            url: "https://example.test/endpoint".into(),
+            headers: BTreeMap::new(),
+            secret_ref: Some("WEBHOOK_SIGNING_SECRET".into()),
+            signing: OutboundSigning::Hmac(scheme.clone()),
+            encoding: OutboundEncoding::default(),
+        };
+
+        let outgoing =
+            build_outgoing_request(&cfg, &outgoing_event(), &signing_secrets()).expect("build");
+        let body = outgoing.request.raw_body.as_deref().expect("raw body");
+        verify_signature(
+            &SignatureScheme::Hmac(scheme),
+            b"sig",
+            &outgoing.request.headers,
+            body.as_bytes(),
+            Utc::now(),
+        )
+        .expect("receiver verifies");
+        assert!(!outgoing.request.headers.contains_key("webhook-signature"));
+
+        let err = build_outgoing_request(&cfg, &outgoing_event(), &StaticSecretProvider::empty())
+            .err()
+            .expect("secret missing");
+        assert!(matches!(err, ProviderError::Config(_)));
+    }
+
     #[test]
     fn resolves_signing_secret() {
         let route = WebhookRoute {
             path: "/stripe".into(),
             secret_ref: Some("WEBHOOK_SIGNING_SECRET".into()),
             topic_prefix: "webhook.stripe".into(),
+            ..Default::default()
         };
         let secrets = StaticSecretProvider::new(BTreeMap::from([(
             "WEBHOOK_SIGNING_SECRET".into(),
@@ -306,6 +845,7 @@ mod tests {
             path: "/stripe".into(),
             secret_ref: Some("WEBHOOK_SIGNING_SECRET".into()),
             topic_prefix: "webhook.stripe".into(),
+            ..Default::default()
         };
         let secrets = StaticSecretProvider::empty();
         let events =
@@ -313,4 +853,137 @@ mod tests {
         assert_eq!(events.len(), 1);
         assert_eq!(events[0].topic, "greentic.secrets.missing.detected");
     }
+
+    fn replay_source(signature: Option<SignatureScheme>, max_skew_secs: u64) -> WebhookSource {
+        WebhookSource::new(HttpEndpointConfig {
+            base_path: "/webhook".into(),
+            routes: vec![WebhookRoute {
+                path: "/github".into(),
+                secret_ref: signature.as_ref().map(|_| "WEBHOOK_SIGNING_SECRET".into()),
+                topic_prefix: "webhook.github".into(),
+                replay: Some(ReplayProtection {
+                    key_header: signature.is_none().then(|| "X-GitHub-Delivery".into()),
+                    max_skew_secs,
+                    ..Default::default()
+                }),
+                signature,
+                ..Default::default()
+            }],
+        })
+        .expect("valid config")
+    }
+
+    #[test]
+    fn drops_replayed_deliveries() {
+        let source = replay_source(None, 300);
+        let dedup = InMemoryDedupStore::new();
+        let secrets = StaticSecretProvider::empty();
+        let mut req = signed_request("unused");
+        req.path = "/webhook/github".into();
+        req.headers = BTreeMap::from([("x-github-delivery".into(), "d-1".into())]);
+
+        let outcome = source
+            .handle_request_deduped(sample_tenant(), req.clone(), &secrets, &dedup)
+            .expect("first delivery");
+        let WebhookOutcome::Accepted(result) = outcome else {
+            panic!("first delivery should be accepted");
+        };
+        assert_eq!(
+            result.event.metadata.get("dedup_key"),
+            Some(&"webhook/dev/acme/core/github/d-1".into())
+        );
+
+        let outcome = source
+            .handle_request_deduped(sample_tenant(), req.clone(), &secrets, &dedup)
+            .expect("replay");
+        assert!(matches!(
+            outcome,
+            WebhookOutcome::Duplicate { ref dedup_key, .. } if dedup_key.ends_with("/d-1")
+        ));
+
+        req.headers.clear();
+        let first = source
+            .handle_request_deduped(sample_tenant(), req.clone(), &secrets, &dedup)
+            .expect("body digest");
+        assert!(matches!(first, WebhookOutcome::Accepted(_)));
+        let second = source
+            .handle_request_deduped(sample_tenant(), req, &secrets, &dedup)
+            .expect("body digest replay");
+        assert!(matches!(second, WebhookOutcome::Duplicate { .. }));
+    }
+
+    #[test]
+    fn rejects_key_headers_on_signed_routes() {
+        let err = WebhookSource::new(HttpEndpointConfig {
+            base_path: "/webhook".into(),
+            routes: vec![WebhookRoute {
+                path: "/github".into(),
+                secret_ref: Some("WEBHOOK_SIGNING_SECRET".into()),
+                topic_prefix: "webhook.github".into(),
+                replay: Some(ReplayProtection {
+                    key_header: Some("X-GitHub-Delivery".into()),
+                    ..Default::default()
+                }),
+                signature: Some(SignatureScheme::Github),
+                ..Default::default()
+            }],
+        })
+        .err()
+        .expect("key_header on a signed route");
+        assert!(matches!(err, ProviderError::Config(msg) if msg.contains("X-GitHub-Delivery")));
+    }
+
+    #[test]
+    fn replay_skew_rejects_stale_signed_timestamps() {
+        use hmac::{Hmac, Mac};
+
+        let slack = || {
+            Some(SignatureScheme::Slack {
+                tolerance_secs: 3600,
+            })
+        };
+        let body = br#"{"type":"event_callback"}"#;
+        let signed = |timestamp: i64| {
+            let mut mac = Hmac::<Sha256>::new_from_slice(b"sig").unwrap();
+            mac.update(format!("v0:{timestamp}:").as_bytes());
+            mac.update(body);
+            let mut req = signed_request("unused");
+            req.path = "/webhook/github".into();
+            req.raw_body = Some(body.to_vec());
+            req.headers = BTreeMap::from([
+                ("X-Slack-Request-Timestamp".into(), timestamp.to_string()),
+                (
+                    "X-Slack-Signature".into(),
+                    format!("v0={}", hex::encode(mac.finalize().into_bytes())),
+                ),
+            ]);
+            req
+        };
+        let stale = signed(Utc::now().timestamp() - 600);
+        let dedup = InMemoryDedupStore::new();
+
+        let outcome = replay_source(slack(), 900)
+            .handle_request_deduped(sample_tenant(), stale.clone(), &signing_secrets(), &dedup)
+            .expect("within replay skew");
+        assert!(matches!(outcome, WebhookOutcome::Accepted(_)));
+
+        // The delivery header is unsigned, so a replay with a fresh one is still a duplicate.
+        let mut forged = stale.clone();
+        forged
+            .headers
+            .insert("X-GitHub-Delivery".into(), "forged".into());
+        let outcome = replay_source(slack(), 900)
+            .handle_request_deduped(sample_tenant(), forged, &signing_secrets(), &dedup)
+            .expect("replayed signed body");
+        assert!(matches!(
+            outcome,
+            WebhookOutcome::Duplicate { ref dedup_key, .. } if dedup_key.contains("/sha256:")
+        ));
+
+        let err = replay_source(slack(), 300)
+            .handle_request_deduped(sample_tenant(), stale, &signing_secrets(), &dedup)
+            .err()
+            .expect("outside replay skew");
+        assert!(matches!(err, ProviderError::Signature(_)));
+    }
 }
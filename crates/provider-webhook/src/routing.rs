@@ -0,0 +1,183 @@
+use crate::topic::sanitize_segment;
+use provider_core::{ProviderError, WebhookRoute};
+use std::collections::BTreeMap;
+
+/// Parameter name under which a trailing `*` wildcard captures the remaining path.
+pub const WILDCARD_PARAM: &str = "wildcard";
+
+/// A route selected for a request, with the path parameters it captured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteMatch<'a> {
+    pub route: &'a WebhookRoute,
+    pub params: BTreeMap<String, String>,
+}
+
+/// Select the route for `method` + `path` (already relative to the base path).
+///
+/// Literal routes win over templated ones; otherwise routes are tried in configuration order.
+pub fn select_route<'a>(
+    routes: &'a [WebhookRoute],
+    method: &str,
+    path: &str,
+) -> Result<RouteMatch<'a>, ProviderError> {
+    let (literal, templated): (Vec<_>, Vec<_>) =
+        routes.iter().partition(|route| !is_template(&route.path));
+    let mut path_matched = false;
+    for route in literal.into_iter().chain(templated) {
+        let Some(params) = match_path(&route.path, path) else {
+            continue;
+        };
+        path_matched = true;
+        if method_allowed(route, method) {
+            return Ok(RouteMatch { route, params });
+        }
+    }
+    Err(ProviderError::Config(if path_matched {
+        format!("method {method} not allowed for path {path}")
+    } else {
+        format!("no route for path {path}")
+    }))
+}
+
+/// Match `path` against a route pattern made of literal segments, `{param}` captures
+/// and an optional trailing `*` that captures the remainder (possibly empty).
+pub fn match_path(pattern: &str, path: &str) -> Option<BTreeMap<String, String>> {
+    let pattern: Vec<&str> = segments(pattern).collect();
+    let path: Vec<&str> = segments(path).collect();
+    let mut params = BTreeMap::new();
+
+    for (idx, part) in pattern.iter().enumerate() {
+        if *part == "*" && idx == pattern.len() - 1 {
+            params.insert(WILDCARD_PARAM.to_string(), path.get(idx..)?.join("/"));
+            return Some(params);
+        }
+        let segment = path.get(idx)?;
+        match param_name(part) {
+            Some(name) => {
+                params.insert(name.to_string(), segment.to_string());
+            }
+            None if part == segment => {}
+            None => return None,
+        }
+    }
+    (pattern.len() == path.len()).then_some(params)
+}
+
+/// Substitute `{param}` placeholders in a topic prefix with captured values, each
+/// sanitized into a single topic segment.
+pub fn render_topic_prefix(
+    prefix: &str,
+    params: &BTreeMap<String, String>,
+) -> Result<String, ProviderError> {
+    let mut rendered = String::with_capacity(prefix.len());
+    let mut rest = prefix;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|offset| start + offset)
+            .ok_or_else(|| ProviderError::Config(format!("unclosed placeholder in {prefix}")))?;
+        let name = &rest[start + 1..end];
+        let value = params.get(name).ok_or_else(|| {
+            ProviderError::Config(format!(
+                "topic_prefix {prefix} references unknown param {name}"
+            ))
+        })?;
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(&sanitize_segment(value));
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+fn method_allowed(route: &WebhookRoute, method: &str) -> bool {
+    route.methods.is_empty()
+        || route
+            .methods
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(method))
+}
+
+fn is_template(pattern: &str) -> bool {
+    pattern.contains('{') || pattern.ends_with('*')
+}
+
+fn param_name(segment: &str) -> Option<&str> {
+    segment
+        .strip_prefix('{')
+        .and_then(|rest| rest.strip_suffix('}'))
+        .filter(|name| !name.is_empty())
+}
+
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(path: &str, methods: &[&str]) -> WebhookRoute {
+        WebhookRoute {
+            path: path.into(),
+            topic_prefix: "webhook".into(),
+            methods: methods.iter().map(|m| m.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn captures_template_params() {
+        let params = match_path("/{tenant}/{team}/{handler}", "/acme/core/stripe").unwrap();
+        assert_eq!(params.get("tenant"), Some(&"acme".to_string()));
+        assert_eq!(params.get("team"), Some(&"core".to_string()));
+        assert_eq!(params.get("handler"), Some(&"stripe".to_string()));
+        assert!(match_path("/{tenant}/{team}/{handler}", "/acme/core").is_none());
+        assert!(match_path("/{tenant}/{team}/{handler}", "/acme/core/a/b").is_none());
+    }
+
+    #[test]
+    fn trailing_wildcard_captures_remainder() {
+        let params = match_path("/github/*", "/github/org/repo").unwrap();
+        assert_eq!(params.get(WILDCARD_PARAM), Some(&"org/repo".to_string()));
+        let params = match_path("/github/*", "/github").unwrap();
+        assert_eq!(params.get(WILDCARD_PARAM), Some(&String::new()));
+        assert!(match_path("/github/*", "/gitlab/org").is_none());
+    }
+
+    #[test]
+    fn literal_routes_take_precedence() {
+        let routes = vec![route("/{handler}", &[]), route("/stripe", &[])];
+        let matched = select_route(&routes, "POST", "/stripe").unwrap();
+        assert_eq!(matched.route.path, "/stripe");
+        assert!(matched.params.is_empty());
+    }
+
+    #[test]
+    fn filters_by_method() {
+        let routes = vec![route("/hook", &["POST"]), route("/{any}", &["get"])];
+        assert_eq!(
+            select_route(&routes, "post", "/hook").unwrap().route.path,
+            "/hook"
+        );
+        assert_eq!(
+            select_route(&routes, "GET", "/hook").unwrap().route.path,
+            "/{any}"
+        );
+        let err = select_route(&routes, "DELETE", "/hook").unwrap_err();
+        assert!(err.to_string().contains("method DELETE not allowed"));
+        let err = select_route(&routes, "POST", "/a/b").unwrap_err();
+        assert!(err.to_string().contains("no route for path"));
+    }
+
+    #[test]
+    fn renders_topic_prefix_placeholders() {
+        let params = BTreeMap::from([("handler".to_string(), "Stripe.Live".to_string())]);
+        assert_eq!(
+            render_topic_prefix("webhook.{handler}", &params).unwrap(),
+            "webhook.stripe_live"
+        );
+        assert!(render_topic_prefix("webhook.{team}", &params).is_err());
+        assert!(render_topic_prefix("webhook.{handler", &params).is_err());
+    }
+}
@@ -0,0 +1,363 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use provider_core::{DigestEncoding, HmacAlgorithm, HmacSignature, ProviderError, SignatureScheme};
+use sha1::Sha1;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+
+/// Verify an inbound request against the route's signature scheme.
+///
+/// `body` must be the exact bytes the sender signed; digests are compared in constant time.
+/// `now` bounds the signed timestamp for schemes that carry one (Stripe, Slack); that Unix
+/// timestamp is returned so callers can apply replay policies.
+pub fn verify_signature(
+    scheme: &SignatureScheme,
+    secret: &[u8],
+    headers: &BTreeMap<String, String>,
+    body: &[u8],
+    now: DateTime<Utc>,
+) -> Result<Option<i64>, ProviderError> {
+    match scheme {
+        SignatureScheme::Hmac(cfg) => verify_hmac_header(cfg, secret, headers, body).map(|_| None),
+        SignatureScheme::Github => verify_hmac_header(
+            &HmacSignature {
+                header: "X-Hub-Signature-256".into(),
+                algorithm: HmacAlgorithm::Sha256,
+                encoding: DigestEncoding::Hex,
+                prefix: Some("sha256=".into()),
+            },
+            secret,
+            headers,
+            body,
+        )
+        .map(|_| None),
+        SignatureScheme::Shopify => verify_hmac_header(
+            &HmacSignature {
+                header: "X-Shopify-Hmac-Sha256".into(),
+                algorithm: HmacAlgorithm::Sha256,
+                encoding: DigestEncoding::Base64,
+                prefix: None,
+            },
+            secret,
+            headers,
+            body,
+        )
+        .map(|_| None),
+        SignatureScheme::Stripe { tolerance_secs } => {
+            verify_stripe(secret, headers, body, now, *tolerance_secs).map(Some)
+        }
+        SignatureScheme::Slack { tolerance_secs } => {
+            verify_slack(secret, headers, body, now, *tolerance_secs).map(Some)
+        }
+    }
+}
+
+fn verify_stripe(
+    secret: &[u8],
+    headers: &BTreeMap<String, String>,
+    body: &[u8],
+    now: DateTime<Utc>,
+    tolerance_secs: u64,
+) -> Result<i64, ProviderError> {
+    let header = required_header(headers, "Stripe-Signature")?;
+    let mut timestamp = None;
+    let mut candidates = Vec::new();
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = Some(value),
+            Some(("v1", value)) => candidates.push(value),
+            _ => {}
+        }
+    }
+    let timestamp = timestamp
+        .ok_or_else(|| ProviderError::Signature("Stripe-Signature lacks timestamp".into()))?;
+    let signed_at = check_tolerance(timestamp, now, tolerance_secs)?;
+
+    let mut signed = format!("{timestamp}.").into_bytes();
+    signed.extend_from_slice(body);
+    let matched = candidates.iter().any(|candidate| {
+        hex::decode(candidate)
+            .map(|digest| verify_hmac(HmacAlgorithm::Sha256, secret, &signed, &digest))
+            .unwrap_or(false)
+    });
+    if matched {
+        Ok(signed_at)
+    } else {
+        Err(ProviderError::Signature(
+            "no v1 signature in Stripe-Signature matches body".into(),
+        ))
+    }
+}
+
+fn verify_slack(
+    secret: &[u8],
+    headers: &BTreeMap<String, String>,
+    body: &[u8],
+    now: DateTime<Utc>,
+    tolerance_secs: u64,
+) -> Result<i64, ProviderError> {
+    let timestamp = required_header(headers, "X-Slack-Request-Timestamp")?.trim();
+    let signed_at = check_tolerance(timestamp, now, tolerance_secs)?;
+    let signature = required_header(headers, "X-Slack-Signature")?.trim();
+    let digest = signature
+        .strip_prefix("v0=")
+        .ok_or_else(|| ProviderError::Signature("X-Slack-Signature lacks v0= prefix".into()))?;
+    let expected = decode_digest(digest, DigestEncoding::Hex)?;
+
+    let mut signed = format!("v0:{timestamp}:").into_bytes();
+    signed.extend_from_slice(body);
+    if verify_hmac(HmacAlgorithm::Sha256, secret, &signed, &expected) {
+        Ok(signed_at)
+    } else {
+        Err(ProviderError::Signature(
+            "X-Slack-Signature does not match body".into(),
+        ))
+    }
+}
+
+fn check_tolerance(
+    timestamp: &str,
+    now: DateTime<Utc>,
+    tolerance_secs: u64,
+) -> Result<i64, ProviderError> {
+    let signed_at: i64 = timestamp
+        .parse()
+        .map_err(|_| ProviderError::Signature(format!("malformed timestamp {timestamp}")))?;
+    check_skew(signed_at, now, tolerance_secs)?;
+    Ok(signed_at)
+}
+
+/// Reject signed timestamps further than `max_skew_secs` from `now`.
+pub(crate) fn check_skew(
+    signed_at: i64,
+    now: DateTime<Utc>,
+    max_skew_secs: u64,
+) -> Result<(), ProviderError> {
+    if now.timestamp().abs_diff(signed_at) > max_skew_secs {
+        return Err(ProviderError::Signature(format!(
+            "timestamp {signed_at} outside tolerance of {max_skew_secs}s"
+        )));
+    }
+    Ok(())
+}
+
+fn verify_hmac_header(
+    cfg: &HmacSignature,
+    secret: &[u8],
+    headers: &BTreeMap<String, String>,
+    body: &[u8],
+) -> Result<(), ProviderError> {
+    let raw = required_header(headers, &cfg.header)?;
+    let value = match cfg.prefix.as_deref() {
+        Some(prefix) => raw.trim().strip_prefix(prefix).ok_or_else(|| {
+            ProviderError::Signature(format!("header {} lacks prefix {prefix}", cfg.header))
+        })?,
+        None => raw.trim(),
+    };
+    let expected = decode_digest(value, cfg.encoding)?;
+    if verify_hmac(cfg.algorithm, secret, body, &expected) {
+        Ok(())
+    } else {
+        Err(ProviderError::Signature(format!(
+            "digest in {} does not match body",
+            cfg.header
+        )))
+    }
+}
+
+/// Header value for a plain HMAC signature: optional prefix plus the encoded digest of `body`.
+pub fn sign_hmac(cfg: &HmacSignature, secret: &[u8], body: &[u8]) -> String {
+    let digest = compute_hmac(cfg.algorithm, secret, body);
+    let encoded = match cfg.encoding {
+        DigestEncoding::Hex => hex::encode(digest),
+        DigestEncoding::Base64 => STANDARD.encode(digest),
+    };
+    format!("{}{encoded}", cfg.prefix.as_deref().unwrap_or_default())
+}
+
+/// `webhook-signature` value per Standard Webhooks: `v1,<base64 HMAC-SHA256>` over
+/// `<msg_id>.<timestamp>.<body>`.
+///
+/// Secrets in the spec's `whsec_<base64>` form are decoded; other secrets are used as raw bytes.
+pub fn sign_standard_webhooks(
+    secret: &[u8],
+    msg_id: &str,
+    timestamp: i64,
+    body: &[u8],
+) -> Result<String, ProviderError> {
+    let key = match secret.strip_prefix(b"whsec_") {
+        Some(encoded) => STANDARD.decode(encoded).map_err(|err| {
+            ProviderError::Config(format!("malformed whsec_ signing secret: {err}"))
+        })?,
+        None => secret.to_vec(),
+    };
+    let mut message = format!("{msg_id}.{timestamp}.").into_bytes();
+    message.extend_from_slice(body);
+    let digest = compute_hmac(HmacAlgorithm::Sha256, &key, &message);
+    Ok(format!("v1,{}", STANDARD.encode(digest)))
+}
+
+fn compute_hmac(algorithm: HmacAlgorithm, secret: &[u8], message: &[u8]) -> Vec<u8> {
+    match algorithm {
+        HmacAlgorithm::Sha256 => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("hmac accepts any key");
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+        HmacAlgorithm::Sha1 => {
+            let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("hmac accepts any key");
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+    }
+}
+
+/// Constant-time check of `expected` against the HMAC of `message`.
+pub(crate) fn verify_hmac(
+    algorithm: HmacAlgorithm,
+    secret: &[u8],
+    message: &[u8],
+    expected: &[u8],
+) -> bool {
+    match algorithm {
+        HmacAlgorithm::Sha256 => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("hmac accepts any key");
+            mac.update(message);
+            mac.verify_slice(expected).is_ok()
+        }
+        HmacAlgorithm::Sha1 => {
+            let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("hmac accepts any key");
+            mac.update(message);
+            mac.verify_slice(expected).is_ok()
+        }
+    }
+}
+
+fn decode_digest(value: &str, encoding: DigestEncoding) -> Result<Vec<u8>, ProviderError> {
+    match encoding {
+        DigestEncoding::Hex => hex::decode(value)
+            .map_err(|err| ProviderError::Signature(format!("malformed hex digest: {err}"))),
+        DigestEncoding::Base64 => STANDARD
+            .decode(value)
+            .map_err(|err| ProviderError::Signature(format!("malformed base64 digest: {err}"))),
+    }
+}
+
+fn required_header<'a>(
+    headers: &'a BTreeMap<String, String>,
+    name: &str,
+) -> Result<&'a str, ProviderError> {
+    header_value(headers, name)
+        .ok_or_else(|| ProviderError::Signature(format!("missing header {name}")))
+}
+
+/// Case-insensitive header lookup.
+pub(crate) fn header_value<'a>(
+    headers: &'a BTreeMap<String, String>,
+    name: &str,
+) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 2104 style vectors published on Wikipedia's HMAC article.
+    const MESSAGE: &[u8] = b"The quick brown fox jumps over the lazy dog";
+    const SHA256_HEX: &str = "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8";
+    const SHA1_BASE64: &str = "3nybhbi3iqa8ino29wqQcBydtNk=";
+
+    fn hmac_scheme(
+        algorithm: HmacAlgorithm,
+        encoding: DigestEncoding,
+        prefix: Option<&str>,
+    ) -> SignatureScheme {
+        SignatureScheme::Hmac(HmacSignature {
+            header: "X-Signature".into(),
+            algorithm,
+            encoding,
+            prefix: prefix.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn verifies_sha256_hex_with_prefix() {
+        let scheme = hmac_scheme(HmacAlgorithm::Sha256, DigestEncoding::Hex, Some("sha256="));
+        let headers = BTreeMap::from([("x-signature".into(), format!("sha256={SHA256_HEX}"))]);
+        verify_signature(&scheme, b"key", &headers, MESSAGE, Utc::now()).expect("valid signature");
+    }
+
+    #[test]
+    fn verifies_sha1_base64() {
+        let scheme = hmac_scheme(HmacAlgorithm::Sha1, DigestEncoding::Base64, None);
+        let headers = BTreeMap::from([("X-Signature".into(), SHA1_BASE64.into())]);
+        verify_signature(&scheme, b"key", &headers, MESSAGE, Utc::now()).expect("valid signature");
+    }
+
+    #[test]
+    fn rejects_tampered_body_and_wrong_secret() {
+        let scheme = hmac_scheme(HmacAlgorithm::Sha256, DigestEncoding::Hex, None);
+        let headers = BTreeMap::from([("x-signature".into(), SHA256_HEX.into())]);
+        let err = verify_signature(&scheme, b"key", &headers, b"tampered", Utc::now()).unwrap_err();
+        assert!(matches!(err, ProviderError::Signature(_)));
+        let err = verify_signature(&scheme, b"other", &headers, MESSAGE, Utc::now()).unwrap_err();
+        assert!(matches!(err, ProviderError::Signature(_)));
+    }
+
+    #[test]
+    fn rejects_missing_or_malformed_header() {
+        let scheme = hmac_scheme(HmacAlgorithm::Sha256, DigestEncoding::Hex, Some("sha256="));
+        let err =
+            verify_signature(&scheme, b"key", &BTreeMap::new(), MESSAGE, Utc::now()).unwrap_err();
+        assert!(matches!(err, ProviderError::Signature(_)));
+
+        let headers = BTreeMap::from([("x-signature".into(), SHA256_HEX.into())]);
+        let err = verify_signature(&scheme, b"key", &headers, MESSAGE, Utc::now()).unwrap_err();
+        assert!(matches!(err, ProviderError::Signature(_)));
+
+        let headers = BTreeMap::from([("x-signature".into(), "sha256=not-hex".into())]);
+        let err = verify_signature(&scheme, b"key", &headers, MESSAGE, Utc::now()).unwrap_err();
+        assert!(matches!(err, ProviderError::Signature(_)));
+    }
+
+    #[test]
+    fn signs_hmac_headers_that_verify() {
+        let cfg = HmacSignature {
+            header: "X-Signature".into(),
+            algorithm: HmacAlgorithm::Sha256,
+            encoding: DigestEncoding::Hex,
+            prefix: Some("sha256=".into()),
+        };
+        let value = sign_hmac(&cfg, b"key", MESSAGE);
+        assert_eq!(value, format!("sha256={SHA256_HEX}"));
+        let headers = BTreeMap::from([(cfg.header.clone(), value)]);
+        verify_signature(
+            &SignatureScheme::Hmac(cfg),
+            b"key",
+            &headers,
+            MESSAGE,
+            Utc::now(),
+        )
+        .expect("self-signed");
+    }
+
+    #[test]
+    fn signs_standard_webhooks_spec_vector() {
+        // Example from the Standard Webhooks specification test suite.
+        let signature = sign_standard_webhooks(
+            b"whsec_MfKQ9r8GKYqrTwjUPD8ILPZIo2LaLaSw",
+            "msg_p5jXN8AQM9LWM0D4loKWxJek",
+            1614265330,
+            br#"{"test": 2432232314}"#,
+        )
+        .expect("sign");
+        assert_eq!(signature, "v1,g0hM9SsE+OTPJTGt/tmIKtSyZlE3uFJELVlNIOLJ1OE=");
+        assert!(sign_standard_webhooks(b"whsec_!!", "id", 0, b"").is_err());
+    }
+}
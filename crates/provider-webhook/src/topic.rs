@@ -0,0 +1,137 @@
+use crate::signature::header_value;
+use provider_core::{EventTypeSource, TopicRule};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Derive the sanitized event type for a request according to the route's topic rule.
+pub fn derive_event_type(
+    rule: &TopicRule,
+    payload: &Value,
+    headers: &BTreeMap<String, String>,
+) -> String {
+    rule.sources
+        .iter()
+        .filter_map(|source| match source {
+            EventTypeSource::Pointer(pointer) => payload.pointer(pointer).and_then(scalar_text),
+            EventTypeSource::Header(name) => header_value(headers, name).map(str::to_string),
+            EventTypeSource::Constant(value) => Some(value.clone()),
+        })
+        .find_map(|raw| sanitize_event_type(&raw))
+        .or_else(|| sanitize_event_type(&rule.fallback))
+        .unwrap_or_else(|| "received".into())
+}
+
+/// Normalize a vendor event type into dot-separated topic segments.
+///
+/// Letters are lowercased, `/` and `:` become segment separators (Shopify's
+/// "orders/create" → "orders.create"), other unsupported characters become `_`,
+/// and empty segments are dropped. Returns `None` when nothing usable remains.
+pub fn sanitize_event_type(raw: &str) -> Option<String> {
+    let mapped: String = raw
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | ':' | '.' => '.',
+            c if c.is_ascii_alphanumeric() || c == '_' || c == '-' => c.to_ascii_lowercase(),
+            _ => '_',
+        })
+        .collect();
+    let joined = mapped
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join(".");
+    (!joined.is_empty()).then_some(joined)
+}
+
+/// Normalize a value into a single topic segment (no separators).
+pub fn sanitize_segment(raw: &str) -> String {
+    sanitize_event_type(raw)
+        .map(|value| value.replace('.', "_"))
+        .unwrap_or_else(|| "_".into())
+}
+
+fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(sources: Vec<EventTypeSource>) -> TopicRule {
+        TopicRule {
+            sources,
+            fallback: "unknown".into(),
+        }
+    }
+
+    #[test]
+    fn default_rule_reads_top_level_type() {
+        let headers = BTreeMap::new();
+        let rule = TopicRule::default();
+        assert_eq!(
+            derive_event_type(
+                &rule,
+                &json!({"type": "payment_intent.succeeded"}),
+                &headers
+            ),
+            "payment_intent.succeeded"
+        );
+        assert_eq!(
+            derive_event_type(&rule, &json!("text"), &headers),
+            "received"
+        );
+    }
+
+    #[test]
+    fn reads_header_then_pointer_then_fallback() {
+        let rule = rule(vec![
+            EventTypeSource::Header("X-GitHub-Event".into()),
+            EventTypeSource::Pointer("/event/type".into()),
+        ]);
+        let headers = BTreeMap::from([("x-github-event".into(), "pull_request".into())]);
+        assert_eq!(
+            derive_event_type(&rule, &json!({}), &headers),
+            "pull_request"
+        );
+        assert_eq!(
+            derive_event_type(&rule, &json!({"event": {"type": 42}}), &BTreeMap::new()),
+            "42"
+        );
+        assert_eq!(
+            derive_event_type(&rule, &json!({}), &BTreeMap::new()),
+            "unknown"
+        );
+    }
+
+    #[test]
+    fn constant_source_always_wins() {
+        let rule = rule(vec![EventTypeSource::Constant("Ping".into())]);
+        assert_eq!(
+            derive_event_type(&rule, &json!({"type": "x"}), &BTreeMap::new()),
+            "ping"
+        );
+    }
+
+    #[test]
+    fn sanitizes_vendor_event_types() {
+        assert_eq!(
+            sanitize_event_type("orders/create").as_deref(),
+            Some("orders.create")
+        );
+        assert_eq!(
+            sanitize_event_type(" App Uninstalled ").as_deref(),
+            Some("app_uninstalled")
+        );
+        assert_eq!(sanitize_event_type("a..b.").as_deref(), Some("a.b"));
+        assert_eq!(sanitize_event_type("..."), None);
+        assert_eq!(sanitize_segment("acme.corp"), "acme_corp");
+    }
+}
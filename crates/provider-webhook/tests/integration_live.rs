@@ -31,14 +31,16 @@ fn live_webhook_smoke() -> Result<(), Box<dyn Error>> {
             path: "/live".into(),
             secret_ref: None,
             topic_prefix: "webhook.live".into(),
+            ..Default::default()
         }],
     };
-    let source = WebhookSource::new(cfg);
+    let source = WebhookSource::new(cfg).expect("valid config");
     let request = InboundHttpRequest {
         method: "POST".into(),
         path: "/webhook/live".into(),
         headers: BTreeMap::from([("content-type".into(), "application/json".into())]),
         body: serde_json::json!({"type": "smoke", "ok": true}),
+        raw_body: None,
         correlation_id: Some("live-webhook-1".into()),
         signature_validated: true,
     };
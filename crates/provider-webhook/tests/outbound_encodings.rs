@@ -0,0 +1,134 @@
+use greentic_types::{EnvId, EventEnvelope, TeamId, TenantCtx, TenantId};
+use provider_core::secrets::StaticSecretProvider;
+use provider_core::{HttpEndpointConfig, WebhookRoute, new_event, set_idempotency_key};
+use provider_webhook::{
+    InboundHttpRequest, OutboundEncoding, OutboundSigning, OutboundWebhookConfig,
+    OutgoingWebhookRequest, WebhookSource, build_outgoing_request, decode_cloudevent,
+};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+fn tenant() -> TenantCtx {
+    let env = EnvId::try_from("dev").unwrap();
+    let tenant = TenantId::try_from("acme").unwrap();
+    let team = Some(TeamId::try_from("core").unwrap());
+    TenantCtx::new(env, tenant).with_team(team)
+}
+
+fn sample_event() -> EventEnvelope {
+    let mut metadata = BTreeMap::new();
+    set_idempotency_key(&mut metadata, "order-42");
+    metadata.insert("note".into(), "100% \"quoted\" ünïcode".into());
+    new_event(
+        "orders.created",
+        "com.example.orders.created.v1",
+        "orders-service",
+        tenant(),
+        Some("order/42".into()),
+        Some("corr-1".into()),
+        json!({"type": "order.created", "id": 42, "lines": [{"sku": "a", "qty": 2}]}),
+        metadata,
+    )
+}
+
+fn send(encoding: OutboundEncoding, event: &EventEnvelope) -> OutgoingWebhookRequest {
+    let config = OutboundWebhookConfig {
+        url: "https://example.test/hook".into(),
+        headers: BTreeMap::from([("x-custom".into(), "1".into())]),
+        secret_ref: None,
+        signing: OutboundSigning::default(),
+        encoding,
+    };
+    build_outgoing_request(&config, event, &StaticSecretProvider::empty())
+        .expect("build")
+        .request
+}
+
+#[test]
+fn envelope_mode_roundtrips() {
+    let event = sample_event();
+    let request = send(OutboundEncoding::Envelope, &event);
+    assert_eq!(request.headers["content-type"], "application/json");
+    let decoded: EventEnvelope = serde_json::from_value(request.body).expect("envelope");
+    assert_eq!(decoded, event);
+}
+
+#[test]
+fn cloudevents_structured_mode_roundtrips() {
+    let event = sample_event();
+    let request = send(OutboundEncoding::CloudeventsStructured, &event);
+    assert_eq!(
+        request.headers["content-type"],
+        "application/cloudevents+json"
+    );
+    assert_eq!(request.body["specversion"], "1.0");
+    assert_eq!(request.body["type"], "com.example.orders.created.v1");
+    assert_eq!(request.body["data"], event.payload);
+    let decoded = decode_cloudevent(&request.headers, &request.body).expect("cloudevent");
+    assert_eq!(decoded, event);
+}
+
+#[test]
+fn cloudevents_binary_mode_roundtrips() {
+    let event = sample_event();
+    let request = send(OutboundEncoding::CloudeventsBinary, &event);
+    assert_eq!(request.headers["content-type"], "application/json");
+    assert_eq!(request.headers["ce-id"], event.id.to_string());
+    assert_eq!(request.headers["ce-source"], "orders-service");
+    assert_eq!(request.headers["ce-subject"], "order/42");
+    assert_eq!(request.headers["x-custom"], "1");
+    assert!(
+        request
+            .headers
+            .values()
+            .all(|value| value.bytes().all(|b| b.is_ascii_graphic() && b != b'"'))
+    );
+    assert_eq!(request.body, event.payload);
+    let decoded = decode_cloudevent(&request.headers, &request.body).expect("cloudevent");
+    assert_eq!(decoded, event);
+}
+
+#[test]
+fn payload_mode_roundtrips_through_webhook_source() {
+    let event = sample_event();
+    let request = send(OutboundEncoding::Payload, &event);
+    assert_eq!(request.body, event.payload);
+
+    let source = WebhookSource::new(HttpEndpointConfig {
+        base_path: "/webhook".into(),
+        routes: vec![WebhookRoute {
+            path: "/orders".into(),
+            topic_prefix: "webhook.orders".into(),
+            ..Default::default()
+        }],
+    })
+    .expect("valid config");
+    let inbound = InboundHttpRequest {
+        method: request.method,
+        path: "/webhook/orders".into(),
+        raw_body: request.raw_body.map(String::into_bytes),
+        headers: request.headers,
+        body: serde_json::Value::Null,
+        correlation_id: event.correlation_id.clone(),
+        signature_validated: false,
+    };
+    let received = source
+        .handle_request(tenant(), inbound, &StaticSecretProvider::empty())
+        .expect("inbound")
+        .event;
+    assert_eq!(received.payload, event.payload);
+    assert_eq!(received.topic, "webhook.orders.order.created");
+    assert_eq!(received.tenant, event.tenant);
+    assert_eq!(received.correlation_id, event.correlation_id);
+}
+
+#[test]
+fn rejects_incomplete_cloudevents() {
+    let event = sample_event();
+    let mut request = send(OutboundEncoding::CloudeventsBinary, &event);
+    request.headers.remove("ce-greentictenant");
+    assert!(decode_cloudevent(&request.headers, &request.body).is_err());
+
+    let structured = json!({"specversion": "0.3", "id": "x", "source": "s", "type": "t"});
+    assert!(decode_cloudevent(&BTreeMap::new(), &structured).is_err());
+}
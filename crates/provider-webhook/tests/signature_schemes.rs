@@ -0,0 +1,116 @@
+use chrono::{DateTime, Utc};
+use provider_core::{ProviderError, SignatureScheme};
+use provider_webhook::verify_signature;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+struct SignatureFixture {
+    #[allow(dead_code)]
+    description: String,
+    scheme: SignatureScheme,
+    secret: String,
+    headers: BTreeMap<String, String>,
+    body: String,
+    now: i64,
+}
+
+fn load(name: &str) -> SignatureFixture {
+    let path = Path::new("tests/fixtures/signatures").join(format!("{name}.json"));
+    let raw = fs::read_to_string(&path).unwrap();
+    serde_json::from_str(&raw).unwrap()
+}
+
+fn at(unix: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(unix, 0).unwrap()
+}
+
+fn verify(fixture: &SignatureFixture, body: &[u8], now: i64) -> Result<(), ProviderError> {
+    verify_signature(
+        &fixture.scheme,
+        fixture.secret.as_bytes(),
+        &fixture.headers,
+        body,
+        at(now),
+    )
+    .map(|_| ())
+}
+
+fn assert_vector(name: &str) {
+    let fixture = load(name);
+    verify(&fixture, fixture.body.as_bytes(), fixture.now)
+        .unwrap_or_else(|err| panic!("{name} vector should verify: {err}"));
+
+    let mut tampered = fixture.body.clone().into_bytes();
+    tampered.push(b' ');
+    let err = verify(&fixture, &tampered, fixture.now).unwrap_err();
+    assert!(matches!(err, ProviderError::Signature(_)), "{name}: {err}");
+
+    let mut wrong_secret = load(name);
+    wrong_secret.secret.push('x');
+    let err = verify(&wrong_secret, fixture.body.as_bytes(), fixture.now).unwrap_err();
+    assert!(matches!(err, ProviderError::Signature(_)), "{name}: {err}");
+}
+
+#[test]
+fn github_vector_verifies() {
+    assert_vector("github");
+}
+
+#[test]
+fn shopify_vector_verifies() {
+    assert_vector("shopify");
+}
+
+#[test]
+fn stripe_vector_verifies_within_tolerance() {
+    assert_vector("stripe");
+
+    let fixture = load("stripe");
+    let err = verify(&fixture, fixture.body.as_bytes(), fixture.now + 600).unwrap_err();
+    assert!(matches!(err, ProviderError::Signature(_)));
+}
+
+#[test]
+fn stripe_accepts_any_matching_v1_signature() {
+    let mut fixture = load("stripe");
+    let header = fixture.headers.get_mut("Stripe-Signature").unwrap();
+    *header = header.replacen("v1=", &format!("v1={},v1=", "ab".repeat(32)), 1);
+    verify(&fixture, fixture.body.as_bytes(), fixture.now).expect("second v1 matches");
+}
+
+#[test]
+fn stripe_docs_example_header_parses() {
+    // The header shown in Stripe's "verify signatures manually" docs; its secret is not
+    // published, so this only checks that `t`, `v1` and `v0` parse and a mismatch is
+    // reported as a signature failure rather than a malformed header.
+    let mut fixture = load("stripe");
+    fixture.headers.insert(
+        "Stripe-Signature".into(),
+        "t=1492774577,\
+         v1=5257a869e7ecebeda32affa62cdca3fa51cad7e77a0e56ff536d0ce8e108d8bd,\
+         v0=6ffbb59b2300aae63f272406069a9788598b792a944a07aba816edb039989a39"
+            .into(),
+    );
+    let err = verify(&fixture, fixture.body.as_bytes(), fixture.now).unwrap_err();
+    assert!(matches!(err, ProviderError::Signature(msg) if msg.contains("no v1 signature")));
+}
+
+#[test]
+fn slack_vector_verifies_within_tolerance() {
+    assert_vector("slack");
+
+    let fixture = load("slack");
+    let err = verify(&fixture, fixture.body.as_bytes(), fixture.now - 301).unwrap_err();
+    assert!(matches!(err, ProviderError::Signature(_)));
+}
+
+#[test]
+fn scheme_tolerance_is_configurable() {
+    let mut fixture = load("slack");
+    fixture.scheme = serde_json::from_str(r#"{"scheme": "slack", "tolerance_secs": 3600}"#)
+        .expect("slack scheme");
+    verify(&fixture, fixture.body.as_bytes(), fixture.now + 1800).expect("within widened window");
+}